@@ -0,0 +1,142 @@
+use crate::settings::Settings;
+use amethyst::{
+  assets::{AssetStorage, Loader},
+  audio::{output::Output, Source, SourceHandle, WavFormat},
+  core::Time,
+  derive::SystemDesc,
+  ecs::prelude::{Read, ReadExpect, System, SystemData, Write},
+  utils::application_root_dir,
+};
+use log::warn;
+use std::collections::HashMap;
+use std::fs;
+
+///
+/// constants
+///
+
+/// Packs live in `assets/announcers/<pack>/`, every clip is a wav file named after its event.
+const ANNOUNCERS_DIR: &str = "announcers";
+const ANNOUNCER_COOLDOWN: f32 = 2.;
+const ANNOUNCER_VOLUME: f32 = 0.6;
+
+///
+/// types
+///
+
+#[allow(dead_code)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum AnnouncerEvent {
+  LevelClear,
+  /// Not raised yet, there is no multiball power-up in the game so far.
+  MultiBall,
+  /// Not raised yet, the game can't be lost so far.
+  GameOver,
+}
+
+impl AnnouncerEvent {
+  const ALL: [AnnouncerEvent; 3] = [
+    AnnouncerEvent::LevelClear,
+    AnnouncerEvent::MultiBall,
+    AnnouncerEvent::GameOver,
+  ];
+
+  fn clip(self) -> &'static str {
+    match self {
+      AnnouncerEvent::LevelClear => "level_clear.wav",
+      AnnouncerEvent::MultiBall => "multiball.wav",
+      AnnouncerEvent::GameOver => "game_over.wav",
+    }
+  }
+}
+
+/// Events the announcer should call out, `AnnouncerSystem` drains it every frame.
+#[derive(Default)]
+pub struct AnnouncerQueue(pub Vec<AnnouncerEvent>);
+
+/// Names of the installed announcer packs, sorted.
+pub fn packs() -> Vec<String> {
+  let dir = match application_root_dir() {
+    Ok(app_root) => app_root.join("assets").join(ANNOUNCERS_DIR),
+    Err(_) => return vec![],
+  };
+  let mut packs = fs::read_dir(dir)
+    .map(|entries| {
+      entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect::<Vec<_>>()
+    })
+    .unwrap_or_default();
+  packs.sort();
+  packs
+}
+
+///
+/// systems
+///
+
+/// Plays the clip of the selected pack for queued events. Runs in the base dispatcher so a level clear is still
+/// announced after the game switched to the next state. Events arriving during the cooldown are dropped so
+/// announcements don't pile up.
+#[derive(Default, SystemDesc)]
+pub struct AnnouncerSystem {
+  pack: Option<String>,
+  clips: HashMap<AnnouncerEvent, SourceHandle>,
+  cooldown: f32,
+}
+
+impl AnnouncerSystem {
+  fn load_pack(&mut self, pack: Option<&String>, loader: &Loader, storage: &AssetStorage<Source>) {
+    self.pack = pack.cloned();
+    self.clips.clear();
+    let pack = match pack {
+      Some(pack) => pack,
+      None => return,
+    };
+    let pack_dir = match application_root_dir() {
+      Ok(app_root) => app_root.join("assets").join(ANNOUNCERS_DIR).join(pack),
+      Err(_) => return,
+    };
+    if !pack_dir.is_dir() {
+      warn!("Announcer pack {} not found in assets/{}", pack, ANNOUNCERS_DIR);
+      return;
+    }
+    for &event in AnnouncerEvent::ALL.iter() {
+      if pack_dir.join(event.clip()).is_file() {
+        let path = format!("{}/{}/{}", ANNOUNCERS_DIR, pack, event.clip());
+        self.clips.insert(event, loader.load(path, WavFormat, (), storage));
+      }
+    }
+  }
+}
+
+impl<'a> System<'a> for AnnouncerSystem {
+  type SystemData = (
+    Write<'a, AnnouncerQueue>,
+    Read<'a, Settings>,
+    ReadExpect<'a, Loader>,
+    Read<'a, AssetStorage<Source>>,
+    Option<Read<'a, Output>>,
+    Read<'a, Time>,
+  );
+
+  fn run(&mut self, (mut queue, settings, loader, storage, output, time): Self::SystemData) {
+    if self.pack != settings.announcer {
+      self.load_pack(settings.announcer.as_ref(), &loader, &storage);
+    }
+    self.cooldown = (self.cooldown - time.delta_seconds()).max(0.);
+
+    for event in queue.0.drain(..) {
+      if self.cooldown > 0. {
+        continue;
+      }
+      let sound = self.clips.get(&event).and_then(|clip| storage.get(clip));
+      if let (Some(output), Some(sound)) = (output.as_ref(), sound) {
+        output.play_once(sound, ANNOUNCER_VOLUME);
+        self.cooldown = ANNOUNCER_COOLDOWN;
+      }
+    }
+  }
+}
@@ -0,0 +1,84 @@
+///
+/// constants
+///
+
+const BEEPS: u32 = 8;
+const BEEP_INTERVAL: f32 = 0.75;
+/// Seconds before the first beep, so the first tap isn't a surprise.
+const LEAD_IN: f32 = 1.5;
+/// Taps needed for a result, the others may be missed or too far off a beep to count.
+const MIN_TAPS: usize = 4;
+/// Larger offsets are more likely a distracted player than a slow setup.
+pub const MAX_LATENCY: f32 = 0.25;
+
+///
+/// types
+///
+
+/// A run of the latency calibration. The player taps along with `BEEPS` beeps and the median of how late the taps
+/// were is the latency of their setup.
+#[derive(Clone, Debug)]
+pub struct Calibration {
+  /// Seconds since the first beep, negative during the lead-in.
+  clock: f32,
+  /// Seconds each tap came after its beep, negative for early taps.
+  offsets: Vec<f32>,
+}
+
+impl Default for Calibration {
+  fn default() -> Self {
+    Calibration {
+      clock: -LEAD_IN,
+      offsets: vec![],
+    }
+  }
+}
+
+impl Calibration {
+  /// Advances the clock, returns `true` if a beep is due.
+  pub fn tick(&mut self, delta_seconds: f32) -> bool {
+    let before = self.clock;
+    self.clock += delta_seconds;
+    let beep = (self.clock / BEEP_INTERVAL).floor();
+    self.clock >= 0. && beep < BEEPS as f32 && (before < 0. || beep > (before / BEEP_INTERVAL).floor())
+  }
+
+  /// Records a tap against the nearest beep.
+  pub fn tap(&mut self) {
+    let beep = (self.clock / BEEP_INTERVAL).round();
+    if beep < 0. || beep >= BEEPS as f32 {
+      return;
+    }
+    let offset = self.clock - beep * BEEP_INTERVAL;
+    if offset.abs() < BEEP_INTERVAL / 2. {
+      self.offsets.push(offset);
+    }
+  }
+
+  /// 1 right after a beep, fading to 0 until the next one. Drives the flash shown with the beep.
+  pub fn flash(&self) -> f32 {
+    if self.clock < 0. || self.is_finished() {
+      return 0.;
+    }
+    1. - (self.clock % BEEP_INTERVAL) / BEEP_INTERVAL
+  }
+
+  /// The beeps are over and the last one had time for its tap.
+  pub fn is_finished(&self) -> bool {
+    self.clock >= BEEPS as f32 * BEEP_INTERVAL
+  }
+
+  pub fn taps(&self) -> usize {
+    self.offsets.len()
+  }
+
+  /// Seconds the input of the player lags behind, `None` with too few taps to tell.
+  pub fn latency(&self) -> Option<f32> {
+    if self.offsets.len() < MIN_TAPS {
+      return None;
+    }
+    let mut offsets = self.offsets.clone();
+    offsets.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    Some(offsets[offsets.len() / 2].max(0.).min(MAX_LATENCY))
+  }
+}
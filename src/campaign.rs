@@ -0,0 +1,292 @@
+use crate::cutscene::Cutscene;
+use crate::embedded;
+use crate::level::{parse_level, Level, LevelFileError};
+use crate::save::{self, Versioned};
+use amethyst::utils::application_root_dir;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+///
+/// constants
+///
+
+pub const CAMPAIGN_DIR: &str = "campaign";
+const MANIFEST_FILE: &str = "campaign.ron";
+const PROGRESS_PATH: &str = "config/progress.ron";
+
+///
+/// enums
+///
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum Unlock {
+  Always,
+  /// Every level of the named world has to be cleared.
+  AfterWorld(String),
+  /// At least this many campaign levels have to be cleared, in any world.
+  LevelsCleared(usize),
+}
+
+impl Default for Unlock {
+  fn default() -> Self {
+    Unlock::Always
+  }
+}
+
+///
+/// types
+///
+
+/// Colors applied while a world is played, `None` keeps the untinted sprites.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Theme {
+  pub background: Option<[f32; 3]>,
+  pub bricks: Option<[f32; 3]>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CampaignWorld {
+  pub name: String,
+  #[serde(default)]
+  pub unlock: Unlock,
+  /// Level files relative to the campaign directory, in play order.
+  pub levels: Vec<String>,
+  /// Played after every other level of the world.
+  #[serde(default)]
+  pub boss: Option<String>,
+  #[serde(default)]
+  pub theme: Theme,
+  /// Cutscene file played before the first level of the world.
+  #[serde(default)]
+  pub intro: Option<String>,
+}
+
+/// The campaign manifest, it groups level files into worlds and decides what is unlocked.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Campaign {
+  pub worlds: Vec<CampaignWorld>,
+  /// Cutscene file played after the last level of the campaign.
+  #[serde(default)]
+  pub ending: Option<String>,
+}
+
+/// Position of a level inside the campaign, `level` counts the boss level last.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CampaignLevel {
+  pub world: usize,
+  pub level: usize,
+}
+
+/// Cleared campaign level files, written whenever a campaign level starts or is cleared. New Game+ keeps its own
+/// set of cleared levels so its worlds unlock one after another again.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Progress {
+  pub cleared: HashSet<String>,
+  /// Set once the whole campaign has been cleared.
+  pub new_game_plus: bool,
+  pub cleared_plus: HashSet<String>,
+  /// The level that was played last, the campaign menu starts on it.
+  pub resume: Option<CampaignLevel>,
+  pub resume_plus: Option<CampaignLevel>,
+  /// Score and lives the run takes into its next level, `None` once the run is over.
+  pub carried: Option<CarriedRun>,
+  pub carried_plus: Option<CarriedRun>,
+  /// Names of the selected skins, see `skins::PADDLE_SKINS` and `skins::BALL_SKINS`.
+  pub paddle_skin: String,
+  pub ball_skin: String,
+}
+
+/// What a campaign run keeps from one level to the next, written whenever a level is cleared.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CarriedRun {
+  /// The level the run continues with.
+  pub at: CampaignLevel,
+  pub score: u32,
+  pub lives: Option<u32>,
+}
+
+impl CampaignWorld {
+  pub fn level_files(&self) -> impl Iterator<Item = &String> {
+    self.levels.iter().chain(self.boss.iter())
+  }
+
+  pub fn is_boss(&self, level: usize) -> bool {
+    self.boss.is_some() && level == self.levels.len()
+  }
+}
+
+impl Campaign {
+  /// Path of a campaign file relative to the application root, the bundled campaign stands in for it when it is
+  /// missing.
+  fn path(file: &str) -> String {
+    format!("{}/{}", CAMPAIGN_DIR, file)
+  }
+
+  pub fn load() -> Self {
+    embedded::read(&Campaign::path(MANIFEST_FILE))
+      .ok()
+      .and_then(|bytes| match ron::de::from_bytes(&bytes) {
+        Ok(campaign) => Some(campaign),
+        Err(e) => {
+          warn!("Couldn't parse {}/{}: {}", CAMPAIGN_DIR, MANIFEST_FILE, e);
+          None
+        }
+      })
+      .unwrap_or_default()
+  }
+
+  pub fn file(&self, at: CampaignLevel) -> Option<&String> {
+    self
+      .worlds
+      .get(at.world)
+      .and_then(|world| world.level_files().nth(at.level))
+  }
+
+  pub fn load_level(&self, at: CampaignLevel) -> Option<Result<Level, LevelFileError>> {
+    let file = self.file(at)?;
+    let bytes = embedded::read(&Campaign::path(file)).map_err(LevelFileError::from);
+    Some(bytes.and_then(|bytes| parse_level(&bytes)))
+  }
+
+  /// The intro cutscene of `world`, a broken cutscene is skipped with a warning rather than blocking the campaign.
+  pub fn load_intro(&self, world: usize) -> Option<Cutscene> {
+    Campaign::load_cutscene(self.worlds.get(world)?.intro.as_ref()?)
+  }
+
+  pub fn load_ending(&self) -> Option<Cutscene> {
+    Campaign::load_cutscene(self.ending.as_ref()?)
+  }
+
+  fn load_cutscene(file: &str) -> Option<Cutscene> {
+    match Cutscene::load(&Campaign::path(file)) {
+      Ok(cutscene) => Some(cutscene),
+      Err(e) => {
+        warn!("Couldn't load cutscene {}: {}", file, e);
+        None
+      }
+    }
+  }
+
+  /// Every level in play order.
+  pub fn levels(&self) -> impl Iterator<Item = CampaignLevel> + '_ {
+    self.worlds.iter().enumerate().flat_map(|(world_index, world)| {
+      (0..world.level_files().count()).map(move |level| CampaignLevel {
+        world: world_index,
+        level,
+      })
+    })
+  }
+
+  /// The level after `at`, which might be the first level of the next world.
+  pub fn next(&self, at: CampaignLevel) -> Option<CampaignLevel> {
+    self.levels().skip_while(|&level| level != at).nth(1)
+  }
+
+  pub fn is_world_cleared(&self, world: &CampaignWorld, cleared: &HashSet<String>) -> bool {
+    world.level_files().all(|file| cleared.contains(file))
+  }
+
+  pub fn is_cleared(&self, cleared: &HashSet<String>) -> bool {
+    self.worlds.iter().all(|world| self.is_world_cleared(world, cleared))
+  }
+
+  /// How many levels of this campaign are in `cleared`, files that are no longer part of it don't count.
+  pub fn cleared_count(&self, cleared: &HashSet<String>) -> usize {
+    self
+      .levels()
+      .filter_map(|at| self.file(at))
+      .filter(|file| cleared.contains(*file))
+      .count()
+  }
+
+  pub fn is_unlocked(&self, world: usize, cleared: &HashSet<String>) -> bool {
+    match self.worlds.get(world).map(|world| &world.unlock) {
+      Some(Unlock::Always) => true,
+      Some(Unlock::AfterWorld(name)) => self
+        .worlds
+        .iter()
+        .find(|world| &world.name == name)
+        .map_or(false, |world| self.is_world_cleared(world, cleared)),
+      Some(Unlock::LevelsCleared(count)) => self.cleared_count(cleared) >= *count,
+      None => false,
+    }
+  }
+}
+
+impl Progress {
+  pub fn path() -> Option<PathBuf> {
+    application_root_dir().ok().map(|app_root| app_root.join(PROGRESS_PATH))
+  }
+
+  pub fn load() -> Self {
+    Progress::path().and_then(|path| save::load(&path)).unwrap_or_default()
+  }
+
+  /// The cleared levels of the regular campaign or of New Game+.
+  pub fn cleared(&self, new_game_plus: bool) -> &HashSet<String> {
+    if new_game_plus {
+      &self.cleared_plus
+    } else {
+      &self.cleared
+    }
+  }
+
+  pub fn cleared_mut(&mut self, new_game_plus: bool) -> &mut HashSet<String> {
+    if new_game_plus {
+      &mut self.cleared_plus
+    } else {
+      &mut self.cleared
+    }
+  }
+
+  pub fn resume(&self, new_game_plus: bool) -> &Option<CampaignLevel> {
+    if new_game_plus {
+      &self.resume_plus
+    } else {
+      &self.resume
+    }
+  }
+
+  pub fn resume_mut(&mut self, new_game_plus: bool) -> &mut Option<CampaignLevel> {
+    if new_game_plus {
+      &mut self.resume_plus
+    } else {
+      &mut self.resume
+    }
+  }
+
+  /// The run carried over to the next level of the regular campaign or of New Game+.
+  pub fn carried(&self, new_game_plus: bool) -> Option<CarriedRun> {
+    if new_game_plus {
+      self.carried_plus
+    } else {
+      self.carried
+    }
+  }
+
+  pub fn carried_mut(&mut self, new_game_plus: bool) -> &mut Option<CarriedRun> {
+    if new_game_plus {
+      &mut self.carried_plus
+    } else {
+      &mut self.carried
+    }
+  }
+
+  pub fn save(&self) {
+    let path = match Progress::path() {
+      Some(path) => path,
+      None => return,
+    };
+    if let Err(e) = save::store(&path, self) {
+      warn!("Couldn't save {}: {}", PROGRESS_PATH, e);
+    }
+  }
+}
+
+impl Versioned for Progress {
+  const VERSION: u32 = 1;
+}
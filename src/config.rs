@@ -0,0 +1,166 @@
+use amethyst::utils::application_root_dir;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+
+///
+/// constants
+///
+
+const GAME_CONFIG_PATH: &str = "config/game.ron";
+
+///
+/// types
+///
+
+/// Gameplay tuning values, unlike `Settings` these are not changed from inside the game.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GameConfig {
+  pub paddle: PaddleConfig,
+  pub debris: DebrisConfig,
+  pub multi_ball: MultiBallConfig,
+  pub coop: CoopConfig,
+  pub materials: MaterialsConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PaddleConfig {
+  /// When `false` the paddle moves at `max_speed` as long as a direction is held.
+  pub accelerated: bool,
+  pub acceleration: f32,
+  pub max_speed: f32,
+  /// Fraction of the velocity lost per second while no direction is held.
+  pub friction: f32,
+}
+
+impl Default for PaddleConfig {
+  fn default() -> Self {
+    PaddleConfig {
+      accelerated: false,
+      acceleration: 1400.,
+      max_speed: 200.,
+      friction: 8.,
+    }
+  }
+}
+
+/// Fragments left behind by bricks the ball destroys, they are purely cosmetic.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DebrisConfig {
+  /// Fragments per destroyed brick, `0` turns debris off.
+  pub pieces: usize,
+  /// Lets falling fragments bounce off the paddle.
+  pub paddle_bounce: bool,
+}
+
+impl Default for DebrisConfig {
+  fn default() -> Self {
+    DebrisConfig {
+      pieces: 4,
+      paddle_bounce: true,
+    }
+  }
+}
+
+/// Balls split off by split bricks.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MultiBallConfig {
+  /// Balls in play at the same time, split bricks destroyed at the cap leave the ball as it is.
+  pub max_balls: usize,
+  /// Degrees both halves of a split ball turn away from its direction, one to each side.
+  pub split_angle: f32,
+}
+
+impl Default for MultiBallConfig {
+  fn default() -> Self {
+    MultiBallConfig {
+      max_balls: 3,
+      split_angle: 20.,
+    }
+  }
+}
+
+/// Rules of the co-op mode.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CoopConfig {
+  /// Every brick scores for the player whose ball broke it. Otherwise bricks only score on the lane of the player,
+  /// breaking the bricks of the other player clears them without points.
+  pub shared_bricks: bool,
+}
+
+/// What the surfaces the ball bounces off are made of.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MaterialsConfig {
+  /// The side walls and the ceiling.
+  pub walls: Material,
+  pub paddle: Material,
+  pub brick: Material,
+  pub split_brick: Material,
+  /// Armored bricks while their shield is up.
+  pub armored_brick: Material,
+  pub bumper: Material,
+}
+
+impl Default for MaterialsConfig {
+  fn default() -> Self {
+    MaterialsConfig {
+      walls: Material::default(),
+      paddle: Material {
+        spin: 0.5,
+        ..Default::default()
+      },
+      brick: Material::default(),
+      split_brick: Material::default(),
+      armored_brick: Material::default(),
+      bumper: Material {
+        restitution: 1.25,
+        ..Default::default()
+      },
+    }
+  }
+}
+
+/// How a surface changes the ball that bounces off it.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Material {
+  /// Speed of the ball after the bounce relative to before, rubbery surfaces kick it above `1` and dead ones
+  /// swallow some of it below.
+  pub restitution: f32,
+  /// Fraction of the speed along the surface the ball loses, rough surfaces send it off straighter.
+  pub damping: f32,
+  /// How much of the velocity of the surface is transferred onto the ball, only the paddle moves.
+  pub spin: f32,
+}
+
+impl Default for Material {
+  fn default() -> Self {
+    Material {
+      restitution: 1.,
+      damping: 0.,
+      spin: 0.,
+    }
+  }
+}
+
+impl GameConfig {
+  pub fn load() -> Self {
+    application_root_dir()
+      .ok()
+      .and_then(|app_root| File::open(app_root.join(GAME_CONFIG_PATH)).ok())
+      .and_then(|file| match ron::de::from_reader(file) {
+        Ok(config) => Some(config),
+        Err(e) => {
+          warn!("Couldn't parse {}, using the defaults: {}", GAME_CONFIG_PATH, e);
+          None
+        }
+      })
+      .unwrap_or_default()
+  }
+}
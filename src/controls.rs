@@ -0,0 +1,216 @@
+use crate::embedded;
+use amethyst::input::{
+  Bindings, ControllerAxis, ControllerButton, InputEvent, InputHandler, StringBindings, VirtualKeyCode,
+};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+///
+/// constants
+///
+
+const CONTROLS_DIR: &str = "config/controls";
+/// Stick movement below this is ignored, so a resting stick doesn't drift the paddle.
+const STICK_DEAD_ZONE: f32 = 0.2;
+/// Left, right and serve of the two halves of a shared keyboard.
+const KEYBOARD_LEFT_KEYS: [VirtualKeyCode; 3] = [VirtualKeyCode::A, VirtualKeyCode::D, VirtualKeyCode::W];
+const KEYBOARD_RIGHT_KEYS: [VirtualKeyCode; 3] = [VirtualKeyCode::Left, VirtualKeyCode::Right, VirtualKeyCode::Up];
+
+///
+/// enums
+///
+
+/// Preset binding profiles, each one is a bindings file in `config/controls`.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ControlScheme {
+  /// Arrows to move, shift, T and B for the actions.
+  Classic,
+  /// Everything on the arrow keys and right control.
+  Arrows,
+  Wasd,
+  /// The paddle follows the mouse, the buttons trigger the actions.
+  Mouse,
+  /// Every action within reach of the left hand, without modifier keys.
+  LeftHand,
+}
+
+impl Default for ControlScheme {
+  fn default() -> Self {
+    ControlScheme::Classic
+  }
+}
+
+impl ControlScheme {
+  const ALL: [ControlScheme; 5] = [
+    ControlScheme::Classic,
+    ControlScheme::Arrows,
+    ControlScheme::Wasd,
+    ControlScheme::Mouse,
+    ControlScheme::LeftHand,
+  ];
+
+  pub fn next(self) -> Self {
+    let index = ControlScheme::ALL
+      .iter()
+      .position(|&scheme| scheme == self)
+      .unwrap_or(0);
+    ControlScheme::ALL[(index + 1) % ControlScheme::ALL.len()]
+  }
+
+  pub fn label(self) -> &'static str {
+    match self {
+      ControlScheme::Classic => "classic",
+      ControlScheme::Arrows => "arrows only",
+      ControlScheme::Wasd => "wasd",
+      ControlScheme::Mouse => "mouse only",
+      ControlScheme::LeftHand => "left hand",
+    }
+  }
+
+  fn file(self) -> &'static str {
+    match self {
+      ControlScheme::Classic => "classic.ron",
+      ControlScheme::Arrows => "arrows.ron",
+      ControlScheme::Wasd => "wasd.ron",
+      ControlScheme::Mouse => "mouse.ron",
+      ControlScheme::LeftHand => "left_hand.ron",
+    }
+  }
+
+  pub fn follows_mouse(self) -> bool {
+    self == ControlScheme::Mouse
+  }
+
+  pub fn path(self, app_root: &Path) -> PathBuf {
+    app_root.join(self.config_path())
+  }
+
+  /// The bindings file relative to the application root.
+  fn config_path(self) -> String {
+    format!("{}/{}", CONTROLS_DIR, self.file())
+  }
+
+  /// Reads the bindings of the scheme so they can replace the bindings of the running input handler, or build it. The
+  /// embedded bindings of the scheme stand in for a missing or broken file.
+  pub fn load_bindings(self) -> Option<Bindings<StringBindings>> {
+    match embedded::load_config(&self.config_path()) {
+      Ok(bindings) => Some(bindings),
+      Err(e) => {
+        warn!(
+          "Couldn't load the {} controls from {}: {}",
+          self.label(),
+          self.config_path(),
+          e
+        );
+        None
+      }
+    }
+  }
+}
+
+/// What a player of a multiplayer mode plays with, claimed in the join lobby.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum InputDevice {
+  /// A and D to move, W to serve.
+  KeyboardLeft,
+  /// The arrow keys, up serves.
+  KeyboardRight,
+  /// The left stick moves, A serves.
+  Gamepad(u32),
+}
+
+impl InputDevice {
+  /// The keyboard half a key belongs to, if any.
+  pub fn from_key(key: VirtualKeyCode) -> Option<Self> {
+    if KEYBOARD_LEFT_KEYS.contains(&key) {
+      Some(InputDevice::KeyboardLeft)
+    } else if KEYBOARD_RIGHT_KEYS.contains(&key) {
+      Some(InputDevice::KeyboardRight)
+    } else {
+      None
+    }
+  }
+
+  pub fn label(self) -> String {
+    match self {
+      InputDevice::KeyboardLeft => "KEYBOARD LEFT".to_string(),
+      InputDevice::KeyboardRight => "KEYBOARD RIGHT".to_string(),
+      InputDevice::Gamepad(id) => format!("GAMEPAD {}", id + 1),
+    }
+  }
+
+  /// Movement from -1 to 1, like the horizontal axis of the bindings.
+  pub fn horizontal(self, input: &InputHandler<StringBindings>) -> f32 {
+    let keys = match self {
+      InputDevice::KeyboardLeft => KEYBOARD_LEFT_KEYS,
+      InputDevice::KeyboardRight => KEYBOARD_RIGHT_KEYS,
+      InputDevice::Gamepad(id) => {
+        let value = input.controller_axis_value(id, ControllerAxis::LeftX).unwrap_or(0.);
+        return if value.abs() < STICK_DEAD_ZONE { 0. } else { value };
+      }
+    };
+    let [left, right, _] = keys;
+    (input.key_is_down(right) as i32 - input.key_is_down(left) as i32) as f32
+  }
+
+  pub fn serve(self, input: &InputHandler<StringBindings>) -> bool {
+    match self {
+      InputDevice::KeyboardLeft => input.key_is_down(KEYBOARD_LEFT_KEYS[2]),
+      InputDevice::KeyboardRight => input.key_is_down(KEYBOARD_RIGHT_KEYS[2]),
+      InputDevice::Gamepad(id) => input.controller_button_is_down(id, ControllerButton::A),
+    }
+  }
+
+  /// Whether `event` is a press of the serve of this device.
+  pub fn serve_pressed(self, event: &InputEvent<StringBindings>) -> bool {
+    match (self, event) {
+      (InputDevice::KeyboardLeft, InputEvent::KeyPressed { key_code, .. }) => *key_code == KEYBOARD_LEFT_KEYS[2],
+      (InputDevice::KeyboardRight, InputEvent::KeyPressed { key_code, .. }) => *key_code == KEYBOARD_RIGHT_KEYS[2],
+      (InputDevice::Gamepad(id), InputEvent::ControllerButtonPressed { which, button }) => {
+        *which == id && *button == ControllerButton::A
+      }
+      _ => false,
+    }
+  }
+}
+
+///
+/// types
+///
+
+/// The devices claimed by the players of a multiplayer mode, by seat. Seats without a device play with the bindings
+/// of the control scheme.
+#[derive(Clone, Debug, Default)]
+pub struct InputAssignment {
+  pub devices: Vec<InputDevice>,
+}
+
+impl InputAssignment {
+  pub fn device(&self, seat: usize) -> Option<InputDevice> {
+    self.devices.get(seat).copied()
+  }
+
+  /// Movement of the player in `seat`, from their device or else from the `axis` of the bindings.
+  pub fn horizontal(&self, input: &InputHandler<StringBindings>, seat: usize, axis: &str) -> f32 {
+    self.device(seat).map_or_else(
+      || input.axis_value(axis).unwrap_or(0.),
+      |device| device.horizontal(input),
+    )
+  }
+
+  pub fn serve(&self, input: &InputHandler<StringBindings>, seat: usize, action: &str) -> bool {
+    self.device(seat).map_or_else(
+      || input.action_is_down(action).unwrap_or(false),
+      |device| device.serve(input),
+    )
+  }
+
+  /// The seat whose serve `event` presses, from their device or else from their action in `actions`.
+  pub fn serve_pressed(&self, event: &InputEvent<StringBindings>, actions: &[&str]) -> Option<usize> {
+    (0..actions.len()).find(|&seat| match self.device(seat) {
+      Some(device) => device.serve_pressed(event),
+      None => matches!(event, InputEvent::ActionPressed(action) if action == actions[seat]),
+    })
+  }
+}
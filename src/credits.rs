@@ -0,0 +1,46 @@
+use crate::embedded;
+use log::warn;
+
+///
+/// constants
+///
+
+const CREDITS_PATH: &str = "assets/credits.md";
+
+///
+/// functions
+///
+
+/// Reads the credits file, a missing file still leaves a single line so the credits screen isn't empty.
+pub fn load() -> Vec<String> {
+  let source = embedded::read(CREDITS_PATH)
+    .map_err(|e| e.to_string())
+    .and_then(|bytes| String::from_utf8(bytes).map_err(|e| e.to_string()));
+  match source {
+    Ok(source) => parse(&source),
+    Err(e) => {
+      warn!("Couldn't read {}: {}", CREDITS_PATH, e);
+      vec!["BREAKOUT".to_string()]
+    }
+  }
+}
+
+/// Turns the markdown-ish credits into display lines: `#` headings get a blank line above them, `-` list items
+/// lose their marker and everything is upper case to match the font.
+fn parse(source: &str) -> Vec<String> {
+  let mut lines = vec![];
+  for line in source.lines().map(str::trim) {
+    if line.starts_with('#') {
+      if !lines.is_empty() {
+        lines.push(String::new());
+      }
+      lines.push(line.trim_start_matches('#').trim().to_uppercase());
+      lines.push(String::new());
+    } else if line.starts_with("- ") {
+      lines.push(line[2..].to_uppercase());
+    } else {
+      lines.push(line.to_uppercase());
+    }
+  }
+  lines
+}
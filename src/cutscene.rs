@@ -0,0 +1,60 @@
+use crate::embedded;
+use serde::Deserialize;
+
+///
+/// constants
+///
+
+pub const CUTSCENE_FADE_SECONDS: f32 = 0.5;
+const DEFAULT_CARD_SECONDS: f32 = 4.;
+
+///
+/// types
+///
+
+/// A single story card, shown between a fade in and a fade out.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Card {
+  /// Texture path relative to the assets directory.
+  #[serde(default)]
+  pub image: Option<String>,
+  #[serde(default)]
+  pub text: String,
+  #[serde(default = "default_card_seconds")]
+  pub seconds: f32,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Cutscene {
+  pub cards: Vec<Card>,
+}
+
+impl Card {
+  /// Time the card is on screen including both fades.
+  pub fn total_seconds(&self) -> f32 {
+    self.seconds + 2. * CUTSCENE_FADE_SECONDS
+  }
+
+  /// Opacity of the black overlay after `elapsed` seconds, fully covering the card at both ends.
+  pub fn fade(&self, elapsed: f32) -> f32 {
+    let fade_out_start = self.total_seconds() - CUTSCENE_FADE_SECONDS;
+    if elapsed < CUTSCENE_FADE_SECONDS {
+      1. - elapsed / CUTSCENE_FADE_SECONDS
+    } else if elapsed > fade_out_start {
+      ((elapsed - fade_out_start) / CUTSCENE_FADE_SECONDS).min(1.)
+    } else {
+      0.
+    }
+  }
+}
+
+impl Cutscene {
+  /// Loads the cutscene at `path` relative to the application root, see `embedded::read`.
+  pub fn load(path: &str) -> Result<Cutscene, Box<dyn std::error::Error>> {
+    Ok(ron::de::from_bytes(&embedded::read(path)?)?)
+  }
+}
+
+fn default_card_seconds() -> f32 {
+  DEFAULT_CARD_SECONDS
+}
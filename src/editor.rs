@@ -0,0 +1,473 @@
+use crate::level::{BrickPath, BrickProperties, Difficulty, Level, PowerUp, BRICK_CHARS};
+use std::collections::VecDeque;
+
+///
+/// constants
+///
+
+pub const EDITOR_COLUMNS: usize = 13;
+pub const EDITOR_ROWS: usize = 10;
+pub const EMPTY: char = '.';
+const HISTORY_LIMIT: usize = 100;
+const MAX_HP: u32 = 9;
+const SCORE_STEP: u32 = 10;
+const MAX_PATH_CELLS: u8 = 4;
+const MAX_LINK: u8 = 9;
+
+///
+/// enums
+///
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Tool {
+  Pencil,
+  Rectangle,
+  Ellipse,
+  Fill,
+  Select,
+}
+
+impl Default for Tool {
+  fn default() -> Self {
+    Tool::Pencil
+  }
+}
+
+impl Tool {
+  pub fn next(self) -> Self {
+    match self {
+      Tool::Pencil => Tool::Rectangle,
+      Tool::Rectangle => Tool::Ellipse,
+      Tool::Ellipse => Tool::Fill,
+      Tool::Fill => Tool::Select,
+      Tool::Select => Tool::Pencil,
+    }
+  }
+
+  pub fn label(self) -> &'static str {
+    match self {
+      Tool::Pencil => "PENCIL",
+      Tool::Rectangle => "RECTANGLE",
+      Tool::Ellipse => "ELLIPSE",
+      Tool::Fill => "FILL",
+      Tool::Select => "SELECT",
+    }
+  }
+
+  /// Stamps and selections need a second corner, the first press only sets the anchor.
+  pub fn needs_anchor(self) -> bool {
+    match self {
+      Tool::Rectangle | Tool::Ellipse | Tool::Select => true,
+      Tool::Pencil | Tool::Fill => false,
+    }
+  }
+
+  /// The cells the tool covers between `from` and `to`, mirrored around the vertical center line if asked to.
+  pub fn positions(self, cells: &Cells, from: Position, to: Position, mirror: bool) -> Vec<Position> {
+    let positions = match self {
+      Tool::Pencil => vec![to],
+      Tool::Rectangle | Tool::Select => Selection::new(from, to).positions().collect(),
+      Tool::Ellipse => ellipse(from, to),
+      Tool::Fill => flood(cells, to),
+    };
+    if !mirror {
+      return positions;
+    }
+    let mirrored = positions
+      .iter()
+      .map(|&(x, y)| (cells[y].len() - 1 - x, y))
+      .collect::<Vec<_>>();
+    match self {
+      // the mirrored start may sit in a different area than the original one
+      Tool::Fill => positions.into_iter().chain(flood(cells, mirrored[0])).collect(),
+      _ => positions.into_iter().chain(mirrored).collect(),
+    }
+  }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum InspectorField {
+  Hp,
+  Score,
+  PowerUp,
+  Path,
+  Link,
+}
+
+impl Default for InspectorField {
+  fn default() -> Self {
+    InspectorField::Hp
+  }
+}
+
+impl InspectorField {
+  pub const ALL: [InspectorField; 5] = [
+    InspectorField::Hp,
+    InspectorField::Score,
+    InspectorField::PowerUp,
+    InspectorField::Path,
+    InspectorField::Link,
+  ];
+
+  pub fn next(self) -> Self {
+    let index = InspectorField::ALL.iter().position(|&field| field == self).unwrap_or(0);
+    InspectorField::ALL[(index + 1) % InspectorField::ALL.len()]
+  }
+
+  pub fn label(self, properties: &BrickProperties) -> String {
+    match self {
+      InspectorField::Hp => format!("HP {}", properties.hp),
+      InspectorField::Score => format!("SCORE {}", properties.score),
+      InspectorField::PowerUp => match properties.power_up {
+        Some(power_up) => format!("POWER-UP {}", power_up),
+        None => "POWER-UP RANDOM".to_string(),
+      },
+      InspectorField::Path => match properties.path {
+        Some(path) => format!("PATH {}", path),
+        None => "PATH NONE".to_string(),
+      },
+      InspectorField::Link => format!("LINK {}", properties.link),
+    }
+  }
+
+  /// Steps the field up or down, values are clamped and the optional fields cycle through their choices.
+  pub fn adjust(self, properties: &BrickProperties, up: bool) -> BrickProperties {
+    let mut properties = properties.clone();
+    match self {
+      InspectorField::Hp => {
+        properties.hp = if up {
+          (properties.hp + 1).min(MAX_HP)
+        } else {
+          properties.hp.saturating_sub(1).max(1)
+        }
+      }
+      InspectorField::Score => {
+        properties.score = if up {
+          properties.score + SCORE_STEP
+        } else {
+          properties.score.saturating_sub(SCORE_STEP)
+        }
+      }
+      InspectorField::PowerUp => {
+        let choices = std::iter::once(None)
+          .chain(PowerUp::ALL.iter().copied().map(Some))
+          .collect::<Vec<_>>();
+        properties.power_up = cycle(&choices, properties.power_up, up);
+      }
+      InspectorField::Path => {
+        let choices = std::iter::once(None)
+          .chain((1..=MAX_PATH_CELLS).map(|cells| Some(BrickPath::Horizontal(cells))))
+          .chain((1..=MAX_PATH_CELLS).map(|cells| Some(BrickPath::Vertical(cells))))
+          .collect::<Vec<_>>();
+        properties.path = cycle(&choices, properties.path, up);
+      }
+      InspectorField::Link => {
+        properties.link = if up {
+          (properties.link + 1).min(MAX_LINK)
+        } else {
+          properties.link.saturating_sub(1)
+        }
+      }
+    }
+    properties
+  }
+}
+
+/// A single undoable edit, the history replays it for redo and restores the overwritten cells for undo.
+#[derive(Clone, Debug)]
+pub enum Command {
+  Place {
+    positions: Vec<Position>,
+    brick: char,
+  },
+  Erase {
+    positions: Vec<Position>,
+  },
+  /// Only touches cells that already hold a brick and keeps their properties.
+  ChangeType {
+    positions: Vec<Position>,
+    brick: char,
+  },
+  MoveSelection {
+    selection: Selection,
+    offset: (isize, isize),
+  },
+  SetProperties {
+    position: Position,
+    properties: BrickProperties,
+  },
+}
+
+///
+/// types
+///
+
+pub type Cells = Vec<Vec<Cell>>;
+pub type Position = (usize, usize);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cell {
+  pub brick: char,
+  pub properties: BrickProperties,
+}
+
+/// Inclusive cell rectangle, `from` is always the top left corner.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Selection {
+  pub from: Position,
+  pub to: Position,
+}
+
+/// Executed commands together with the cells they overwrote, capped at `HISTORY_LIMIT` entries.
+#[derive(Default)]
+pub struct History {
+  undo: VecDeque<(Command, Vec<(Position, Cell)>)>,
+  redo: Vec<Command>,
+}
+
+impl Cell {
+  pub fn empty() -> Self {
+    Cell::new(EMPTY)
+  }
+
+  pub fn new(brick: char) -> Self {
+    Cell {
+      brick,
+      properties: BrickProperties::default(),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.brick == EMPTY
+  }
+}
+
+impl Selection {
+  pub fn new(a: Position, b: Position) -> Self {
+    Selection {
+      from: (a.0.min(b.0), a.1.min(b.1)),
+      to: (a.0.max(b.0), a.1.max(b.1)),
+    }
+  }
+
+  pub fn contains(&self, (x, y): Position) -> bool {
+    (self.from.0..=self.to.0).contains(&x) && (self.from.1..=self.to.1).contains(&y)
+  }
+
+  pub fn positions(&self) -> impl Iterator<Item = Position> {
+    let Selection { from, to } = *self;
+    (from.1..=to.1).flat_map(move |y| (from.0..=to.0).map(move |x| (x, y)))
+  }
+
+  /// The selection moved by `offset`, or `None` if it would leave the grid.
+  pub fn moved(&self, (dx, dy): (isize, isize)) -> Option<Selection> {
+    let shift = |(x, y): Position| -> Option<Position> {
+      let x = x as isize + dx;
+      let y = y as isize + dy;
+      if x < 0 || y < 0 || x >= EDITOR_COLUMNS as isize || y >= EDITOR_ROWS as isize {
+        None
+      } else {
+        Some((x as usize, y as usize))
+      }
+    };
+    Some(Selection {
+      from: shift(self.from)?,
+      to: shift(self.to)?,
+    })
+  }
+}
+
+impl Command {
+  /// Applies the command and returns every overwritten cell in write order.
+  fn execute(&self, cells: &mut Cells) -> Vec<(Position, Cell)> {
+    let writes: Vec<(Position, Cell)> = match self {
+      Command::Place { positions, brick } => positions
+        .iter()
+        .map(|&position| (position, Cell::new(*brick)))
+        .collect(),
+      Command::Erase { positions } => positions.iter().map(|&position| (position, Cell::empty())).collect(),
+      Command::ChangeType { positions, brick } => positions
+        .iter()
+        .filter(|&&(x, y)| !cells[y][x].is_empty())
+        .map(|&(x, y)| {
+          let cell = Cell {
+            brick: *brick,
+            ..cells[y][x].clone()
+          };
+          ((x, y), cell)
+        })
+        .collect(),
+      Command::MoveSelection { selection, offset } => match selection.moved(*offset) {
+        Some(target) => {
+          let moved = selection
+            .positions()
+            .map(|(x, y)| cells[y][x].clone())
+            .collect::<Vec<_>>();
+          selection
+            .positions()
+            .map(|position| (position, Cell::empty()))
+            .chain(target.positions().zip(moved))
+            .collect()
+        }
+        None => vec![],
+      },
+      Command::SetProperties { position, properties } => {
+        let (x, y) = *position;
+        if cells[y][x].is_empty() {
+          vec![]
+        } else {
+          let cell = Cell {
+            properties: properties.clone(),
+            ..cells[y][x].clone()
+          };
+          vec![(*position, cell)]
+        }
+      }
+    };
+    writes
+      .into_iter()
+      .map(|((x, y), cell)| ((x, y), std::mem::replace(&mut cells[y][x], cell)))
+      .collect()
+  }
+}
+
+impl History {
+  /// Runs the command and records it, commands that don't change anything are dropped.
+  pub fn execute(&mut self, command: Command, cells: &mut Cells) {
+    let before = cells.clone();
+    let previous = command.execute(cells);
+    if *cells == before {
+      return;
+    }
+    self.undo.push_back((command, previous));
+    if self.undo.len() > HISTORY_LIMIT {
+      self.undo.pop_front();
+    }
+    self.redo.clear();
+  }
+
+  pub fn undo(&mut self, cells: &mut Cells) -> bool {
+    match self.undo.pop_back() {
+      Some((command, previous)) => {
+        for ((x, y), cell) in previous.into_iter().rev() {
+          cells[y][x] = cell;
+        }
+        self.redo.push(command);
+        true
+      }
+      None => false,
+    }
+  }
+
+  pub fn redo(&mut self, cells: &mut Cells) -> bool {
+    match self.redo.pop() {
+      Some(command) => {
+        let previous = command.execute(cells);
+        self.undo.push_back((command, previous));
+        true
+      }
+      None => false,
+    }
+  }
+}
+
+///
+/// functions
+///
+
+pub fn empty_cells() -> Cells {
+  vec![vec![Cell::empty(); EDITOR_COLUMNS]; EDITOR_ROWS]
+}
+
+/// Builds a level from the cells, empty rows at the bottom are dropped and only non default properties are kept.
+pub fn level_from_cells(cells: &Cells, name: &str, author: &str) -> Level {
+  let mut rows = cells
+    .iter()
+    .map(|row| row.iter().map(|cell| cell.brick).collect::<String>())
+    .collect::<Vec<_>>();
+  while rows.len() > 1 && rows.last().map_or(false, |row| row.chars().all(|c| c == EMPTY)) {
+    rows.pop();
+  }
+  let properties = cells
+    .iter()
+    .enumerate()
+    .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, cell)| ((x, y), cell)))
+    .filter(|(_, cell)| !cell.is_empty() && cell.properties != BrickProperties::default())
+    .map(|(position, cell)| (position, cell.properties.clone()))
+    .collect();
+  Level {
+    name: name.to_string(),
+    author: author.to_string(),
+    difficulty: Difficulty::default(),
+    rows,
+    properties,
+    playfield: None,
+    movers: vec![],
+  }
+}
+
+/// Cycles through every brick kind a level may contain, skipping the empty cell.
+pub fn next_brick(brick: char) -> char {
+  let bricks = &BRICK_CHARS[1..];
+  let index = bricks
+    .iter()
+    .position(|&c| c == brick)
+    .map_or(0, |i| (i + 1) % bricks.len());
+  bricks[index]
+}
+
+/// Fills the ellipse that touches all four sides of the box spanned by `from` and `to`.
+fn ellipse(from: Position, to: Position) -> Vec<Position> {
+  let Selection { from, to } = Selection::new(from, to);
+  let center_x = (from.0 + to.0) as f32 / 2.;
+  let center_y = (from.1 + to.1) as f32 / 2.;
+  let radius_x = (to.0 - from.0) as f32 / 2. + 0.5;
+  let radius_y = (to.1 - from.1) as f32 / 2. + 0.5;
+
+  Selection { from, to }
+    .positions()
+    .filter(|&(x, y)| {
+      let dx = (x as f32 - center_x) / radius_x;
+      let dy = (y as f32 - center_y) / radius_y;
+      dx * dx + dy * dy <= 1.
+    })
+    .collect()
+}
+
+/// The four way connected area of the brick type under `start`.
+fn flood(cells: &Cells, start: Position) -> Vec<Position> {
+  let target = cells[start.1][start.0].brick;
+  let mut visited = vec![vec![false; EDITOR_COLUMNS]; EDITOR_ROWS];
+  let mut area = vec![];
+  let mut stack = vec![start];
+  while let Some((x, y)) = stack.pop() {
+    if visited[y][x] || cells[y][x].brick != target {
+      continue;
+    }
+    visited[y][x] = true;
+    area.push((x, y));
+    if x > 0 {
+      stack.push((x - 1, y));
+    }
+    if x + 1 < cells[y].len() {
+      stack.push((x + 1, y));
+    }
+    if y > 0 {
+      stack.push((x, y - 1));
+    }
+    if y + 1 < cells.len() {
+      stack.push((x, y + 1));
+    }
+  }
+  area
+}
+
+fn cycle<T: Copy + PartialEq>(choices: &[T], current: T, up: bool) -> T {
+  let index = choices.iter().position(|&choice| choice == current).unwrap_or(0);
+  let index = if up {
+    (index + 1) % choices.len()
+  } else {
+    (index + choices.len() - 1) % choices.len()
+  };
+  choices[index]
+}
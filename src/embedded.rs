@@ -0,0 +1,205 @@
+use amethyst::assets::{Directory, Source};
+use amethyst::config::{Config, ConfigError};
+use amethyst::error::Error;
+use amethyst::renderer::sprite::Sprites;
+use amethyst::ui::UiWidget;
+use amethyst::utils::application_root_dir;
+use log::warn;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+///
+/// constants
+///
+
+/// Copies of what the game needs to boot and play a level through to its end, compiled into the binary.
+const FILES: [(&str, &[u8]); 16] = [
+  ("fonts/font.ttf", include_bytes!("../assets/fonts/font.ttf")),
+  (
+    "textures/background.png",
+    include_bytes!("../assets/textures/background.png"),
+  ),
+  (
+    "textures/background.ron",
+    include_bytes!("../assets/textures/background.ron"),
+  ),
+  (
+    "textures/breakout.png",
+    include_bytes!("../assets/textures/breakout.png"),
+  ),
+  (
+    "textures/breakout.ron",
+    include_bytes!("../assets/textures/breakout.ron"),
+  ),
+  ("ui/start.ron", include_bytes!("../assets/ui/start.ron")),
+  ("ui/campaign.ron", include_bytes!("../assets/ui/campaign.ron")),
+  ("ui/modes.ron", include_bytes!("../assets/ui/modes.ron")),
+  ("ui/levels.ron", include_bytes!("../assets/ui/levels.ron")),
+  ("ui/cutscene.ron", include_bytes!("../assets/ui/cutscene.ron")),
+  ("ui/dialog.ron", include_bytes!("../assets/ui/dialog.ron")),
+  ("ui/hud.ron", include_bytes!("../assets/ui/hud.ron")),
+  ("ui/pause.ron", include_bytes!("../assets/ui/pause.ron")),
+  ("ui/tally.ron", include_bytes!("../assets/ui/tally.ron")),
+  ("ui/game_over.ron", include_bytes!("../assets/ui/game_over.ron")),
+  ("ui/victory.ron", include_bytes!("../assets/ui/victory.ron")),
+];
+/// Configs the window and the input handler can't be built without, relative to the application root.
+const CONFIGS: [(&str, &[u8]); 6] = [
+  ("config/display.ron", include_bytes!("../config/display.ron")),
+  (
+    "config/controls/classic.ron",
+    include_bytes!("../config/controls/classic.ron"),
+  ),
+  (
+    "config/controls/arrows.ron",
+    include_bytes!("../config/controls/arrows.ron"),
+  ),
+  (
+    "config/controls/wasd.ron",
+    include_bytes!("../config/controls/wasd.ron"),
+  ),
+  (
+    "config/controls/mouse.ron",
+    include_bytes!("../config/controls/mouse.ron"),
+  ),
+  (
+    "config/controls/left_hand.ron",
+    include_bytes!("../config/controls/left_hand.ron"),
+  ),
+];
+/// Stands in for every missing game sound.
+const BEEP: &[u8] = include_bytes!("../assets/sounds/paddle_hit.wav");
+
+///
+/// types
+///
+
+/// Reads from the assets directory and falls back to the embedded copies when a file is missing, can't be read or
+/// doesn't parse, so the game still boots without it. Built with the `bundled` feature, files are taken from the bundled assets first and the directory is only
+/// needed for files added after the build, like announcer packs.
+pub struct FallbackSource {
+  directory: Directory,
+}
+
+impl FallbackSource {
+  pub fn new<P: AsRef<Path>>(path: P) -> Self {
+    FallbackSource {
+      directory: Directory::new(path),
+    }
+  }
+}
+
+fn embedded(path: &str) -> Option<&'static [u8]> {
+  FILES
+    .iter()
+    .find(|(file, _)| *file == path)
+    .map(|(_, bytes)| *bytes)
+    .or_else(|| Some(BEEP).filter(|_| path.starts_with("sounds/") && path.ends_with(".wav")))
+}
+
+/// Whether `bytes` parse as the format of `path`, checked for the files with an embedded copy so a broken file is
+/// replaced before its format fails on it.
+fn parses(path: &str, bytes: &[u8]) -> bool {
+  if path.starts_with("ui/") {
+    ron::de::from_bytes::<UiWidget>(bytes).is_ok()
+  } else if path.starts_with("textures/") && path.ends_with(".ron") {
+    ron::de::from_bytes::<Sprites>(bytes).is_ok()
+  } else if path.ends_with(".png") {
+    png::Decoder::new(bytes).read_info().is_ok()
+  } else if path.ends_with(".ttf") {
+    bytes.starts_with(&[0, 1, 0, 0]) || bytes.starts_with(b"true") || bytes.starts_with(b"OTTO")
+  } else if path.ends_with(".wav") {
+    bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(&b"WAVE"[..])
+  } else {
+    true
+  }
+}
+
+impl Source for FallbackSource {
+  fn modified(&self, path: &str) -> Result<u64, Error> {
+    if bundle::get(path).is_some() {
+      return Ok(0);
+    }
+    self
+      .directory
+      .modified(path)
+      .or_else(|e| embedded(path).map(|_| 0).ok_or(e))
+  }
+
+  fn load(&self, path: &str) -> Result<Vec<u8>, Error> {
+    if let Some(bytes) = bundle::get(path) {
+      return Ok(bytes.to_vec());
+    }
+    let loaded = self.directory.load(path).and_then(|bytes| match embedded(path) {
+      Some(_) if !parses(path, &bytes) => Err(Error::from_string(format!("{} doesn't parse", path))),
+      _ => Ok(bytes),
+    });
+    loaded.or_else(|e| match embedded(path) {
+      Some(bytes) => {
+        warn!("Couldn't load {}, using the embedded copy: {}", path, e);
+        Ok(bytes.to_vec())
+      }
+      None => Err(e),
+    })
+  }
+}
+
+/// Loads the config at `path` relative to the application root, the embedded default stands in when the file is
+/// missing or broken.
+pub fn load_config<T: Config>(path: &str) -> Result<T, ConfigError> {
+  let loaded = application_root_dir()
+    .map_err(ConfigError::File)
+    .and_then(|app_root| T::load(app_root.join(path)));
+  match (loaded, CONFIGS.iter().find(|(file, _)| *file == path)) {
+    (Err(e), Some((_, bytes))) => {
+      warn!("Couldn't load {}, using the embedded default: {}", path, e);
+      T::load_bytes(bytes)
+    }
+    (loaded, _) => loaded,
+  }
+}
+
+/// Reads the file at `path` relative to the application root. Built with the `bundled` feature, the copy from the
+/// build stands in when the file is missing, like the assets in `FallbackSource`.
+pub fn read(path: &str) -> io::Result<Vec<u8>> {
+  let read = application_root_dir().and_then(|app_root| fs::read(app_root.join(path)));
+  match (read, bundle::get_root(path)) {
+    (Err(e), Some(bytes)) if e.kind() == io::ErrorKind::NotFound => Ok(bytes.to_vec()),
+    (read, _) => read,
+  }
+}
+
+#[cfg(feature = "bundled")]
+mod bundle {
+  use include_dir::{include_dir, Dir};
+
+  static ASSETS: Dir = include_dir!("assets");
+  static CAMPAIGN: Dir = include_dir!("campaign");
+
+  pub fn get(path: &str) -> Option<&'static [u8]> {
+    ASSETS.get_file(path).map(|file| file.contents())
+  }
+
+  /// Bundled files outside of the assets directory, `path` is relative to the application root.
+  pub fn get_root(path: &str) -> Option<&'static [u8]> {
+    if let Some(path) = path.strip_prefix("assets/") {
+      return get(path);
+    }
+    path
+      .strip_prefix("campaign/")
+      .and_then(|path| CAMPAIGN.get_file(path))
+      .map(|file| file.contents())
+  }
+}
+
+#[cfg(not(feature = "bundled"))]
+mod bundle {
+  pub fn get(_path: &str) -> Option<&'static [u8]> {
+    None
+  }
+
+  pub fn get_root(_path: &str) -> Option<&'static [u8]> {
+    None
+  }
+}
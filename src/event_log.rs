@@ -0,0 +1,111 @@
+use crate::narration::NarrationQueue;
+use crate::observer::{ObserverEvent, ObserverQueue};
+use crate::settings::Settings;
+use amethyst::{
+  core::Time,
+  derive::SystemDesc,
+  ecs::prelude::{Read, System, SystemData, Write, WriteStorage},
+  ui::{UiFinder, UiText},
+};
+use std::collections::VecDeque;
+
+///
+/// constants
+///
+
+/// Seconds a line stays in the log, it fades out over the last `EVENT_LOG_FADE_SECONDS`.
+const EVENT_LOG_SECONDS: f32 = 4.;
+const EVENT_LOG_FADE_SECONDS: f32 = 1.;
+/// The lines of the log in the hud, oldest first.
+const EVENT_LOG_IDS: [&str; 4] = ["event_log_0", "event_log_1", "event_log_2", "event_log_3"];
+
+///
+/// types
+///
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GameEvent {
+  /// A bomb went off, with the number of bricks it destroyed.
+  Bomb(usize),
+  /// Chain lightning arced from a brick, with the number of bricks it destroyed.
+  Lightning(usize),
+  /// A bonus brick was hit, with the name of what it awarded.
+  PowerUp(&'static str),
+  /// A player lost their last ball.
+  LifeLost,
+  /// The doors of a link opened, with the number of doors.
+  DoorsOpened(usize),
+}
+
+impl GameEvent {
+  fn message(self) -> String {
+    match self {
+      GameEvent::Bomb(bricks) => format!("BOMB x{}", bricks),
+      GameEvent::Lightning(bricks) => format!("CHAIN LIGHTNING x{}", bricks),
+      GameEvent::PowerUp(name) => format!("POWER-UP: {}", name),
+      GameEvent::LifeLost => "LIFE LOST".to_string(),
+      GameEvent::DoorsOpened(doors) => format!("DOORS OPEN x{}", doors),
+    }
+  }
+}
+
+/// Systems push the events that should show up in the event log here, `EventLogSystem` drains it every frame.
+#[derive(Default)]
+pub struct GameEvents(pub Vec<GameEvent>);
+
+///
+/// systems
+///
+
+/// Lists the latest game events in the top right corner and fades them out again, if the settings allow it. Every
+/// event is handed to the narration and the observer stream as well.
+#[derive(Default, SystemDesc)]
+pub struct EventLogSystem {
+  /// The shown messages with the seconds they have left, oldest first.
+  lines: VecDeque<(String, f32)>,
+}
+
+impl<'a> System<'a> for EventLogSystem {
+  type SystemData = (
+    UiFinder<'a>,
+    WriteStorage<'a, UiText>,
+    Write<'a, GameEvents>,
+    Write<'a, NarrationQueue>,
+    Write<'a, ObserverQueue>,
+    Read<'a, Settings>,
+    Read<'a, Time>,
+  );
+
+  fn run(&mut self, (finder, mut ui_text, mut events, mut narration, mut observer, settings, time): Self::SystemData) {
+    for (_, remaining) in self.lines.iter_mut() {
+      *remaining -= time.delta_real_seconds();
+    }
+    self.lines.retain(|(_, remaining)| *remaining > 0.);
+    for event in events.0.drain(..) {
+      narration.0.push(event.message());
+      observer.0.push(ObserverEvent::Game {
+        message: event.message(),
+      });
+      if settings.event_log {
+        self.lines.push_back((event.message(), EVENT_LOG_SECONDS));
+      }
+    }
+    if !settings.event_log {
+      self.lines.clear();
+    }
+    while self.lines.len() > EVENT_LOG_IDS.len() {
+      self.lines.pop_front();
+    }
+
+    for (row, id) in EVENT_LOG_IDS.iter().enumerate() {
+      if let Some(text) = finder.find(id).and_then(|entity| ui_text.get_mut(entity)) {
+        let (message, remaining) = self
+          .lines
+          .get(row)
+          .map_or(("", 0.), |(message, remaining)| (message.as_str(), *remaining));
+        text.text = message.to_string();
+        text.color[3] = (remaining / EVENT_LOG_FADE_SECONDS).min(1.);
+      }
+    }
+  }
+}
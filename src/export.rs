@@ -0,0 +1,152 @@
+use crate::gallery::ShotRect;
+use crate::replay::{Replay, FRAMES_PER_SECOND};
+use log::{info, warn};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+///
+/// constants
+///
+
+const EXPORTS_DIR: &str = "exports";
+/// Pixels per playfield unit, the playfield is small enough to be blurry in a video otherwise.
+const EXPORT_SCALE: f32 = 4.;
+const BACKGROUND: [u8; 4] = [0, 0, 0, 255];
+
+///
+/// types
+///
+
+/// Where the frames of an export go, a running ffmpeg or a directory of PNGs if ffmpeg couldn't be started.
+enum Sink {
+  Ffmpeg(std::process::Child),
+  Frames(PathBuf),
+}
+
+///
+/// functions
+///
+
+/// Renders every frame of the replay at `path` without a window and encodes them to `exports/<name>.mp4` through
+/// ffmpeg, or writes them as `exports/<name>/frame_<n>.png` when ffmpeg isn't installed. `rects_at` rebuilds a frame
+/// as rectangles, `width` and `height` are the size of the screen they were recorded on, the export grows to fit
+/// wider or taller playfields. Returns where the export was written.
+pub fn export_replay(
+  path: &Path,
+  app_root: &Path,
+  width: f32,
+  height: f32,
+  mut rects_at: impl FnMut(&Replay, usize) -> Vec<ShotRect>,
+) -> Result<PathBuf, String> {
+  let replay = Replay::load(path).ok_or_else(|| format!("couldn't load the replay {}", path.display()))?;
+  let name = path
+    .file_stem()
+    .map_or_else(|| "replay".to_string(), |stem| stem.to_string_lossy().into_owned());
+  let dir = app_root.join(EXPORTS_DIR);
+  fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+  let (right, top) = (0..replay.frames.len())
+    .flat_map(|frame| rects_at(&replay, frame))
+    .fold((width, height), |(right, top), rect| {
+      (right.max(rect.x + rect.width / 2.), top.max(rect.y + rect.height / 2.))
+    });
+  // Video encoders want even dimensions.
+  let pixel_width = even((right * EXPORT_SCALE).ceil() as u32);
+  let pixel_height = even((top * EXPORT_SCALE).ceil() as u32);
+
+  let video = dir.join(format!("{}.mp4", name));
+  let spawned = Command::new("ffmpeg")
+    .args(&["-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba", "-s"])
+    .arg(format!("{}x{}", pixel_width, pixel_height))
+    .arg("-r")
+    .arg(FRAMES_PER_SECOND.to_string())
+    .args(&["-i", "-", "-pix_fmt", "yuv420p"])
+    .arg(&video)
+    .stdin(Stdio::piped())
+    .spawn();
+  let mut sink = match spawned {
+    Ok(child) => Sink::Ffmpeg(child),
+    Err(e) => {
+      warn!("Couldn't start ffmpeg, writing the frames as PNGs instead: {}", e);
+      let frames = dir.join(&name);
+      fs::create_dir_all(&frames).map_err(|e| e.to_string())?;
+      Sink::Frames(frames)
+    }
+  };
+
+  for frame in 0..replay.frames.len() {
+    let pixels = rasterize(&rects_at(&replay, frame), pixel_width, pixel_height);
+    match &mut sink {
+      Sink::Ffmpeg(child) => child
+        .stdin
+        .as_mut()
+        .ok_or_else(|| "ffmpeg closed its input".to_string())?
+        .write_all(&pixels)
+        .map_err(|e| format!("ffmpeg stopped taking frames: {}", e))?,
+      Sink::Frames(frames) => write_png(
+        &frames.join(format!("frame_{:05}.png", frame)),
+        &pixels,
+        pixel_width,
+        pixel_height,
+      )?,
+    }
+  }
+
+  match sink {
+    Sink::Ffmpeg(mut child) => {
+      // Closing the input tells ffmpeg the video is complete.
+      drop(child.stdin.take());
+      let status = child.wait().map_err(|e| e.to_string())?;
+      if !status.success() {
+        return Err(format!("ffmpeg failed with {}", status));
+      }
+      info!("Exported {} frames to {}", replay.frames.len(), video.display());
+      Ok(video)
+    }
+    Sink::Frames(frames) => {
+      info!("Exported {} frames to {}", replay.frames.len(), frames.display());
+      Ok(frames)
+    }
+  }
+}
+
+fn even(pixels: u32) -> u32 {
+  (pixels + 1) / 2 * 2
+}
+
+/// Draws `rects` on an RGBA image `width` by `height` pixels, the playfield origin is the bottom left corner.
+fn rasterize(rects: &[ShotRect], width: u32, height: u32) -> Vec<u8> {
+  let mut pixels = BACKGROUND.repeat((width * height) as usize);
+  for rect in rects {
+    let left = ((rect.x - rect.width / 2.) * EXPORT_SCALE).round().max(0.) as u32;
+    let right = (((rect.x + rect.width / 2.) * EXPORT_SCALE).round().max(0.) as u32).min(width);
+    let bottom = ((rect.y - rect.height / 2.) * EXPORT_SCALE).round().max(0.) as u32;
+    let top = (((rect.y + rect.height / 2.) * EXPORT_SCALE).round().max(0.) as u32).min(height);
+    let [r, g, b, a] = rect.color;
+    let alpha = a.max(0.).min(1.);
+    for y in bottom..top {
+      let row = (height - 1 - y) * width;
+      for x in left..right {
+        let at = ((row + x) * 4) as usize;
+        for (channel, value) in [r, g, b].iter().enumerate() {
+          let under = f32::from(pixels[at + channel]) / 255.;
+          pixels[at + channel] = ((value * alpha + under * (1. - alpha)).max(0.).min(1.) * 255.).round() as u8;
+        }
+      }
+    }
+  }
+  pixels
+}
+
+fn write_png(path: &Path, pixels: &[u8], width: u32, height: u32) -> Result<(), String> {
+  let file = File::create(path).map_err(|e| e.to_string())?;
+  let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+  encoder.set_color(png::ColorType::RGBA);
+  encoder.set_depth(png::BitDepth::Eight);
+  encoder
+    .write_header()
+    .and_then(|mut writer| writer.write_image_data(pixels))
+    .map_err(|e| format!("couldn't write {}: {}", path.display(), e))
+}
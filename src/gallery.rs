@@ -0,0 +1,107 @@
+use crate::save::{self, Versioned};
+use amethyst::utils::application_root_dir;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+///
+/// constants
+///
+
+const GALLERY_DIR: &str = "gallery";
+/// The oldest highlights are deleted beyond this many.
+const GALLERY_LIMIT: usize = 50;
+
+///
+/// types
+///
+
+/// A brick, paddle or ball of a highlight, centered on `x` and `y` in playfield coordinates.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ShotRect {
+  pub x: f32,
+  pub y: f32,
+  pub width: f32,
+  pub height: f32,
+  pub color: [f32; 4],
+}
+
+/// A moment worth keeping, like a cleared level or a new best. The renderer can't read the picture back, so the
+/// playfield is kept as rectangles and drawn again by the gallery.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Highlight {
+  pub title: String,
+  pub level: String,
+  pub score: u32,
+  /// Seconds since the unix epoch.
+  pub taken_at: u64,
+  pub rects: Vec<ShotRect>,
+}
+
+impl Highlight {
+  pub fn new(title: &str, level: &str, score: u32, rects: Vec<ShotRect>) -> Self {
+    Highlight {
+      title: title.to_string(),
+      level: level.to_string(),
+      score,
+      taken_at: SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs()),
+      rects,
+    }
+  }
+
+  fn dir() -> Option<PathBuf> {
+    application_root_dir().ok().map(|app_root| app_root.join(GALLERY_DIR))
+  }
+
+  /// Every highlight in the gallery, newest first, sorted by their file names.
+  fn paths() -> Vec<PathBuf> {
+    let entries = match Highlight::dir().and_then(|dir| fs::read_dir(dir).ok()) {
+      Some(entries) => entries,
+      None => return vec![],
+    };
+    let mut paths = entries
+      .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+      .filter(|path| path.extension().map_or(false, |ext| ext == "ron"))
+      .collect::<Vec<_>>();
+    paths.sort();
+    paths.reverse();
+    paths
+  }
+
+  pub fn load_all() -> Vec<Highlight> {
+    Highlight::paths().iter().filter_map(|path| save::load(path)).collect()
+  }
+
+  /// Writes the highlight as `<taken_at>_<n>.ron` and deletes the oldest ones beyond `GALLERY_LIMIT`.
+  pub fn save(&self) {
+    let dir = match Highlight::dir() {
+      Some(dir) => dir,
+      None => return,
+    };
+    if let Err(e) = fs::create_dir_all(&dir) {
+      warn!("Couldn't create {}: {}", dir.display(), e);
+      return;
+    }
+    let path = (1..)
+      .map(|n| dir.join(format!("{:012}_{}.ron", self.taken_at, n)))
+      .find(|path| !path.exists())
+      .expect("Ran out of highlight file names!");
+    if let Err(e) = save::store(&path, self) {
+      warn!("Couldn't save highlight {}: {}", path.display(), e);
+      return;
+    }
+    for old in Highlight::paths().iter().skip(GALLERY_LIMIT) {
+      if let Err(e) = fs::remove_file(old) {
+        warn!("Couldn't remove highlight {}: {}", old.display(), e);
+      }
+    }
+  }
+}
+
+impl Versioned for Highlight {
+  const VERSION: u32 = 1;
+}
@@ -10,6 +10,7 @@ use amethyst::{
 pub struct BreakoutGameData<'a, 'b> {
   pub base: Option<Dispatcher<'a, 'b>>,
   pub running: Option<Dispatcher<'a, 'b>>,
+  pub fixed: Option<Dispatcher<'a, 'b>>,
 }
 
 impl<'a, 'b> BreakoutGameData<'a, 'b> {
@@ -24,6 +25,12 @@ impl<'a, 'b> BreakoutGameData<'a, 'b> {
     }
   }
 
+  pub fn fixed_update(&mut self, world: &World) {
+    if let Some(fixed) = &mut self.fixed {
+      fixed.dispatch(&world);
+    }
+  }
+
   pub fn dispose(&mut self, world: &mut World) {
     if let Some(base) = self.base.take() {
       base.dispose(world);
@@ -31,6 +38,9 @@ impl<'a, 'b> BreakoutGameData<'a, 'b> {
     if let Some(running) = self.running.take() {
       running.dispose(world);
     }
+    if let Some(fixed) = self.fixed.take() {
+      fixed.dispose(world);
+    }
   }
 }
 
@@ -43,6 +53,7 @@ impl DataDispose for BreakoutGameData<'_, '_> {
 pub struct BreakoutGameDataBuilder<'a, 'b> {
   base_dispatcher_operations: Vec<Box<dyn DispatcherOperation<'a, 'b>>>,
   running_dispatcher_operations: Vec<Box<dyn DispatcherOperation<'a, 'b>>>,
+  fixed_dispatcher_operations: Vec<Box<dyn DispatcherOperation<'a, 'b>>>,
 }
 
 impl<'a, 'b> Default for BreakoutGameDataBuilder<'a, 'b> {
@@ -56,6 +67,7 @@ impl<'a, 'b> BreakoutGameDataBuilder<'a, 'b> {
     BreakoutGameDataBuilder {
       base_dispatcher_operations: vec![],
       running_dispatcher_operations: vec![],
+      fixed_dispatcher_operations: vec![],
     }
   }
 
@@ -79,6 +91,28 @@ impl<'a, 'b> BreakoutGameDataBuilder<'a, 'b> {
     self
   }
 
+  pub fn with_base<SD, S>(
+    mut self,
+    system_desc: SD,
+    name: &'static str,
+    dependencies: &'static [&'static str],
+  ) -> Self
+  where
+    SD: SystemDesc<'a, 'b, S> + 'static,
+    S: for<'c> System<'c> + 'static + Send,
+  {
+    let dispatcher_operation = Box::new(AddSystem {
+      system_desc,
+      name,
+      dependencies,
+      marker: PhantomData::<S>,
+    }) as Box<dyn DispatcherOperation<'a, 'b> + 'static>;
+    self
+      .base_dispatcher_operations
+      .push(dispatcher_operation);
+    self
+  }
+
   pub fn with_running<SD, S>(
     mut self,
     system_desc: SD,
@@ -100,16 +134,40 @@ impl<'a, 'b> BreakoutGameDataBuilder<'a, 'b> {
       .push(dispatcher_operation);
     self
   }
+
+  pub fn with_fixed<SD, S>(
+    mut self,
+    system_desc: SD,
+    name: &'static str,
+    dependencies: &'static [&'static str],
+  ) -> Self
+  where
+    SD: SystemDesc<'a, 'b, S> + 'static,
+    S: for<'c> System<'c> + 'static + Send,
+  {
+    let dispatcher_operation = Box::new(AddSystem {
+      system_desc,
+      name,
+      dependencies,
+      marker: PhantomData::<S>,
+    }) as Box<dyn DispatcherOperation<'a, 'b> + 'static>;
+    self
+      .fixed_dispatcher_operations
+      .push(dispatcher_operation);
+    self
+  }
 }
 
 impl<'a, 'b> DataInit<BreakoutGameData<'a, 'b>> for BreakoutGameDataBuilder<'a, 'b> {
   fn build(self, world: &mut World) -> BreakoutGameData<'a, 'b> {
     let base = build_dispatcher(world, self.base_dispatcher_operations);
     let running = build_dispatcher(world, self.running_dispatcher_operations);
+    let fixed = build_dispatcher(world, self.fixed_dispatcher_operations);
 
     BreakoutGameData {
       base: Some(base),
       running: Some(running),
+      fixed: Some(fixed),
     }
   }
 }
@@ -0,0 +1,948 @@
+use crate::save::{self, Versioned};
+use hmac::{Hmac, Mac, NewMac};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+///
+/// constants
+///
+
+/// Version 1 codes only hold the name, the author and the bricks, the rest of the level is left at its defaults.
+const CODE_VERSION: u8 = 2;
+const MAX_COLUMNS: usize = 13;
+const MAX_ROWS: usize = 30;
+const MAX_TEXT_LEN: usize = 32;
+/// Largest playfield a level may ask for, twice the width of the screen and five times its height.
+const MAX_PLAYFIELD_WIDTH: f32 = 864.;
+const MAX_PLAYFIELD_HEIGHT: f32 = 1215.;
+const MAX_MOVERS: usize = 8;
+const RECORDS_FILE: &str = "records.ron";
+/// Only keeps people from casually editing their records, anyone reading the source can sign a file.
+const RECORDS_KEY: &[u8] = b"breakout-personal-bests";
+
+/// Every character a level row may contain, the position in this list is the value that gets packed
+/// into a level code, so new brick kinds must only ever be appended.
+pub const BRICK_CHARS: [char; 10] = [
+  '.',
+  PLAIN_BRICK,
+  SPLIT_BRICK,
+  ARMORED_BRICK,
+  BUMPER,
+  GATE_UP,
+  GATE_DOWN,
+  DOOR,
+  SWITCH_BRICK,
+  METAL_BRICK,
+];
+pub const PLAIN_BRICK: char = '#';
+/// Splits the ball that destroys it in two.
+pub const SPLIT_BRICK: char = 'S';
+/// Shielded until the breaker meter is charged by paddle hits.
+pub const ARMORED_BRICK: char = 'A';
+/// A round pinball bumper that kicks the ball away and scores on every hit. It can't be destroyed and doesn't count
+/// towards clearing the level.
+pub const BUMPER: char = 'O';
+/// One-way gates, the ball passes them going up or going down only. Like bumpers they stay for the whole level.
+pub const GATE_UP: char = '^';
+pub const GATE_DOWN: char = 'v';
+/// The ball bounces off a door until every switch brick with the same link is destroyed, then it opens. Bombs
+/// and lightning break doors like any brick.
+pub const DOOR: char = 'D';
+pub const SWITCH_BRICK: char = 'W';
+/// Can't be destroyed by anything and doesn't count towards clearing the level, for walls and channels.
+pub const METAL_BRICK: char = 'M';
+
+///
+/// types
+///
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Difficulty {
+  Easy,
+  Normal,
+  Hard,
+}
+
+impl Default for Difficulty {
+  fn default() -> Self {
+    Difficulty::Normal
+  }
+}
+
+impl fmt::Display for Difficulty {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Difficulty::Easy => write!(f, "EASY"),
+      Difficulty::Normal => write!(f, "NORMAL"),
+      Difficulty::Hard => write!(f, "HARD"),
+    }
+  }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum PowerUp {
+  WidePaddle,
+  SlowBall,
+  MultiBall,
+}
+
+impl PowerUp {
+  pub const ALL: [PowerUp; 3] = [PowerUp::WidePaddle, PowerUp::SlowBall, PowerUp::MultiBall];
+}
+
+impl fmt::Display for PowerUp {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      PowerUp::WidePaddle => write!(f, "WIDE PADDLE"),
+      PowerUp::SlowBall => write!(f, "SLOW BALL"),
+      PowerUp::MultiBall => write!(f, "MULTI BALL"),
+    }
+  }
+}
+
+/// A brick moving back and forth along one axis, the value is how many cells it travels.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum BrickPath {
+  Horizontal(u8),
+  Vertical(u8),
+}
+
+impl fmt::Display for BrickPath {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BrickPath::Horizontal(cells) => write!(f, "HORIZONTAL {}", cells),
+      BrickPath::Vertical(cells) => write!(f, "VERTICAL {}", cells),
+    }
+  }
+}
+
+/// A mini-paddle of a bonus stage, it moves back and forth along its waypoints and deflects the ball without ever
+/// being destroyed.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MoverPath {
+  /// Waypoints as fractions of the playfield width and height, measured from the bottom left corner.
+  pub points: Vec<(f32, f32)>,
+  /// Playfield units per second.
+  #[serde(default = "mover_speed")]
+  pub speed: f32,
+  #[serde(default = "mover_width")]
+  pub width: f32,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct BrickProperties {
+  pub hp: u32,
+  pub score: u32,
+  /// Drops this power-up instead of a random one.
+  pub power_up: Option<PowerUp>,
+  pub path: Option<BrickPath>,
+  /// Ties doors to the switch bricks that open them, only used by those two.
+  pub link: u8,
+}
+
+impl Default for BrickProperties {
+  fn default() -> Self {
+    BrickProperties {
+      hp: 1,
+      score: 10,
+      power_up: None,
+      path: None,
+      link: 0,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Level {
+  pub name: String,
+  pub author: String,
+  #[serde(default)]
+  pub difficulty: Difficulty,
+  pub rows: Vec<String>,
+  /// Bricks that differ from `BrickProperties::default()`, keyed by `(column, row)`.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub properties: BTreeMap<(usize, usize), BrickProperties>,
+  /// Width and height of the playfield instead of the ones of the mode. It is never smaller than the screen or
+  /// than the rows need.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub playfield: Option<(f32, f32)>,
+  /// Mini-paddles that make the level a bonus stage.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub movers: Vec<MoverPath>,
+}
+
+/// The parts of a level beyond its name, author and bricks, packed into a level code as RON.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+struct CodeExtras {
+  difficulty: Difficulty,
+  properties: BTreeMap<(usize, usize), BrickProperties>,
+  playfield: Option<(f32, f32)>,
+  movers: Vec<MoverPath>,
+}
+
+/// Best scores per level name, stored next to the custom levels. The records are signed so a hand edited file can
+/// be told apart, it is still loaded but flagged as modified.
+#[derive(Default, Deserialize, Serialize)]
+pub struct PersonalBests {
+  records: HashMap<String, u32>,
+  /// How the runs behind the leaderboard records were played, level records don't have any.
+  #[serde(default)]
+  flags: HashMap<String, RunFlags>,
+  /// Best clean run of each leaderboard, a clean run counts for `records` as well.
+  #[serde(default)]
+  clean: HashMap<String, u32>,
+  signature: String,
+  #[serde(skip)]
+  modified: bool,
+}
+
+/// How a leaderboard run was played, so clean runs can be told apart from assisted ones.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RunFlags {
+  /// The intercept marker was on or a tilt was used at some point of the run.
+  pub assisted: bool,
+  /// Any mutator was on.
+  pub mutated: bool,
+  /// The game adjusted its difficulty to the player during the run. Nothing does that yet, the flag is there so
+  /// records keep it once something does.
+  #[serde(default)]
+  pub dynamic_difficulty: bool,
+  /// A replay of the whole run was saved.
+  pub replay: bool,
+}
+
+impl RunFlags {
+  /// Runs without assists, mutators or dynamic difficulty.
+  pub fn is_clean(self) -> bool {
+    !self.assisted && !self.mutated && !self.dynamic_difficulty
+  }
+
+  /// Tags shown next to the record on the leaderboard.
+  pub fn labels(self) -> Vec<&'static str> {
+    [
+      (self.assisted, "ASSIST"),
+      (self.mutated, "MUTATORS"),
+      (self.dynamic_difficulty, "DYNAMIC"),
+      (self.replay, "REPLAY"),
+    ]
+    .iter()
+    .filter(|(set, _)| *set)
+    .map(|&(_, label)| label)
+    .collect()
+  }
+}
+
+/// The records file before it was signed.
+#[derive(Deserialize)]
+struct UnsignedPersonalBests(HashMap<String, u32>);
+
+#[derive(Debug)]
+pub enum LevelError {
+  InvalidCode,
+  UnsupportedVersion(u8),
+  Empty,
+  TooLarge { columns: usize, rows: usize },
+  RaggedRow { row: usize, expected: usize, found: usize },
+  UnknownBrick { row: usize, column: usize, brick: char },
+  TextTooLong(&'static str),
+  NoBricks,
+  PropertiesOutOfBounds { row: usize, column: usize },
+  PropertiesWithoutBrick { row: usize, column: usize },
+  PathOutOfBounds { row: usize, column: usize },
+  PlayfieldSize { width: f32, height: f32 },
+  DoorWithoutSwitch { row: usize, column: usize },
+  TooManyMovers(usize),
+  MoverPath(usize),
+}
+
+/// Why a level file couldn't be loaded, precise enough to point the author at the broken line or field.
+#[derive(Debug)]
+pub enum LevelFileError {
+  Io(io::Error),
+  Syntax {
+    line: usize,
+    column: usize,
+    message: String,
+  },
+  Field(String),
+  Invalid(LevelError),
+}
+
+impl fmt::Display for LevelError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      LevelError::InvalidCode => write!(f, "not a valid level code"),
+      LevelError::UnsupportedVersion(version) => write!(f, "level code version {} is not supported", version),
+      LevelError::Empty => write!(f, "the level has no rows"),
+      LevelError::TooLarge { columns, rows } => write!(
+        f,
+        "the level is {}x{} but at most {}x{} is supported",
+        columns, rows, MAX_COLUMNS, MAX_ROWS
+      ),
+      LevelError::RaggedRow { row, expected, found } => {
+        write!(f, "row {} has {} columns, expected {}", row + 1, found, expected)
+      }
+      LevelError::UnknownBrick { row, column, brick } => {
+        write!(f, "unknown brick '{}' in row {}, column {}", brick, row + 1, column + 1)
+      }
+      LevelError::TextTooLong(field) => write!(f, "the {} is longer than {} characters", field, MAX_TEXT_LEN),
+      LevelError::NoBricks => write!(f, "the level has no bricks"),
+      LevelError::PropertiesOutOfBounds { row, column } => {
+        write!(
+          f,
+          "properties for row {}, column {} are outside the level",
+          row + 1,
+          column + 1
+        )
+      }
+      LevelError::PropertiesWithoutBrick { row, column } => {
+        write!(f, "row {}, column {} has properties but no brick", row + 1, column + 1)
+      }
+      LevelError::DoorWithoutSwitch { row, column } => write!(
+        f,
+        "the door in row {}, column {} has no switch brick with its link",
+        row + 1,
+        column + 1
+      ),
+      LevelError::TooManyMovers(movers) => write!(f, "the level has {} movers but at most {} fit", movers, MAX_MOVERS),
+      LevelError::MoverPath(index) => write!(
+        f,
+        "mover {} needs two waypoints inside the playfield, a positive speed and width",
+        index + 1
+      ),
+      LevelError::PlayfieldSize { width, height } => write!(
+        f,
+        "the playfield is {}x{} but at most {}x{} is supported",
+        width, height, MAX_PLAYFIELD_WIDTH, MAX_PLAYFIELD_HEIGHT
+      ),
+      LevelError::PathOutOfBounds { row, column } => {
+        write!(
+          f,
+          "the brick in row {}, column {} moves outside the level",
+          row + 1,
+          column + 1
+        )
+      }
+    }
+  }
+}
+
+impl std::error::Error for LevelError {}
+
+impl fmt::Display for LevelFileError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      LevelFileError::Io(e) => write!(f, "{}", e),
+      LevelFileError::Syntax { line, column, message } => write!(f, "line {}, column {}: {}", line, column, message),
+      LevelFileError::Field(message) => write!(f, "{}", message),
+      LevelFileError::Invalid(e) => write!(f, "{}", e),
+    }
+  }
+}
+
+impl std::error::Error for LevelFileError {}
+
+impl From<io::Error> for LevelFileError {
+  fn from(e: io::Error) -> Self {
+    LevelFileError::Io(e)
+  }
+}
+
+impl From<LevelError> for LevelFileError {
+  fn from(e: LevelError) -> Self {
+    LevelFileError::Invalid(e)
+  }
+}
+
+impl From<ron::de::Error> for LevelFileError {
+  fn from(e: ron::de::Error) -> Self {
+    match &e {
+      // the message of a parser error already starts with `line:column: `
+      ron::de::Error::Parser(_, position) => {
+        let message = e.to_string();
+        let prefix = format!("{}: ", position);
+        LevelFileError::Syntax {
+          line: position.line,
+          column: position.col,
+          message: message.trim_start_matches(&prefix).to_string(),
+        }
+      }
+      ron::de::Error::IoError(message) | ron::de::Error::Message(message) => LevelFileError::Field(message.clone()),
+    }
+  }
+}
+
+impl Default for Level {
+  fn default() -> Self {
+    Level {
+      name: "Classic".to_string(),
+      author: "Breakout".to_string(),
+      difficulty: Difficulty::Easy,
+      rows: vec!["#########".to_string(), "#########".to_string()],
+      properties: BTreeMap::new(),
+      playfield: None,
+      movers: vec![],
+    }
+  }
+}
+
+impl Level {
+  pub fn columns(&self) -> usize {
+    self.rows.first().map_or(0, |row| row.chars().count())
+  }
+
+  /// Yields `(column, row, brick)` for every non empty cell, row `0` is the top row.
+  pub fn bricks(&self) -> impl Iterator<Item = (usize, usize, char)> + '_ {
+    self.rows.iter().enumerate().flat_map(|(y, row)| {
+      row
+        .chars()
+        .enumerate()
+        .filter(|&(_, brick)| brick != BRICK_CHARS[0])
+        .map(move |(x, brick)| (x, y, brick))
+    })
+  }
+
+  pub fn brick(&self, column: usize, row: usize) -> Option<char> {
+    self
+      .rows
+      .get(row)
+      .and_then(|r| r.chars().nth(column))
+      .filter(|&brick| brick != BRICK_CHARS[0])
+  }
+
+  /// The level mirrored left to right with the paths of its mini-paddles, used for the New Game+ layouts. Horizontal
+  /// paths are shortened where the mirrored brick would otherwise travel out of the playfield.
+  pub fn remixed(&self) -> Level {
+    let columns = self.columns();
+    let rows = self.rows.iter().map(|row| row.chars().rev().collect()).collect();
+    let properties = self
+      .properties
+      .iter()
+      .map(|(&(column, row), properties)| {
+        let column = columns - 1 - column.min(columns - 1);
+        let mut properties = properties.clone();
+        if let Some(BrickPath::Horizontal(cells)) = properties.path {
+          let cells = cells.min((columns - 1 - column) as u8);
+          properties.path = if cells == 0 {
+            None
+          } else {
+            Some(BrickPath::Horizontal(cells))
+          };
+        }
+        ((column, row), properties)
+      })
+      .collect();
+    let movers = self
+      .movers
+      .iter()
+      .map(|mover| MoverPath {
+        points: mover.points.iter().map(|&(x, y)| (1. - x, y)).collect(),
+        ..mover.clone()
+      })
+      .collect();
+    Level {
+      rows,
+      properties,
+      movers,
+      ..self.clone()
+    }
+  }
+
+  pub fn validate(&self) -> Result<(), LevelError> {
+    if self.rows.is_empty() {
+      return Err(LevelError::Empty);
+    }
+    let columns = self.columns();
+    if columns > MAX_COLUMNS || self.rows.len() > MAX_ROWS {
+      return Err(LevelError::TooLarge {
+        columns,
+        rows: self.rows.len(),
+      });
+    }
+    if self.name.chars().count() > MAX_TEXT_LEN {
+      return Err(LevelError::TextTooLong("name"));
+    }
+    if self.author.chars().count() > MAX_TEXT_LEN {
+      return Err(LevelError::TextTooLong("author"));
+    }
+    for (y, row) in self.rows.iter().enumerate() {
+      let found = row.chars().count();
+      if found != columns {
+        return Err(LevelError::RaggedRow {
+          row: y,
+          expected: columns,
+          found,
+        });
+      }
+      if let Some((x, brick)) = row.chars().enumerate().find(|(_, c)| !BRICK_CHARS.contains(c)) {
+        return Err(LevelError::UnknownBrick {
+          row: y,
+          column: x,
+          brick,
+        });
+      }
+    }
+    if self
+      .bricks()
+      .all(|(_, _, brick)| [BUMPER, GATE_UP, GATE_DOWN, METAL_BRICK].contains(&brick))
+    {
+      return Err(LevelError::NoBricks);
+    }
+    for (&(column, row), properties) in &self.properties {
+      if column >= columns || row >= self.rows.len() {
+        return Err(LevelError::PropertiesOutOfBounds { row, column });
+      }
+      if self.brick(column, row).is_none() {
+        return Err(LevelError::PropertiesWithoutBrick { row, column });
+      }
+      let in_bounds = match properties.path {
+        Some(BrickPath::Horizontal(cells)) => column + (cells as usize) < columns,
+        Some(BrickPath::Vertical(cells)) => row + (cells as usize) < self.rows.len(),
+        None => true,
+      };
+      if !in_bounds {
+        return Err(LevelError::PathOutOfBounds { row, column });
+      }
+    }
+    let link = |column: usize, row: usize| {
+      self
+        .properties
+        .get(&(column, row))
+        .map_or(0, |properties| properties.link)
+    };
+    for (column, row, _) in self.bricks().filter(|&(_, _, brick)| brick == DOOR) {
+      let opened = self
+        .bricks()
+        .any(|(x, y, brick)| brick == SWITCH_BRICK && link(x, y) == link(column, row));
+      if !opened {
+        return Err(LevelError::DoorWithoutSwitch { row, column });
+      }
+    }
+    if let Some((width, height)) = self.playfield {
+      let in_range = |size: f32, max: f32| size > 0. && size <= max;
+      if !in_range(width, MAX_PLAYFIELD_WIDTH) || !in_range(height, MAX_PLAYFIELD_HEIGHT) {
+        return Err(LevelError::PlayfieldSize { width, height });
+      }
+    }
+    if self.movers.len() > MAX_MOVERS {
+      return Err(LevelError::TooManyMovers(self.movers.len()));
+    }
+    let inside = |&(x, y): &(f32, f32)| (0. ..=1.).contains(&x) && (0. ..=1.).contains(&y);
+    if let Some(index) = self.movers.iter().position(|mover| {
+      mover.points.len() < 2 || !mover.points.iter().all(inside) || mover.speed <= 0. || mover.width <= 0.
+    }) {
+      return Err(LevelError::MoverPath(index));
+    }
+    Ok(())
+  }
+
+  /// Packs the level into a short url safe base64 string. Layout: version, columns, rows, name and author as
+  /// length prefixed utf8, two cells per byte and then the `CodeExtras` as RON behind a two byte length.
+  pub fn to_code(&self) -> Result<String, LevelError> {
+    self.validate()?;
+
+    let mut bytes = vec![CODE_VERSION, self.columns() as u8, self.rows.len() as u8];
+    for text in &[&self.name, &self.author] {
+      bytes.push(text.len() as u8);
+      bytes.extend_from_slice(text.as_bytes());
+    }
+    let cells = self
+      .rows
+      .iter()
+      .flat_map(|row| row.chars())
+      .map(|brick| BRICK_CHARS.iter().position(|&c| c == brick).unwrap_or(0) as u8)
+      .collect::<Vec<_>>();
+    for pair in cells.chunks(2) {
+      bytes.push(pair[0] << 4 | pair.get(1).copied().unwrap_or(0));
+    }
+    let extras = ron::ser::to_string(&CodeExtras {
+      difficulty: self.difficulty,
+      properties: self.properties.clone(),
+      playfield: self.playfield,
+      movers: self.movers.clone(),
+    })
+    .map_err(|_| LevelError::InvalidCode)?;
+    if extras.len() > u16::MAX as usize {
+      return Err(LevelError::InvalidCode);
+    }
+    bytes.extend_from_slice(&(extras.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(extras.as_bytes());
+
+    Ok(base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD))
+  }
+
+  pub fn from_code(code: &str) -> Result<Level, LevelError> {
+    let bytes = base64::decode_config(code.trim(), base64::URL_SAFE_NO_PAD).map_err(|_| LevelError::InvalidCode)?;
+    let mut bytes = bytes.iter().copied();
+    let mut next = || bytes.next().ok_or(LevelError::InvalidCode);
+
+    let version = next()?;
+    if version == 0 || version > CODE_VERSION {
+      return Err(LevelError::UnsupportedVersion(version));
+    }
+    let columns = next()? as usize;
+    let rows = next()? as usize;
+    if columns > MAX_COLUMNS || rows > MAX_ROWS {
+      return Err(LevelError::TooLarge { columns, rows });
+    }
+    let mut read_text = || -> Result<String, LevelError> {
+      let len = next()? as usize;
+      let text = (0..len).map(|_| next()).collect::<Result<Vec<_>, _>>()?;
+      String::from_utf8(text).map_err(|_| LevelError::InvalidCode)
+    };
+    let name = read_text()?;
+    let author = read_text()?;
+
+    let mut cells = Vec::with_capacity(columns * rows + 1);
+    while cells.len() < columns * rows {
+      let byte = next()?;
+      cells.push(byte >> 4);
+      cells.push(byte & 0x0f);
+    }
+    let rows = cells[..columns * rows]
+      .chunks(columns.max(1))
+      .map(|row| {
+        row
+          .iter()
+          .map(|&cell| BRICK_CHARS.get(cell as usize).copied().ok_or(LevelError::InvalidCode))
+          .collect::<Result<String, _>>()
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let extras = if version >= 2 {
+      let len = u16::from_be_bytes([next()?, next()?]) as usize;
+      let extras = (0..len).map(|_| next()).collect::<Result<Vec<_>, _>>()?;
+      ron::de::from_bytes(&extras).map_err(|_| LevelError::InvalidCode)?
+    } else {
+      CodeExtras::default()
+    };
+
+    let level = Level {
+      name,
+      author,
+      difficulty: extras.difficulty,
+      rows,
+      properties: extras.properties,
+      playfield: extras.playfield,
+      movers: extras.movers,
+    };
+    level.validate()?;
+    Ok(level)
+  }
+}
+
+fn mover_speed() -> f32 {
+  60.
+}
+
+fn mover_width() -> f32 {
+  24.
+}
+
+/// Loads and validates a single level file, this is shared by everything that plays levels from disk.
+pub fn load_level(path: &Path) -> Result<Level, LevelFileError> {
+  parse_level(&fs::read(path)?)
+}
+
+/// Reads a level from the contents of a level file.
+pub fn parse_level(bytes: &[u8]) -> Result<Level, LevelFileError> {
+  let level: Level = ron::de::from_bytes(bytes)?;
+  level.validate()?;
+  Ok(level)
+}
+
+/// Loads every `*.ron` level in `dir`, files that fail to load come back with their error so they can be reported.
+pub fn load_custom_levels(dir: &Path) -> Vec<(PathBuf, Result<Level, LevelFileError>)> {
+  let entries = match fs::read_dir(dir) {
+    Ok(entries) => entries,
+    Err(e) => {
+      warn!("Couldn't read custom levels from {}: {}", dir.display(), e);
+      return vec![];
+    }
+  };
+
+  entries
+    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    .filter(|path| path.extension().map_or(false, |ext| ext == "ron"))
+    .filter(|path| path.file_name().map_or(false, |name| name != RECORDS_FILE))
+    .map(|path| {
+      let level = load_level(&path);
+      if let Err(e) = &level {
+        warn!("Skipping level {}: {}", path.display(), e);
+      }
+      (path, level)
+    })
+    .collect()
+}
+
+impl PersonalBests {
+  pub fn path(dir: &Path) -> PathBuf {
+    dir.join(RECORDS_FILE)
+  }
+
+  pub fn load(dir: &Path) -> Self {
+    match save::load::<PersonalBests>(&PersonalBests::path(dir)) {
+      Some(mut bests) => {
+        bests.modified = bests.signature != bests.sign();
+        if bests.modified {
+          warn!("{} was modified outside of the game", RECORDS_FILE);
+        }
+        bests
+      }
+      None => PersonalBests::default(),
+    }
+  }
+
+  fn signed(records: HashMap<String, u32>) -> Self {
+    let mut bests = PersonalBests {
+      records,
+      ..Default::default()
+    };
+    bests.signature = bests.sign();
+    bests
+  }
+
+  /// HMAC-SHA256 over the records in name order, as hex. Flags and clean runs are only signed if there are any, and
+  /// dynamic difficulty only if it is set, so files from before they were recorded keep their signature.
+  fn sign(&self) -> String {
+    let mut records = self.records.iter().collect::<Vec<_>>();
+    records.sort();
+    let mut clean = self.clean.iter().collect::<Vec<_>>();
+    clean.sort();
+    let mut mac = HmacSha256::new_varkey(RECORDS_KEY).expect("HMAC accepts keys of any length");
+    for (name, score) in records {
+      let flags = self.flags.get(name).map_or_else(String::new, |flags| {
+        format!(
+          " assisted={} mutated={} replay={}{}",
+          flags.assisted,
+          flags.mutated,
+          flags.replay,
+          if flags.dynamic_difficulty {
+            " dynamic_difficulty=true"
+          } else {
+            ""
+          }
+        )
+      });
+      mac.update(format!("{}={}{}\n", name, score, flags).as_bytes());
+    }
+    for (name, score) in clean {
+      mac.update(format!("clean:{}={}\n", name, score).as_bytes());
+    }
+    mac
+      .finalize()
+      .into_bytes()
+      .iter()
+      .map(|byte| format!("{:02x}", byte))
+      .collect()
+  }
+
+  /// Set when the file didn't match its signature, it stays flagged until the high scores are reset from the save
+  /// data screen.
+  pub fn is_modified(&self) -> bool {
+    self.modified
+  }
+
+  pub fn get(&self, level: &Level) -> Option<u32> {
+    self.records.get(&level.name).copied()
+  }
+
+  /// Best score recorded under `name`, for records that don't belong to a level like the mode leaderboards.
+  pub fn best(&self, name: &str) -> Option<u32> {
+    self.records.get(name).copied()
+  }
+
+  /// Records `score` under `name` if it beats the best so far, returns whether it did.
+  pub fn submit(&mut self, name: &str, score: u32) -> bool {
+    if self.best(name).map_or(false, |best| best >= score) {
+      return false;
+    }
+    self.records.insert(name.to_string(), score);
+    if !self.modified {
+      self.signature = self.sign();
+    }
+    true
+  }
+
+  /// Records a leaderboard run with its flags, a clean run is also recorded as the best clean run. Returns whether
+  /// it beat the best of all runs.
+  pub fn submit_run(&mut self, name: &str, score: u32, flags: RunFlags) -> bool {
+    if flags.is_clean() && self.best_clean(name).map_or(true, |best| best < score) {
+      self.clean.insert(name.to_string(), score);
+    }
+    let beaten = self.best(name).map_or(true, |best| best < score);
+    if beaten {
+      self.records.insert(name.to_string(), score);
+      self.flags.insert(name.to_string(), flags);
+    }
+    if !self.modified {
+      self.signature = self.sign();
+    }
+    beaten
+  }
+
+  /// Best clean run recorded under `name`.
+  pub fn best_clean(&self, name: &str) -> Option<u32> {
+    self.clean.get(name).copied()
+  }
+
+  /// Flags of the run behind the best score under `name`, records from before flags were kept have none.
+  pub fn flags(&self, name: &str) -> RunFlags {
+    self.flags.get(name).copied().unwrap_or_default()
+  }
+
+  pub fn reset(&mut self, level: &Level) {
+    self.records.remove(&level.name);
+    if !self.modified {
+      self.signature = self.sign();
+    }
+  }
+
+  pub fn save(&self, dir: &Path) {
+    if let Err(e) = save::store(&PersonalBests::path(dir), self) {
+      warn!("Couldn't save {}: {}", RECORDS_FILE, e);
+    }
+  }
+}
+
+impl Versioned for PersonalBests {
+  const VERSION: u32 = 2;
+
+  /// Files from before signing are trusted and signed on load.
+  fn migrate(version: u32, contents: &str) -> Result<Self, String> {
+    let unsigned = match version {
+      0 => ron::de::from_str(contents).map_err(|e| e.to_string())?,
+      1 => save::data::<UnsignedPersonalBests>(contents)?,
+      _ => return Err(format!("no migration from version {}", version)),
+    };
+    Ok(PersonalBests::signed(unsigned.0))
+  }
+}
+
+/// Writes the level as `level_<n>.ron` into `dir`, using the first free number.
+pub fn save_level(dir: &Path, level: &Level) -> Result<PathBuf, Box<dyn std::error::Error>> {
+  level.validate()?;
+  fs::create_dir_all(dir)?;
+  let path = (1..)
+    .map(|n| dir.join(format!("level_{}.ron", n)))
+    .find(|path| !path.exists())
+    .expect("Ran out of level file names!");
+  fs::write(
+    &path,
+    ron::ser::to_string_pretty(level, ron::ser::PrettyConfig::default())?,
+  )?;
+  Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn level() -> Level {
+    let linked = BrickProperties {
+      link: 1,
+      ..Default::default()
+    };
+    let mut properties = BTreeMap::new();
+    properties.insert(
+      (0, 0),
+      BrickProperties {
+        hp: 3,
+        score: 40,
+        path: Some(BrickPath::Horizontal(1)),
+        ..Default::default()
+      },
+    );
+    properties.insert((1, 0), linked.clone());
+    properties.insert((1, 1), linked);
+    Level {
+      name: "ROUND TRIP".to_string(),
+      author: "TESTS".to_string(),
+      difficulty: Difficulty::Hard,
+      rows: vec!["#D.".to_string(), ".W#".to_string()],
+      properties,
+      playfield: Some((500., 300.)),
+      movers: vec![MoverPath {
+        points: vec![(0.2, 0.4), (0.8, 0.4)],
+        speed: 50.,
+        width: 20.,
+      }],
+    }
+  }
+
+  #[test]
+  fn code_round_trips_the_whole_level() {
+    let level = level();
+    let decoded = Level::from_code(&level.to_code().unwrap()).unwrap();
+    assert_eq!(decoded.name, level.name);
+    assert_eq!(decoded.author, level.author);
+    assert_eq!(decoded.difficulty, level.difficulty);
+    assert_eq!(decoded.rows, level.rows);
+    assert_eq!(decoded.properties, level.properties);
+    assert_eq!(decoded.playfield, level.playfield);
+    assert_eq!(decoded.movers, level.movers);
+  }
+
+  #[test]
+  fn version_1_codes_still_decode() {
+    let level = level();
+    let mut bytes = vec![1, 3, 2];
+    for text in &["OLD", "CODE"] {
+      bytes.push(text.len() as u8);
+      bytes.extend_from_slice(text.as_bytes());
+    }
+    let cells = level
+      .rows
+      .iter()
+      .flat_map(|row| row.chars())
+      .map(|brick| BRICK_CHARS.iter().position(|&c| c == brick).unwrap() as u8)
+      .collect::<Vec<_>>();
+    for pair in cells.chunks(2) {
+      bytes.push(pair[0] << 4 | pair.get(1).copied().unwrap_or(0));
+    }
+
+    let decoded = Level::from_code(&base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD)).unwrap();
+    assert_eq!(decoded.name, "OLD");
+    assert_eq!(decoded.rows, level.rows);
+    assert_eq!(decoded.difficulty, Difficulty::default());
+    assert!(decoded.properties.is_empty() && decoded.playfield.is_none() && decoded.movers.is_empty());
+  }
+
+  #[test]
+  fn newer_code_versions_are_rejected() {
+    let code = base64::encode_config(&[CODE_VERSION + 1, 1, 1], base64::URL_SAFE_NO_PAD);
+    assert!(matches!(
+      Level::from_code(&code),
+      Err(LevelError::UnsupportedVersion(version)) if version == CODE_VERSION + 1
+    ));
+  }
+
+  #[test]
+  fn edited_records_no_longer_match_their_signature() {
+    let mut bests = PersonalBests::default();
+    bests.submit("LEVEL", 100);
+    assert_eq!(bests.signature, bests.sign());
+    bests.records.insert("LEVEL".to_string(), 9999);
+    assert_ne!(bests.signature, bests.sign());
+  }
+
+  #[test]
+  fn unsigned_records_are_migrated_and_signed() {
+    let bests = PersonalBests::migrate(0, r#"({"LEVEL": 100})"#).unwrap();
+    assert_eq!(bests.best("LEVEL"), Some(100));
+    assert_eq!(bests.signature, bests.sign());
+
+    let bests = PersonalBests::migrate(1, r#"(version: 1, data: ({"LEVEL": 200}))"#).unwrap();
+    assert_eq!(bests.best("LEVEL"), Some(200));
+    assert_eq!(bests.signature, bests.sign());
+  }
+}
@@ -1,32 +1,111 @@
+mod announcer;
+mod calibration;
+mod campaign;
+mod config;
+mod controls;
+mod credits;
+mod cutscene;
+mod editor;
+mod embedded;
+mod event_log;
+mod export;
+mod gallery;
 mod game_data;
+mod level;
+mod modes;
+mod narration;
+mod observer;
+mod physics;
+mod radial;
+mod replay;
+mod report;
+mod rumble;
+mod save;
+mod settings;
+mod skins;
+mod snapshot;
+mod sounds;
+mod sync;
+mod thumbnail;
+mod tournament;
+mod updates;
 
+use crate::announcer::{AnnouncerEvent, AnnouncerQueue, AnnouncerSystem};
+use crate::calibration::{Calibration, MAX_LATENCY};
+use crate::campaign::{Campaign, CampaignLevel, CarriedRun, Progress};
+use crate::config::{GameConfig, Material};
+use crate::controls::{InputAssignment, InputDevice};
+use crate::cutscene::Card;
+use crate::editor::{Cells, Command, History, InspectorField, Position, Selection, Tool, EDITOR_COLUMNS, EDITOR_ROWS};
+use crate::embedded::{self, FallbackSource};
+use crate::event_log::{EventLogSystem, GameEvent, GameEvents};
+use crate::gallery::{Highlight, ShotRect};
 use crate::game_data::{BreakoutGameData, BreakoutGameDataBuilder};
+use crate::level::{
+  load_custom_levels, save_level, BrickProperties, Level, MoverPath, PersonalBests, RunFlags, ARMORED_BRICK, BUMPER,
+  DOOR, GATE_DOWN, GATE_UP, METAL_BRICK, PLAIN_BRICK, SPLIT_BRICK, SWITCH_BRICK,
+};
+use crate::modes::{self, Mutators, PlayMode};
+use crate::narration::{NarrationQueue, NarrationSystem};
+use crate::observer::{ObserverEvent, ObserverQueue, ObserverSystem};
+use crate::physics::{
+  arc_placement, bounce, box_contact, circles_overlap, collide_circles, paddle_deflection, point_in_rect,
+  predict_intercept, reflect_off_corner, set_velocity, track_half_length, BALL_MAX_VELOCITY,
+};
+use crate::replay::{FrameCapture, Replay, ReplayRecorder};
+use crate::report::Diagnostics;
+use crate::rumble::{RumbleEvent, RumbleQueue, RumbleSystem};
+use crate::settings::Settings;
+use crate::skins::Skin;
+use crate::snapshot::{SnapshotPlugin, Suspended, WorldSnapshot};
+use crate::sounds::{SoundEvents, SoundType};
+use crate::tournament::{Tournament, MAX_NAME_LENGTH, MAX_PLAYERS, MIN_PLAYERS};
+use crate::updates::UpdateNotice;
 use amethyst::assets::{AssetStorage, Loader, ProgressCounter};
 use amethyst::audio::output::Output;
-use amethyst::audio::{AudioBundle, Source, SourceHandle, WavFormat};
+use amethyst::audio::{AudioBundle, AudioSink, Source, SourceHandle, WavFormat};
 use amethyst::core::ecs::Entities;
+use amethyst::core::frame_limiter::{FrameLimiter, FrameRateLimitStrategy};
+use amethyst::core::HiddenPropagate;
 use amethyst::input::{
-  is_close_requested, is_key_down, InputBundle, InputEvent, InputHandler, StringBindings, VirtualKeyCode,
+  is_close_requested, InputBundle, InputEvent, InputHandler, ScrollDirection, StringBindings, VirtualKeyCode,
 };
+use amethyst::renderer::debug_drawing::{DebugLines, DebugLinesParams};
+use amethyst::renderer::palette::Srgba;
+use amethyst::renderer::resources::Tint;
 use amethyst::renderer::sprite::SpriteSheetHandle;
+use amethyst::renderer::transparent::Transparent;
 use amethyst::renderer::types::DefaultBackend;
 use amethyst::renderer::{
-  Camera, ImageFormat, RenderFlat2D, RenderToWindow, RenderingBundle, SpriteRender, SpriteSheet, SpriteSheetFormat,
-  Texture,
+  Camera, ImageFormat, RenderDebugLines, RenderFlat2D, RenderToWindow, RenderingBundle, SpriteRender, SpriteSheet,
+  SpriteSheetFormat, Texture,
 };
+use amethyst::shrev::{EventChannel, ReaderId};
 use amethyst::utils::application_root_dir;
+use amethyst::window::{DisplayConfig, ScreenDimensions};
+use amethyst::winit::{Event, MouseButton, Touch, TouchPhase, WindowEvent};
 use amethyst::{
-  core::{math::Vector3, Time, Transform, TransformBundle},
+  core::{
+    math::{Point3, Vector3},
+    Time, Transform, TransformBundle,
+  },
   derive::SystemDesc,
   ecs::prelude::{
-    Builder, DenseVecStorage, Entity, Join, NullStorage, Read, ReadStorage, ResourceId, System, SystemData, World,
-    WorldExt, WriteStorage,
+    Builder, DenseVecStorage, Entity, Join, NullStorage, Read, ReadExpect, ReadStorage, ResourceId, System, SystemData,
+    World, WorldExt, Write, WriteExpect, WriteStorage,
   },
   ecs::Component,
-  ui::{RenderUi, UiBundle, UiCreator, UiFinder, UiText},
+  ui::{Anchor, RenderUi, UiBundle, UiCreator, UiFinder, UiImage, UiText, UiTransform},
 };
-use amethyst::{Application, State, StateData, StateEvent, Trans};
-use std::collections::HashMap;
+use amethyst::{Application, LoggerConfig, State, StateData, StateEvent, Trans};
+use clipboard::{ClipboardContext, ClipboardProvider};
+use log::{error, info, warn};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 ///
 /// constants
@@ -35,6 +114,155 @@ use std::collections::HashMap;
 const VIRTUAL_WIDTH: f32 = 432.;
 const VIRTUAL_HEIGHT: f32 = 243.;
 const BALL_VELOCITY: f32 = 140.;
+const BALL_SPEED_UP_PER_BRICK: f32 = 4.;
+/// Bricks the ball ran into less than this far apart along its path are hit at the same moment, like two bricks of a
+/// row hit right on their seam.
+const SIMULTANEOUS_HIT_DEPTH: f32 = 0.5;
+/// Bricks that take more than one hit are this much darker after each hit they survive.
+const BRICK_DAMAGE_DIM: f32 = 0.7;
+const BALL_HOT_COLOR: [f32; 3] = [1., 0.3, 0.15];
+const SPLIT_BRICK_COLOR: [f32; 3] = [0.5, 1., 0.6];
+const ARMORED_BRICK_COLOR: [f32; 3] = [0.45, 0.5, 0.6];
+const ARMORED_OPEN_COLOR: [f32; 3] = [1., 0.5, 0.4];
+const BUMPER_COLOR: [f32; 3] = [1., 0.4, 0.8];
+const BUMPER_FLASH_COLOR: [f32; 3] = [1., 1., 1.];
+const BUMPER_FLASH_SECONDS: f32 = 0.25;
+/// How much larger a bumper is right after a hit, it shrinks back over the flash.
+const BUMPER_FLASH_GROWTH: f32 = 0.3;
+const GATE_COLOR: [f32; 3] = [0.3, 0.8, 1.];
+const DOOR_COLOR: [f32; 3] = [0.6, 0.4, 0.25];
+const SWITCH_BRICK_COLOR: [f32; 3] = [1., 0.6, 0.1];
+const METAL_BRICK_COLOR: [f32; 3] = [0.75, 0.78, 0.82];
+const MOVER_COLOR: [f32; 3] = [0.6, 1., 1.];
+/// Paddle hits that charge the breaker and drop the shields of armored bricks.
+const BREAKER_HITS: u32 = 5;
+/// Seconds armored bricks stay vulnerable once the breaker is charged.
+const BREAKER_WINDOW_SECONDS: f32 = 8.;
+const BONUS_BRICK_COLOR: [f32; 3] = [1., 0.85, 0.2];
+/// Seconds between a bonus brick expiring or being hit and the next one, picked at random from this range.
+const BONUS_SPAWN_SECONDS: (f32, f32) = (15., 30.);
+const BONUS_LIFETIME: f32 = 6.;
+/// Blinks per second of a fresh bonus brick and of one about to expire.
+const BONUS_BLINK_RATE: (f32, f32) = (1.5, 8.);
+const BONUS_FLASH_SECONDS: f32 = 1.5;
+const BONUS_POINTS: u32 = 50;
+/// The mode timer turns red for the last seconds of a timed run.
+const MODE_CLOCK_WARNING_SECONDS: f32 = 10.;
+const AMBIENT_VOLUME: f32 = 0.1;
+/// HUD labels tinted by `PlayMode::hud_tint`.
+const HUD_TINTED_IDS: [&str; 5] = ["dash", "tilt", "energy", "score", "time"];
+/// Share of bonus bricks that charge chain lightning instead of the common rewards.
+const LIGHTNING_CHANCE: f32 = 0.2;
+/// Bricks the lightning jumps to from the one the ball hit.
+const LIGHTNING_TARGETS: usize = 2;
+const LIGHTNING_ARC_SECONDS: f32 = 0.3;
+const LIGHTNING_SEGMENTS: usize = 8;
+/// How far the corners of an arc stray sideways from the straight line.
+const LIGHTNING_JITTER: f32 = 5.;
+const LIGHTNING_COLOR: [f32; 3] = [0.7, 0.85, 1.];
+/// Every this many waves of an endless mode is a bonus round with a rotating brick field.
+const BONUS_ROUND_INTERVAL: u32 = 4;
+/// Largest angle in radians the brick field of a bonus round turns to either side.
+const BONUS_ROUND_SWING: f32 = 0.35;
+/// Seconds for the brick field to swing to both sides and back.
+const BONUS_ROUND_PERIOD: f32 = 12.;
+const TEXT_COLOR: [f32; 4] = [1., 1., 1., 1.];
+/// Level thumbnails are drawn at this many times their size.
+const THUMBNAIL_SCALE: f32 = 2.;
+/// Seconds a replay jumps when scrubbing.
+const REPLAY_SEEK_SECONDS: f32 = 5.;
+const TEXT_SELECTED_COLOR: [f32; 4] = [0.4, 1., 1., 1.];
+const TEXT_ERROR_COLOR: [f32; 4] = [1., 0.4, 0.4, 1.];
+const CUSTOM_LEVELS_DIR: &str = "custom_levels";
+const LEVEL_BROWSER_ROWS: usize = 8;
+const TOUCH_TAP_DISTANCE: f32 = 8.;
+const INPUT_BUFFER_SECONDS: f32 = 0.1;
+const RESUME_COUNTDOWN_SECONDS: f32 = 3.;
+/// Seconds without input before a player of a multiplayer mode is warned that the game will pause.
+const AWAY_WARNING_SECONDS: f32 = 20.;
+/// Seconds without input before the game pauses for an away player.
+const AWAY_PAUSE_SECONDS: f32 = 30.;
+const MENU_REPEAT_DELAY: f32 = 0.4;
+const MENU_REPEAT_RATE: f32 = 0.08;
+const HOLD_TO_CONFIRM_SECONDS: f32 = 1.;
+const HOLD_TO_CONFIRM_STEPS: usize = 10;
+const DASH_SPEED: f32 = 650.;
+const DASH_DURATION: f32 = 0.12;
+const DASH_COOLDOWN: f32 = 1.5;
+const DASH_DOUBLE_TAP_WINDOW: f32 = 0.25;
+const DASH_GHOST_LIFETIME: f32 = 0.2;
+const HUD_DIM_COLOR: [f32; 4] = [0.4, 0.4, 0.4, 1.];
+const TILT_USES: u32 = 3;
+const TILT_ANGLE: f32 = 0.25;
+const TILT_PENALTY: u32 = 50;
+const ENERGY_PER_COMBO_HIT: f32 = 0.05;
+/// A paddle hit this close to the paddle edge counts as a close call and charges the energy meter.
+const CLOSE_CALL_DISTANCE: f32 = 3.;
+const CLOSE_CALL_ENERGY: f32 = 0.15;
+const CLOSE_CALL_SECONDS: f32 = 1.;
+const CLUTCH_CLOSE_CALLS: u32 = 3;
+const BOMB_GHOST_LIFETIME: f32 = 0.6;
+const BOMB_ROW_TOLERANCE: f32 = 1.;
+const DEBRIS_SCALE: f32 = 0.3;
+/// Light sprites are copies of their source scaled up by this much, the alpha grows with the ball speed.
+const GLOW_SCALE: f32 = 3.;
+const GLOW_MIN_ALPHA: f32 = 0.08;
+const GLOW_MAX_ALPHA: f32 = 0.3;
+const DEBRIS_LIFETIME: f32 = 1.2;
+const DEBRIS_GRAVITY: f32 = 300.;
+/// Share of the vertical speed a fragment keeps when it bounces off the paddle.
+const DEBRIS_BOUNCE: f32 = 0.6;
+/// Fragments tested against the paddle per frame, the rest just fall through.
+const DEBRIS_PADDLE_CHECKS: usize = 32;
+const INTERCEPT_MARKER_ALPHA: f32 = 0.35;
+const PADDLE_Y: f32 = 16.;
+/// Center of the brick rings of the radial mode, its paddle arc runs around it through `PADDLE_Y`.
+const RADIAL_CENTER: (f32, f32) = (VIRTUAL_WIDTH / 2., 140.);
+const RADIAL_PADDLE_RADIUS: f32 = RADIAL_CENTER.1 - PADDLE_Y;
+/// Input of each seat, the second player of co-op only moves and serves.
+const HORIZONTAL_AXES: [&str; 2] = ["horizontal", "horizontal_2"];
+const SERVE_ACTIONS: [&str; 2] = ["serve", "serve_2"];
+/// Serves at the last serve angle of the level without winding up, only for the first seat.
+const QUICK_SERVE_ACTION: &str = "quick_serve";
+/// Largest angle in radians from straight up a serve can be aimed at, the aim sweeps between both sides while the
+/// serve is held, once every `SERVE_AIM_PERIOD` seconds.
+const SERVE_AIM_SWING: f32 = 0.6;
+const SERVE_AIM_PERIOD: f32 = 1.6;
+/// Seconds the serve is held until it launches the ball with the full `SERVE_SPEED_BONUS`.
+const SERVE_CHARGE_SECONDS: f32 = 1.2;
+const SERVE_SPEED_BONUS: f32 = 0.4;
+const SERVE_AIM_DOTS: usize = 5;
+const SERVE_AIM_SPACING: f32 = 10.;
+const SERVE_AIM_SCALE: f32 = 0.5;
+/// Opacity of the aim dots with no charge and with a full charge.
+const SERVE_AIM_ALPHA: (f32, f32) = (0.2, 0.8);
+/// Paddle and ball color of the second player of co-op.
+const SECOND_PLAYER_COLOR: [f32; 3] = [1., 0.6, 0.3];
+const CAMERA_FOLLOW_SPEED: f32 = 4.;
+const BRICK_TOP_OFFSET: f32 = 20.5;
+const CREDITS_SCROLL_SPEED: f32 = 60.;
+const CREDITS_LINE_HEIGHT: f32 = 48.;
+const CREDITS_MIN_SPEED: f32 = 0.25;
+const CREDITS_MAX_SPEED: f32 = 8.;
+const NEW_GAME_PLUS_BALL_SPEED: f32 = 1.3;
+/// Lives New Game+ takes away from the mode, it always leaves at least one.
+const NEW_GAME_PLUS_LIFE_PENALTY: u32 = 1;
+const CAPTION_SECONDS: f32 = 1.;
+const CAPTION_IDS: [&str; 3] = ["caption_left", "caption_center", "caption_right"];
+/// Real seconds the game runs at `BALL_LOST_TIME_SCALE` after the ball fell out, the ball respawns after
+/// `BALL_LOST_SECONDS`.
+const BALL_LOST_SLOW_MOTION_SECONDS: f32 = 0.8;
+const BALL_LOST_SECONDS: f32 = 1.8;
+const BALL_LOST_TIME_SCALE: f32 = 0.3;
+/// Real seconds the game freezes for after a bomb or the last brick.
+const HIT_STOP_SECONDS: f32 = 0.06;
+/// Real seconds and largest zoom of the pulse that replaces the hit-stop in streamer mode.
+const ZOOM_PULSE_SECONDS: f32 = 0.3;
+const ZOOM_PULSE_AMOUNT: f32 = 0.03;
+/// Updates per second while the window is minimized or out of focus, enough to notice it coming back.
+const BACKGROUND_FPS: u32 = 4;
+const BALL_LOST_EDGE_COLOR: [f32; 3] = [1., 0.2, 0.2];
+const BALL_LOST_EDGE_IDS: [&str; 4] = ["edge_top", "edge_bottom", "edge_left", "edge_right"];
 
 ///
 /// macros
@@ -52,7 +280,7 @@ macro_rules! assign_text_color {
 /// enums
 ///
 
-#[derive(Copy, Clone, Eq, Hash, PartialEq)]
+#[derive(Copy, Clone, Deserialize, Eq, Hash, PartialEq, Serialize)]
 enum AssetType {
   Background(usize),
   PaddleSmall(usize),
@@ -60,19 +288,65 @@ enum AssetType {
   Ball(usize),
 }
 
-#[derive(Copy, Clone, Eq, Hash, PartialEq)]
-enum SoundType {
-  PaddleHit,
-  Confirm,
-  Pause,
-  WallHit,
-  BrickHit2,
+impl AssetType {
+  /// Sprite sheets only needed while playing, editing or customizing. They are released in the campaign menu and
+  /// loaded again by the states that draw them.
+  const GAMEPLAY: [AssetType; 3] = [
+    AssetType::PaddleSmall(0),
+    AssetType::PaddleMedium(1),
+    AssetType::Ball(2),
+  ];
+
+  /// Texture and sprite sheet definition.
+  fn paths(self) -> (&'static str, &'static str) {
+    match self {
+      AssetType::Background(_) => ("textures/background.png", "textures/background.ron"),
+      AssetType::PaddleSmall(_) | AssetType::PaddleMedium(_) => ("textures/breakout.png", "textures/breakout.ron"),
+      AssetType::Ball(_) => ("textures/breakout.png", "textures/breakout.ron"),
+    }
+  }
+}
+
+/// Sprite layers from back to front. Text and the hud are not part of these, they are drawn by the ui pass in
+/// screen space and are therefore not moved by the camera.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Layer {
+  Background,
+  Playfield,
+  Ball,
+  Effects,
+  Overlay,
+}
+
+impl Layer {
+  const CAMERA_Z: f32 = 10.;
+
+  fn z(self) -> f32 {
+    match self {
+      Layer::Background => 1.1,
+      Layer::Playfield => 1.2,
+      Layer::Ball => 1.3,
+      Layer::Effects => 1.4,
+      Layer::Overlay => 1.5,
+    }
+  }
+
+  fn transform(self, x: f32, y: f32) -> Transform {
+    Transform::from(Vector3::new(x, y, self.z()))
+  }
 }
 
 #[derive(Copy, Clone, Eq, Hash, PartialEq)]
 enum TextSelectedType {
   Start,
+  Modes,
   HighScore,
+  CustomLevels,
+  Editor,
+  ImportLevel,
+  Customize,
+  Data,
+  Credits,
 }
 
 impl Default for TextSelectedType {
@@ -81,425 +355,8068 @@ impl Default for TextSelectedType {
   }
 }
 
+impl TextSelectedType {
+  const ALL: [TextSelectedType; 9] = [
+    TextSelectedType::Start,
+    TextSelectedType::Modes,
+    TextSelectedType::HighScore,
+    TextSelectedType::CustomLevels,
+    TextSelectedType::Editor,
+    TextSelectedType::ImportLevel,
+    TextSelectedType::Customize,
+    TextSelectedType::Data,
+    TextSelectedType::Credits,
+  ];
+
+  fn index(self) -> usize {
+    TextSelectedType::ALL.iter().position(|&t| t == self).unwrap_or(0)
+  }
+
+  fn previous(self) -> Self {
+    TextSelectedType::ALL[self.index().saturating_sub(1)]
+  }
+
+  fn next(self) -> Self {
+    TextSelectedType::ALL[(self.index() + 1).min(TextSelectedType::ALL.len() - 1)]
+  }
+
+  /// As written in the start menu.
+  fn label(self) -> &'static str {
+    match self {
+      TextSelectedType::Start => "START",
+      TextSelectedType::Modes => "MODES",
+      TextSelectedType::HighScore => "HIGH SCORES",
+      TextSelectedType::CustomLevels => "CUSTOM LEVELS",
+      TextSelectedType::Editor => "LEVEL EDITOR",
+      TextSelectedType::ImportLevel => "IMPORT LEVEL",
+      TextSelectedType::Customize => "CUSTOMIZE",
+      TextSelectedType::Data => "SAVE DATA",
+      TextSelectedType::Credits => "CREDITS",
+    }
+  }
+}
+
+/// Graded at the end of a campaign level from its `LevelStats`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum LevelBonus {
+  /// No ball lost and no tilt used.
+  Perfect,
+  NoMiss,
+  /// At least `CLUTCH_CLOSE_CALLS` close calls.
+  Clutch,
+}
+
+impl LevelBonus {
+  fn earned(stats: &LevelStats) -> Vec<LevelBonus> {
+    let mut bonuses = vec![];
+    if stats.balls_lost == 0 && stats.tilts == 0 {
+      bonuses.push(LevelBonus::Perfect);
+    } else if stats.balls_lost == 0 {
+      bonuses.push(LevelBonus::NoMiss);
+    }
+    if stats.close_calls >= CLUTCH_CLOSE_CALLS {
+      bonuses.push(LevelBonus::Clutch);
+    }
+    bonuses
+  }
+
+  fn label(self) -> &'static str {
+    match self {
+      LevelBonus::Perfect => "PERFECT",
+      LevelBonus::NoMiss => "NO MISS",
+      LevelBonus::Clutch => "CLUTCH",
+    }
+  }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum DataResetType {
+  HighScores,
+  Progress,
+  Settings,
+  Everything,
+}
+
+impl DataResetType {
+  const ALL: [DataResetType; 4] = [
+    DataResetType::HighScores,
+    DataResetType::Progress,
+    DataResetType::Settings,
+    DataResetType::Everything,
+  ];
+
+  fn label(self) -> &'static str {
+    match self {
+      DataResetType::HighScores => "HIGH SCORES",
+      DataResetType::Progress => "CAMPAIGN PROGRESS",
+      DataResetType::Settings => "SETTINGS",
+      DataResetType::Everything => "EVERYTHING",
+    }
+  }
+
+  fn resets_high_scores(self) -> bool {
+    self == DataResetType::HighScores || self == DataResetType::Everything
+  }
+
+  fn resets_progress(self) -> bool {
+    self == DataResetType::Progress || self == DataResetType::Everything
+  }
+
+  fn resets_settings(self) -> bool {
+    self == DataResetType::Settings || self == DataResetType::Everything
+  }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum LevelSortType {
+  Name,
+  Author,
+  Difficulty,
+  PersonalBest,
+}
+
+impl Default for LevelSortType {
+  fn default() -> Self {
+    LevelSortType::Name
+  }
+}
+
+impl LevelSortType {
+  fn next(self) -> Self {
+    match self {
+      LevelSortType::Name => LevelSortType::Author,
+      LevelSortType::Author => LevelSortType::Difficulty,
+      LevelSortType::Difficulty => LevelSortType::PersonalBest,
+      LevelSortType::PersonalBest => LevelSortType::Name,
+    }
+  }
+
+  fn label(self) -> &'static str {
+    match self {
+      LevelSortType::Name => "NAME",
+      LevelSortType::Author => "AUTHOR",
+      LevelSortType::Difficulty => "DIFFICULTY",
+      LevelSortType::PersonalBest => "PERSONAL BEST",
+    }
+  }
+}
+
 ///
 /// types
 ///
 
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Deserialize, Serialize)]
 #[storage(DenseVecStorage)]
 struct Paddle {
   width: f32,
   height: f32,
+  velocity: f32,
 }
 
-#[derive(Component, Debug)]
+/// A brick of the level or a bonus brick, the kinds that behave differently also carry a marker like `SplitBrick`.
+#[derive(Component, Debug, Deserialize, Serialize)]
+#[storage(DenseVecStorage)]
+struct Brick {
+  width: f32,
+  height: f32,
+  /// The level character the brick was spawned from, bonus bricks and the rings of the radial mode are plain.
+  tier: char,
+  /// Hits it takes to break, from its `BrickProperties`.
+  hp: u32,
+  /// Points scored when it is destroyed, with the score multiplier of the mode applied.
+  score: u32,
+}
+
+#[derive(Component, Debug, Deserialize, Serialize)]
 #[storage(DenseVecStorage)]
 struct Ball {
   direction: Vector3<f32>,
   radius: f32,
+  /// Grows with every destroyed brick up to `BALL_MAX_VELOCITY`.
+  speed: f32,
+  /// Tint of the ball skin, shifted towards `BALL_HOT_COLOR` as the ball speeds up.
+  color: [f32; 3],
+  /// Sits on the player paddle until it is served, at the start of a level and after the ball was lost.
+  stuck: bool,
+  /// Seat of the player the ball belongs to.
+  seat: usize,
+  /// Seconds the serve has been held while the ball is stuck, it launches once the serve is released.
+  #[serde(skip)]
+  wind_up: Option<f32>,
+  /// Seconds the ball has been waiting on the paddle, for the auto serve.
+  #[serde(skip)]
+  waiting: f32,
 }
 
-#[derive(Component, Debug, Default)]
-#[storage(NullStorage)]
-struct Player;
-
-#[derive(Default)]
-struct SpriteSheetMap(HashMap<AssetType, SpriteSheetHandle>);
-
-#[derive(Default)]
-struct SoundMap(HashMap<SoundType, SourceHandle>);
-
-#[derive(SystemData)]
-struct Sounds<'a> {
-  sound_map: Read<'a, SoundMap>,
-  storage: Read<'a, AssetStorage<Source>>,
-  output: Option<Read<'a, Output>>,
+/// The paddle of the player in `seat`, `0` unless two players share the screen in co-op.
+#[derive(Component, Debug, Deserialize, Serialize)]
+#[storage(DenseVecStorage)]
+struct Player {
+  seat: usize,
 }
 
-/// functions
+/// Puts the paddle on the arc of the radial mode. `track` is where it would be on a straight paddle line, the
+/// paddle is drawn that far along the arc.
+#[derive(Component, Debug, Deserialize, Serialize)]
+#[storage(DenseVecStorage)]
+struct ArcPaddle {
+  track: f32,
+}
 
-fn init_camera(world: &mut World) {
-  world
-    .create_entity()
-    .with(Camera::standard_2d(VIRTUAL_WIDTH, VIRTUAL_HEIGHT))
-    .with(Transform::from(Vector3::new(
-      VIRTUAL_WIDTH / 2.,
-      VIRTUAL_HEIGHT / 2.,
-      10.,
-    )))
-    .build();
+/// The background covers the screen with `scale`, on wider playfields it is scaled along with the view.
+#[derive(Component, Debug)]
+#[storage(DenseVecStorage)]
+struct Background {
+  scale: (f32, f32),
 }
 
-fn load_sprite_sheet_handle(
-  world: &World,
-  texture_path: &str,
-  ron_path: &str,
-  progress_counter: &mut ProgressCounter,
-) -> SpriteSheetHandle {
-  let texture_handle = {
-    let loader = world.read_resource::<Loader>();
-    let texture_storage = world.read_resource::<AssetStorage<Texture>>();
-    loader.load(texture_path, ImageFormat::default(), (), &texture_storage)
-  };
-  let loader = world.read_resource::<Loader>();
-  let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
-  loader.load(
-    ron_path,
-    SpriteSheetFormat(texture_handle),
-    progress_counter,
-    &sprite_sheet_store,
-  )
+/// Levels can be taller than the screen, `view_bottom` is where the camera currently shows the playfield from.
+/// Levels and modes can ask for a wider playfield as well, the camera then zooms out until it fits the width.
+#[derive(Deserialize, Serialize)]
+struct Playfield {
+  #[serde(default = "virtual_width")]
+  width: f32,
+  height: f32,
+  view_bottom: f32,
 }
 
-fn init_assets(world: &mut World, asset_type_list: Vec<AssetType>) -> ProgressCounter {
-  let mut sprite_sheet_map = SpriteSheetMap::default();
-  let mut progress_counter = ProgressCounter::new();
-  for &asset_type in asset_type_list.iter() {
-    let (texture_path, ron_path) = match asset_type {
-      AssetType::Background(_) => ("textures/background.png", "textures/background.ron"),
-      AssetType::PaddleSmall(_) | AssetType::PaddleMedium(_) => ("textures/breakout.png", "textures/breakout.ron"),
-      AssetType::Ball(_) => ("textures/breakout.png", "textures/breakout.ron"),
-    };
-    let sprite_sheet_handle = load_sprite_sheet_handle(world, texture_path, ron_path, &mut progress_counter);
-    sprite_sheet_map.0.insert(asset_type, sprite_sheet_handle);
+impl Playfield {
+  /// A playfield of at least the size of the view.
+  fn new(width: f32, height: f32) -> Self {
+    let width = width.max(VIRTUAL_WIDTH);
+    Playfield {
+      width,
+      height: height.max(VIRTUAL_HEIGHT * width / VIRTUAL_WIDTH),
+      view_bottom: 0.,
+    }
   }
-  world.insert(sprite_sheet_map);
-  progress_counter
-}
 
-fn init_audio(world: &mut World, sound_type_list: Vec<SoundType>) {
-  let mut sound_map = SoundMap::default();
-  for &sound_type in sound_type_list.iter() {
-    let sound_path = match sound_type {
-      SoundType::PaddleHit => "sounds/paddle_hit.wav",
-      SoundType::Confirm => "sounds/confirm.wav",
-      SoundType::Pause => "sounds/pause.wav",
-      SoundType::WallHit => "sounds/wall_hit.wav",
-      SoundType::BrickHit2 => "sounds/brick-hit-2.wav",
-    };
-    let source_handle = {
-      let loader = world.read_resource::<Loader>();
-      loader.load(sound_path, WavFormat, (), &world.read_resource())
-    };
-    sound_map.0.insert(sound_type, source_handle);
+  /// How much more of the playfield the camera shows than the screen has virtual pixels.
+  fn zoom(&self) -> f32 {
+    self.width / VIRTUAL_WIDTH
   }
-  world.insert(sound_map);
-}
 
-fn play_sound_in_state(world: &World, sound_type: SoundType) {
-  let sound_map = world.fetch::<SoundMap>();
-  let output = world.try_fetch::<Output>();
-  let storage = world.fetch::<AssetStorage<Source>>();
-  if let Some(ref output) = output.as_ref() {
-    if let Some(sound) = sound_map.0.get(&sound_type) {
-      if let Some(sound) = storage.get(&sound) {
-        output.play_once(sound, 0.15);
-      }
-    }
+  fn view_height(&self) -> f32 {
+    VIRTUAL_HEIGHT * self.zoom()
+  }
+
+  /// Where a point at `x` virtual pixels from the left of the screen is on the playfield, for the mouse and touch.
+  fn screen_x(&self, x: f32) -> f32 {
+    x * self.zoom()
   }
 }
 
-fn play_sound_in_system(sounds: &Sounds, sound_type: SoundType) {
-  if let Some(ref output) = sounds.output.as_ref() {
-    if let Some(sound) = sounds.sound_map.0.get(&sound_type) {
-      if let Some(sound) = sounds.storage.get(&sound) {
-        output.play_once(sound, 0.15);
-      }
-    }
+impl Default for Playfield {
+  fn default() -> Self {
+    Playfield::new(VIRTUAL_WIDTH, VIRTUAL_HEIGHT)
   }
 }
 
-fn point_in_rect(x: f32, y: f32, left: f32, bottom: f32, right: f32, top: f32) -> bool {
-  x >= left && x <= right && y >= bottom && y <= top
+/// Fading copy of a sprite, left behind while dashing or by bricks destroyed by a bomb.
+#[derive(Component, Debug)]
+#[storage(DenseVecStorage)]
+struct Ghost {
+  lifetime: f32,
+  duration: f32,
 }
 
-fn get_texture_dimensions(world: &World, sprite_sheet_handle: &SpriteSheetHandle, sprite_pos: usize) -> (f32, f32) {
-  let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
-  let spritesheet = sprite_sheet_store
-    .get(&sprite_sheet_handle)
-    .expect("Couldn't find the handle for the paddle sprite!");
-  (
-    spritesheet.sprites[sprite_pos].width,
-    spritesheet.sprites[sprite_pos].height,
-  )
+/// A falling brick fragment, it fades out through its `Ghost`.
+#[derive(Component, Debug)]
+#[storage(DenseVecStorage)]
+struct Debris {
+  velocity: Vector3<f32>,
 }
 
-///
-/// systems
-///
+/// A round bumper of a level, the ball bounces off it with the bumper material and scores its `Points` on every hit.
+/// Drawn with the ball sprite scaled up to `radius`.
+#[derive(Component, Debug, Deserialize, Serialize)]
+#[storage(DenseVecStorage)]
+struct Bumper {
+  radius: f32,
+  /// Scale of the sprite while the bumper isn't flashing.
+  scale: f32,
+  /// Seconds left of the flash after a hit.
+  #[serde(skip)]
+  flash: f32,
+}
 
-#[derive(Default, SystemDesc)]
-struct PaddleSystem;
+/// A one-way gate of a level, the ball only passes it moving in `direction`, `1.` up and `-1.` down, and bounces off
+/// it otherwise. Gates don't count towards clearing the level.
+#[derive(Component, Debug, Deserialize, Serialize)]
+#[storage(DenseVecStorage)]
+struct Gate {
+  direction: f32,
+  width: f32,
+  height: f32,
+}
 
-impl<'a> System<'a> for PaddleSystem {
-  type SystemData = (
-    WriteStorage<'a, Transform>,
-    ReadStorage<'a, Paddle>,
-    ReadStorage<'a, Player>,
-    Read<'a, InputHandler<StringBindings>>,
-    Read<'a, Time>,
-  );
+/// A mini-paddle of a bonus stage that `MoverSystem` moves back and forth along `points`, in playfield units. The
+/// ball bounces off it like off a paddle and picks up its motion.
+#[derive(Component, Debug, Deserialize, Serialize)]
+#[storage(DenseVecStorage)]
+struct Mover {
+  points: Vec<(f32, f32)>,
+  speed: f32,
+  width: f32,
+  height: f32,
+  /// Distance covered since the mover was spawned, back and forth.
+  travelled: f32,
+  /// Units per second in the last frame.
+  #[serde(skip)]
+  velocity: (f32, f32),
+}
 
-  fn run(&mut self, (mut transforms, paddles, player, input, time): Self::SystemData) {
-    for (transform, paddle, _) in (&mut transforms, &paddles, &player).join() {
-      let horizontal = input.axis_value("horizontal").unwrap_or(0.0);
-
-      if horizontal != 0.0 {
-        let dx = time.delta_seconds() * 200.0 * horizontal;
-        let paddle_x = transform.translation().x;
-        transform.set_translation_x(
-          (paddle_x + dx)
-            .min(VIRTUAL_WIDTH - paddle.width / 2.)
-            .max(paddle.width / 2.),
-        );
+impl Mover {
+  /// Where the mover is after `travelled`, it turns around at both ends of the path.
+  fn position(&self) -> (f32, f32) {
+    let length = |(x1, y1): (f32, f32), (x2, y2): (f32, f32)| ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+    let total = self.points.windows(2).map(|leg| length(leg[0], leg[1])).sum::<f32>();
+    let first = self.points.first().copied().unwrap_or_default();
+    if total <= 0. {
+      return first;
+    }
+    let mut along = self.travelled % (total * 2.);
+    if along > total {
+      along = total * 2. - along;
+    }
+    for leg in self.points.windows(2) {
+      let ((x1, y1), (x2, y2)) = (leg[0], leg[1]);
+      let leg_length = length(leg[0], leg[1]);
+      if along <= leg_length && leg_length > 0. {
+        let t = along / leg_length;
+        return (x1 + (x2 - x1) * t, y1 + (y2 - y1) * t);
       }
+      along -= leg_length;
     }
+    self.points.last().copied().unwrap_or(first)
   }
 }
 
-#[derive(Default, SystemDesc)]
-struct BallSystem;
+/// Marks a brick the ball bounces off until every `Switch` with the same link is destroyed, see `DoorSystem`.
+#[derive(Component, Debug, Deserialize, Serialize)]
+#[storage(DenseVecStorage)]
+struct Door {
+  link: u8,
+}
 
-impl<'a> System<'a> for BallSystem {
-  type SystemData = (WriteStorage<'a, Transform>, ReadStorage<'a, Ball>, Read<'a, Time>);
+/// Marks a brick that opens the doors with the same link once it and the other switches of the link are destroyed.
+#[derive(Component, Debug, Deserialize, Serialize)]
+#[storage(DenseVecStorage)]
+struct Switch {
+  link: u8,
+}
 
-  fn run(&mut self, (mut transforms, balls, time): Self::SystemData) {
-    for (transform, ball) in (&mut transforms, &balls).join() {
-      transform.prepend_translation(ball.direction * time.delta_seconds() * BALL_VELOCITY);
-    }
-  }
+/// Marks a brick that splits the ball destroying it, see `SplitSystem`.
+#[derive(Component, Debug, Default, Deserialize, Serialize)]
+#[storage(NullStorage)]
+struct SplitBrick;
+
+/// A brick that only stays for `BONUS_LIFETIME` seconds, hitting it in time fills the energy meter or splits the
+/// ball. It doesn't count towards clearing the level.
+#[derive(Component, Debug, Deserialize, Serialize)]
+#[storage(DenseVecStorage)]
+struct BonusBrick {
+  remaining: f32,
 }
 
-#[derive(Default, SystemDesc)]
-struct CollisionSystem;
+/// Seconds until `BonusSystem` spawns the next bonus brick, and what the last one hit gave.
+#[derive(Default)]
+struct BonusState {
+  next: Option<f32>,
+  award: &'static str,
+  flash: f32,
+}
 
-impl<'a> System<'a> for CollisionSystem {
-  type SystemData = (
-    Entities<'a>,
-    WriteStorage<'a, Ball>,
-    ReadStorage<'a, Paddle>,
-    ReadStorage<'a, Transform>,
-    ReadStorage<'a, Player>,
-    Sounds<'a>,
-  );
+/// Chain lightning collected from a bonus brick. The next brick the ball destroys is queued in `strikes` and
+/// `LightningSystem` arcs from it to its nearest neighbours.
+#[derive(Default)]
+struct Lightning {
+  charged: bool,
+  strikes: Vec<Vector3<f32>>,
+  /// Start, end and real seconds left of the arcs on screen.
+  arcs: Vec<(Vector3<f32>, Vector3<f32>, f32)>,
+}
 
-  fn run(&mut self, (entities, mut balls, paddles, transforms, players, sounds): Self::SystemData) {
-    for (ball, transform) in (&mut balls, &transforms).join() {
-      let ball_x = transform.translation().x;
-      let ball_y = transform.translation().y;
-      let ball_transform = transform;
+/// Marks a brick the ball bounces off without harm while the breaker is not open.
+#[derive(Component, Debug, Default, Deserialize, Serialize)]
+#[storage(NullStorage)]
+struct Armored;
 
-      if (ball_y <= ball.radius && ball.direction.y < 0.0)
-        || (ball_y >= VIRTUAL_HEIGHT - ball.radius && ball.direction.y > 0.0)
-      {
-        play_sound_in_system(&sounds, SoundType::WallHit);
-        ball.direction.y = -ball.direction.y;
-      }
+/// Marks a brick nothing can destroy, it is left out of the bricks that clear the level.
+#[derive(Component, Debug, Default, Deserialize, Serialize)]
+#[storage(NullStorage)]
+struct Metal;
 
-      if (ball_x <= ball.radius && ball.direction.x < 0.0)
-        || (ball_x >= VIRTUAL_WIDTH - ball.radius && ball.direction.x > 0.0)
-      {
-        play_sound_in_system(&sounds, SoundType::WallHit);
-        ball.direction.x = -ball.direction.x;
-      }
+/// Paddle hits charge the breaker while armored bricks are left, once charged it opens their shields for
+/// `BREAKER_WINDOW_SECONDS`.
+#[derive(Default, Deserialize, Serialize)]
+struct Breaker {
+  hits: u32,
+  open: f32,
+}
 
-      for (e, paddle, transform) in (&*entities, &paddles, &transforms).join() {
-        let paddle_x = transform.translation().x - paddle.width * 0.5;
-        let paddle_y = transform.translation().y - paddle.height * 0.5;
+impl Breaker {
+  fn is_open(&self) -> bool {
+    self.open > 0.
+  }
+}
 
-        if point_in_rect(
-          ball_x,
-          ball_y,
-          paddle_x - ball.radius,
-          paddle_y - ball.radius,
-          paddle_x + paddle.width + ball.radius,
-          paddle_y + paddle.height + ball.radius,
-        ) {
-          if players.get(e).is_some() {
-            play_sound_in_system(&sounds, SoundType::PaddleHit);
-          } else {
-            entities
-              .delete(e)
-              .expect("Couldn't delete paddle while colliding with ball!");
-            play_sound_in_system(&sounds, SoundType::BrickHit2);
-          }
+/// Points a bumper scores on every hit, taken from its `BrickProperties`.
+#[derive(Component, Debug, Deserialize, Serialize)]
+#[storage(DenseVecStorage)]
+struct Points(u32);
 
-          let delta = ball_transform.translation() - transform.translation();
-          let direction = delta.normalize();
-          ball.direction = Vector3::new(direction.x, direction.y, ball.direction.z);
-        }
-      }
-    }
+/// Points scored since the level started, in total and by seat.
+#[derive(Default, Deserialize, Serialize)]
+struct Score {
+  points: u32,
+  seats: [u32; 2],
+}
+
+impl Score {
+  fn add(&mut self, seat: usize, points: u32) {
+    self.points += points;
+    self.seats[seat] += points;
   }
 }
 
-///
-/// States
-///
+/// Game time left in a timed mode, `None` if the mode has no time limit.
+#[derive(Default, Deserialize, Serialize)]
+struct ModeClock {
+  remaining: Option<f32>,
+}
 
-#[derive(Default)]
+/// Swings the bricks of a bonus round around `center`, see `FieldRotationSystem`. `None` outside of bonus rounds.
+#[derive(Default, Deserialize, Serialize)]
+struct FieldRotation {
+  center: Option<(f32, f32)>,
+  angle: f32,
+  elapsed: f32,
+}
+
+/// The angle of the last serve on each level by name, for the quick serve and the auto serve. Kept until the game is
+/// closed.
+#[derive(Default)]
+struct ServeMemory(HashMap<String, f32>);
+
+/// Balls that destroyed a split brick this frame.
+#[derive(Default)]
+struct SplitQueue(Vec<Entity>);
+
+/// Seconds since each seat last moved their paddle or served, counted by `PaddleSystem`.
+#[derive(Default)]
+struct AwayTimers {
+  idle: [f32; 2],
+}
+
+impl AwayTimers {
+  /// The seat that has been idle the longest of the first `players`, with its idle seconds.
+  fn longest(&self, players: usize) -> Option<(usize, f32)> {
+    self
+      .idle
+      .iter()
+      .copied()
+      .enumerate()
+      .take(players)
+      .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+  }
+}
+
+/// Set by the pause menu when the game is quit, the play state keeps the level to continue it on the next start.
+#[derive(Default)]
+struct SuspendRequested(bool);
+
+/// Bricks destroyed by the ball this frame, `DebrisSystem` shatters them.
+#[derive(Default)]
+struct ShatterQueue(Vec<(Transform, SpriteRender)>);
+
+/// Every tilt nudges the ball once and costs `TILT_PENALTY` times the number of tilts used so far, the sum is
+/// kept in `penalty`.
+#[derive(Default, Deserialize, Serialize)]
+struct TiltState {
+  remaining: u32,
+  used: u32,
+  penalty: u32,
+  pending: bool,
+}
+
+/// Modifiers of the level being played, replaced whenever a level starts.
+#[derive(Default)]
+struct GameMode {
+  new_game_plus: bool,
+  mode: PlayMode,
+}
+
+/// Light sprite following `source`, see `GlowSystem`.
+#[derive(Component, Debug)]
+#[storage(DenseVecStorage)]
+struct Glow {
+  source: Entity,
+}
+
+/// One of the dots showing where a serve being wound up will send `ball`, see `ServeAimSystem`.
+#[derive(Component, Debug)]
+#[storage(DenseVecStorage)]
+struct AimMarker {
+  ball: Entity,
+  index: usize,
+}
+
+/// Faint copy of the ball where it will cross the paddle line, see `InterceptSystem`.
+#[derive(Component, Debug, Default)]
+#[storage(NullStorage)]
+struct InterceptMarker;
+
+/// Filled by consecutive brick hits without touching the paddle, a full meter can be spent on a bomb.
+#[derive(Default, Deserialize, Serialize)]
+struct EnergyMeter {
+  charge: f32,
+  combo: u32,
+  bomb_pending: bool,
+}
+
+/// Real seconds left of a hit-stop, the game is frozen while it runs.
+#[derive(Default)]
+struct HitStop {
+  remaining: f32,
+  /// Real seconds left of the zoom pulse that streamer mode shows instead of freezing the game.
+  zoom_pulse: f32,
+  /// Set in calm modes, nothing triggers a hit-stop then.
+  muted: bool,
+}
+
+impl HitStop {
+  fn trigger(&mut self, settings: &Settings) {
+    if !settings.hit_stop || settings.reduced_motion || self.muted {
+      return;
+    }
+    if settings.streamer_mode {
+      self.zoom_pulse = ZOOM_PULSE_SECONDS;
+    } else {
+      self.remaining = HIT_STOP_SECONDS;
+    }
+  }
+
+  /// Scale of the camera, dipping below 1 and back while a zoom pulse runs.
+  fn zoom(&self) -> f32 {
+    let progress = 1. - self.zoom_pulse / ZOOM_PULSE_SECONDS;
+    1. - ZOOM_PULSE_AMOUNT * (progress * std::f32::consts::PI).sin()
+  }
+}
+
+/// Whether the window is minimized or another window has the focus. Play pauses itself and the frame rate drops to
+/// `BACKGROUND_FPS` until it comes back.
+#[derive(Default)]
+struct WindowActivity {
+  background: bool,
+}
+
+/// When `main` started, removed once the start menu is ready to log the time to menu.
+struct LaunchedAt(Instant);
+
+/// Toggled with F3 while playing.
+#[derive(Default)]
+struct DebugOverlay {
+  visible: bool,
+}
+
+/// Balls left before the run is over, `None` in modes where a lost ball is simply served again. Set when a run
+/// starts, a campaign run carries it from one level to the next with its `Progress`.
+#[derive(Default, Deserialize, Serialize)]
+struct Lives {
+  remaining: Option<u32>,
+}
+
+/// Real seconds since the ball fell out of the playfield, `None` while it is in play.
+#[derive(Default, Deserialize, Serialize)]
+struct BallLost {
+  elapsed: Option<f32>,
+}
+
+/// Counted while a level is played and shown on the tally once it is cleared.
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+struct LevelStats {
+  serves: u32,
+  paddle_hits: u32,
+  balls_lost: u32,
+  close_calls: u32,
+  /// Copied from `TiltState` when the level is cleared.
+  tilts: u32,
+}
+
+/// Seconds left to show the close call flash, set by `CollisionSystem` when the paddle edge saved the ball.
+#[derive(Default)]
+struct CloseCall {
+  flash: f32,
+}
+
+#[derive(Default)]
+struct DashState {
+  active: f32,
+  direction: f32,
+  cooldown: f32,
+}
+
+/// Written by the states from window touch events, the paddle follows `drag_x` while a finger is down on the
+/// lower half of the screen.
+#[derive(Default)]
+struct TouchInput {
+  drag_x: Option<f32>,
+  tap_start: Option<(u64, f32, f32)>,
+}
+
+/// Remembers a press for `INPUT_BUFFER_SECONDS` plus the calibrated input latency, so it still counts when the
+/// game becomes ready shortly after.
+#[derive(Default)]
+struct BufferedPress {
+  remaining: Option<f32>,
+}
+
+impl BufferedPress {
+  fn press(&mut self, latency: f32) {
+    self.remaining = Some(INPUT_BUFFER_SECONDS + latency);
+  }
+
+  fn tick(&mut self, delta_seconds: f32) {
+    self.remaining = self
+      .remaining
+      .map(|time| time - delta_seconds)
+      .filter(|&time| time >= 0.0);
+  }
+
+  fn take(&mut self) -> bool {
+    self.remaining.take().is_some()
+  }
+}
+
+/// Serve presses of each seat, so a serve pressed shortly before the ball is back on the paddle still launches it.
+/// Filled by `PlayState` from the input events and taken by `BallLostSystem`.
+#[derive(Default)]
+struct ServeBuffer([BufferedPress; 2]);
+
+/// Turns a held menu key into repeated steps, first after `MENU_REPEAT_DELAY` and then every `MENU_REPEAT_RATE`.
+/// The OS key repeat is not used because `InputEvent::KeyPressed` only fires once per physical press.
+#[derive(Default)]
+struct MenuRepeat {
+  held: Option<(VirtualKeyCode, f32)>,
+}
+
+impl MenuRepeat {
+  fn press(&mut self, key_code: VirtualKeyCode) {
+    self.held = Some((key_code, MENU_REPEAT_DELAY));
+  }
+
+  fn release(&mut self, key_code: VirtualKeyCode) {
+    if self.held.map_or(false, |(held, _)| held == key_code) {
+      self.held = None;
+    }
+  }
+
+  fn tick(&mut self, delta_seconds: f32) -> Option<VirtualKeyCode> {
+    let (key_code, time) = self.held.as_mut()?;
+    *time -= delta_seconds;
+    if *time > 0.0 {
+      return None;
+    }
+    *time += MENU_REPEAT_RATE;
+    Some(*key_code)
+  }
+}
+
+/// Destructive menu actions only fire once their key has been held for `HOLD_TO_CONFIRM_SECONDS`, releasing the
+/// key early cancels them.
+#[derive(Default)]
+struct HoldToConfirm {
+  held: Option<(VirtualKeyCode, f32)>,
+}
+
+impl HoldToConfirm {
+  fn press(&mut self, key_code: VirtualKeyCode) {
+    if self.held.is_none() {
+      self.held = Some((key_code, 0.));
+    }
+  }
+
+  fn release(&mut self, key_code: VirtualKeyCode) {
+    if self.held.map_or(false, |(held, _)| held == key_code) {
+      self.held = None;
+    }
+  }
+
+  fn is_held(&self) -> bool {
+    self.held.is_some()
+  }
+
+  /// Returns the key once it has been held long enough, the hold has to start over for the next confirmation.
+  fn tick(&mut self, delta_seconds: f32) -> Option<VirtualKeyCode> {
+    let (key_code, time) = self.held.as_mut()?;
+    *time += delta_seconds;
+    if *time < HOLD_TO_CONFIRM_SECONDS {
+      return None;
+    }
+    let key_code = *key_code;
+    self.held = None;
+    Some(key_code)
+  }
+
+  /// A bar filling up while the key is held, e.g. `[|||.......]`.
+  fn indicator(&self) -> String {
+    let time = self.held.map_or(0., |(_, time)| time);
+    let filled = ((time / HOLD_TO_CONFIRM_SECONDS * HOLD_TO_CONFIRM_STEPS as f32) as usize).min(HOLD_TO_CONFIRM_STEPS);
+    format!("[{}{}]", "|".repeat(filled), ".".repeat(HOLD_TO_CONFIRM_STEPS - filled))
+  }
+}
+
+#[derive(Default)]
+struct SpriteSheetMap(HashMap<AssetType, SpriteSheetHandle>);
+
+#[derive(Default)]
+struct SoundMap(HashMap<SoundType, SourceHandle>);
+
+#[derive(SystemData)]
+struct Sounds<'a> {
+  sound_map: Read<'a, SoundMap>,
+  storage: Read<'a, AssetStorage<Source>>,
+  output: Option<Read<'a, Output>>,
+  events: Write<'a, SoundEvents>,
+}
+
+/// functions
+
+fn virtual_width() -> f32 {
+  VIRTUAL_WIDTH
+}
+
+fn init_camera(world: &mut World) {
+  world
+    .create_entity()
+    .with(Camera::standard_2d(VIRTUAL_WIDTH, VIRTUAL_HEIGHT))
+    .with(Transform::from(Vector3::new(
+      VIRTUAL_WIDTH / 2.,
+      VIRTUAL_HEIGHT / 2.,
+      Layer::CAMERA_Z,
+    )))
+    .build();
+}
+
+fn load_sprite_sheet_handle(
+  world: &World,
+  texture_path: &str,
+  ron_path: &str,
+  progress_counter: &mut ProgressCounter,
+) -> SpriteSheetHandle {
+  let texture_handle = {
+    let loader = world.read_resource::<Loader>();
+    let texture_storage = world.read_resource::<AssetStorage<Texture>>();
+    loader.load(texture_path, ImageFormat::default(), (), &texture_storage)
+  };
+  let loader = world.read_resource::<Loader>();
+  let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
+  loader.load(
+    ron_path,
+    SpriteSheetFormat(texture_handle),
+    progress_counter,
+    &sprite_sheet_store,
+  )
+}
+
+fn init_assets(world: &mut World, asset_type_list: Vec<AssetType>) -> ProgressCounter {
+  world.insert(SpriteSheetMap::default());
+  load_assets(world, &asset_type_list)
+}
+
+/// Adds the asset types that aren't loaded yet to the `SpriteSheetMap`.
+fn load_assets(world: &mut World, asset_type_list: &[AssetType]) -> ProgressCounter {
+  let mut sprite_sheet_map = world.remove::<SpriteSheetMap>().unwrap_or_default();
+  let mut progress_counter = ProgressCounter::new();
+  // Asset types on the same atlas share one handle. Loading it again would create a second texture and every
+  // texture change splits the sprite batch `RenderFlat2D` draws in one call.
+  let mut loaded: HashMap<&str, SpriteSheetHandle> = sprite_sheet_map
+    .0
+    .iter()
+    .map(|(asset_type, handle)| (asset_type.paths().0, handle.clone()))
+    .collect();
+  for &asset_type in asset_type_list.iter() {
+    if sprite_sheet_map.0.contains_key(&asset_type) {
+      continue;
+    }
+    let (texture_path, ron_path) = asset_type.paths();
+    let sprite_sheet_handle = loaded
+      .entry(texture_path)
+      .or_insert_with(|| load_sprite_sheet_handle(world, texture_path, ron_path, &mut progress_counter))
+      .clone();
+    sprite_sheet_map.0.insert(asset_type, sprite_sheet_handle);
+  }
+  world.insert(sprite_sheet_map);
+  progress_counter
+}
+
+/// Drops the map's handles to the gameplay sprite sheets. The asset storage frees a texture once no handle to it is
+/// left, so it stays loaded while sprites on screen still use it.
+fn release_gameplay_assets(world: &mut World) {
+  let released = {
+    let mut sprite_sheet_map = world.write_resource::<SpriteSheetMap>();
+    AssetType::GAMEPLAY
+      .iter()
+      .filter_map(|asset_type| sprite_sheet_map.0.remove(asset_type))
+      .collect::<Vec<_>>()
+  };
+  if released.is_empty() {
+    return;
+  }
+  let kept = world.exec(|sprites: ReadStorage<SpriteRender>| {
+    sprites
+      .join()
+      .filter(|sprite| released.contains(&sprite.sprite_sheet))
+      .count()
+  });
+  info!(
+    "Released {} gameplay sprite sheet handles, {} sprites still hold on to them",
+    released.len(),
+    kept
+  );
+}
+
+fn apply_frame_limit(world: &World, settings: &Settings) {
+  let (strategy, fps) = settings.frame_limit();
+  world.write_resource::<FrameLimiter>().set_rate(strategy, fps);
+  info!(
+    "Frame cap {}, vsync {}",
+    settings.frame_cap.label(),
+    if settings.vsync { "on" } else { "off" }
+  );
+}
+
+fn init_audio(world: &mut World) {
+  let mut sound_map = SoundMap::default();
+  for (sound_type, sound_path) in sounds::load_mapping() {
+    let source_handle = {
+      let loader = world.read_resource::<Loader>();
+      loader.load(sound_path, WavFormat, (), &world.read_resource())
+    };
+    sound_map.0.insert(sound_type, source_handle);
+  }
+  world.insert(sound_map);
+}
+
+/// Queues `text` for the narration, it is only spoken if the settings enable it.
+fn narrate(world: &World, text: impl Into<String>) {
+  world.write_resource::<NarrationQueue>().0.push(text.into());
+}
+
+/// Reports `event` to the tools connected to the observer stream.
+fn observe(world: &World, event: ObserverEvent) {
+  world.write_resource::<ObserverQueue>().0.push(event);
+}
+
+/// Bricks that still have to be destroyed to clear the level, bonus bricks and metal bricks don't count.
+fn bricks_left(world: &World) -> usize {
+  world.exec(
+    |(bricks, bonus_bricks, metal): (ReadStorage<Brick>, ReadStorage<BonusBrick>, ReadStorage<Metal>)| {
+      (&bricks, !&bonus_bricks, !&metal).join().count()
+    },
+  )
+}
+
+fn play_sound_in_state(world: &World, sound_type: SoundType) {
+  let sound_map = world.fetch::<SoundMap>();
+  let output = world.try_fetch::<Output>();
+  let storage = world.fetch::<AssetStorage<Source>>();
+  if let Some(ref output) = output.as_ref() {
+    if let Some(sound) = sound_type.lookup(&sound_map.0) {
+      if let Some(sound) = storage.get(&sound) {
+        output.play_once(sound, 0.15);
+      }
+    }
+  }
+}
+
+/// Keeps the `Ambient` sound looping quietly in `sink`, nothing plays while it has no mapping.
+fn loop_ambient(world: &World, sink: &mut Option<AudioSink>) {
+  let output = world.try_fetch::<Output>();
+  let sound_map = world.fetch::<SoundMap>();
+  let storage = world.fetch::<AssetStorage<Source>>();
+  if let Some(ref output) = output.as_ref() {
+    if let Some(sound) = SoundType::Ambient.lookup(&sound_map.0) {
+      if let Some(sound) = storage.get(&sound) {
+        let sink = sink.get_or_insert_with(|| {
+          let sink = AudioSink::new(output);
+          sink.set_volume(AMBIENT_VOLUME);
+          sink
+        });
+        if sink.empty() {
+          if let Err(e) = sink.append(sound) {
+            warn!("Couldn't play the ambient track: {}", e);
+          }
+        }
+      }
+    }
+  }
+}
+
+fn play_sound_in_system(sounds: &mut Sounds, sound_type: SoundType) {
+  play_sound_from(sounds, sound_type, None);
+}
+
+/// Plays a sound whose source is at `source_x`, the caption of the sound is shown on that side of the screen.
+fn play_sound_from(sounds: &mut Sounds, sound_type: SoundType, source_x: Option<f32>) {
+  sounds.events.0.push((sound_type, source_x));
+  if let Some(ref output) = sounds.output.as_ref() {
+    if let Some(sound) = sound_type.lookup(&sounds.sound_map.0) {
+      if let Some(sound) = sounds.storage.get(&sound) {
+        output.play_once(sound, 0.15);
+      }
+    }
+  }
+}
+
+/// Writes a diagnostic bundle of the level being played and sends it off if the settings name an endpoint. Returns
+/// the lines of the dialog that reports the outcome.
+fn write_report(world: &World) -> Vec<String> {
+  let mode = world.read_resource::<GameMode>().mode;
+  let diagnostics = Diagnostics {
+    version: env!("CARGO_PKG_VERSION"),
+    os: std::env::consts::OS,
+    arch: std::env::consts::ARCH,
+    mode: mode.label().to_string(),
+    level: world.read_resource::<Level>().name.clone(),
+    tournament_seed: Tournament::load()
+      .filter(|_| mode == PlayMode::Tournament)
+      .map(|tournament| tournament.seed),
+    score: world.read_resource::<Score>().points,
+    stats: format!("{:?}", *world.read_resource::<LevelStats>()),
+  };
+  let settings = world.read_resource::<Settings>();
+  let controls = application_root_dir()
+    .map(|app_root| settings.controls.path(&app_root))
+    .unwrap_or_default();
+  match report::write_bundle(&diagnostics, &controls) {
+    Ok(path) => {
+      info!("Wrote diagnostic bundle {}", path.display());
+      let mut lines = vec![path.display().to_string()];
+      if let Some(url) = &settings.report_url {
+        report::submit(path, url.clone());
+        lines.push("SENDING IT IN THE BACKGROUND".to_string());
+      }
+      lines
+    }
+    Err(e) => {
+      warn!("Couldn't write the diagnostic bundle: {}", e);
+      vec![e.to_uppercase()]
+    }
+  }
+}
+
+/// The playfield as it is right now, the bricks and then the paddles and balls.
+fn capture_rects(world: &World) -> (Vec<ShotRect>, Vec<ShotRect>) {
+  world.exec(
+    |(paddles, bricks, balls, bumpers, gates, movers, transforms, tints): (
+      ReadStorage<Paddle>,
+      ReadStorage<Brick>,
+      ReadStorage<Ball>,
+      ReadStorage<Bumper>,
+      ReadStorage<Gate>,
+      ReadStorage<Mover>,
+      ReadStorage<Transform>,
+      ReadStorage<Tint>,
+    )| {
+      let color = |tint: Option<&Tint>| {
+        tint.map_or(TEXT_COLOR, |tint| {
+          let (r, g, b, a) = tint.0.into_components();
+          [r, g, b, a]
+        })
+      };
+      let bumper_rects = (&bumpers, &transforms, tints.maybe())
+        .join()
+        .map(|(bumper, transform, tint)| ShotRect {
+          x: transform.translation().x,
+          y: transform.translation().y,
+          width: bumper.radius * 2.,
+          height: bumper.radius * 2.,
+          color: color(tint),
+        });
+      let gate_rects = (&gates, &transforms, tints.maybe())
+        .join()
+        .map(|(gate, transform, tint)| ShotRect {
+          x: transform.translation().x,
+          y: transform.translation().y,
+          width: gate.width,
+          height: gate.height,
+          color: color(tint),
+        });
+      let bricks = (&bricks, &transforms, tints.maybe())
+        .join()
+        .map(|(brick, transform, tint)| ShotRect {
+          x: transform.translation().x,
+          y: transform.translation().y,
+          width: brick.width,
+          height: brick.height,
+          color: color(tint),
+        })
+        .chain(bumper_rects)
+        .chain(gate_rects)
+        .collect();
+      let player_paddles = (&paddles, &transforms, tints.maybe())
+        .join()
+        .map(|(paddle, transform, tint)| ShotRect {
+          x: transform.translation().x,
+          y: transform.translation().y,
+          width: paddle.width,
+          height: paddle.height,
+          color: color(tint),
+        });
+      let mover_rects = (&movers, &transforms, tints.maybe())
+        .join()
+        .map(|(mover, transform, tint)| ShotRect {
+          x: transform.translation().x,
+          y: transform.translation().y,
+          width: mover.width,
+          height: mover.height,
+          color: color(tint),
+        });
+      let balls = (&balls, &transforms).join().map(|(ball, transform)| {
+        let [r, g, b] = ball.color;
+        ShotRect {
+          x: transform.translation().x,
+          y: transform.translation().y,
+          width: ball.radius * 2.,
+          height: ball.radius * 2.,
+          color: [r, g, b, 1.],
+        }
+      });
+      (bricks, player_paddles.chain(mover_rects).chain(balls).collect())
+    },
+  )
+}
+
+/// The components and resources that make up a level being played. Glows, ghosts and other effects are left out,
+/// their systems create them again.
+fn gameplay_snapshot() -> SnapshotPlugin {
+  playfield_snapshot().with_attachment_fns("sprite", capture_sprites, restore_sprite)
+}
+
+/// `gameplay_snapshot` without the sprites, which need the sprite sheets of a running game. Enough to draw the
+/// playfield as rectangles, like the export of a replay does.
+fn playfield_snapshot() -> SnapshotPlugin {
+  SnapshotPlugin::default()
+    .with_component::<Paddle>("paddle")
+    .with_component::<Brick>("brick")
+    .with_component::<Ball>("ball")
+    .with_component::<Bumper>("bumper")
+    .with_component::<Gate>("gate")
+    .with_component::<Mover>("mover")
+    .with_attachment::<Player>("player")
+    .with_attachment::<ArcPaddle>("arc_paddle")
+    .with_attachment::<SplitBrick>("split_brick")
+    .with_attachment::<BonusBrick>("bonus_brick")
+    .with_attachment::<Armored>("armored")
+    .with_attachment::<Metal>("metal")
+    .with_attachment::<Door>("door")
+    .with_attachment::<Switch>("switch")
+    .with_attachment::<Points>("points")
+    .with_attachment_fns("transform", snapshot::capture_transforms, snapshot::restore_transform)
+    .with_attachment_fns("tint", snapshot::capture_tints, snapshot::restore_tint)
+    .with_resource::<Score>("score")
+    .with_resource::<LevelStats>("level_stats")
+    .with_resource::<Lives>("lives")
+    .with_resource::<TiltState>("tilt")
+    .with_resource::<ModeClock>("mode_clock")
+    .with_resource::<EnergyMeter>("energy_meter")
+    .with_resource::<Breaker>("breaker")
+    .with_resource::<BallLost>("ball_lost")
+    .with_resource::<FieldRotation>("field_rotation")
+    .with_resource::<Playfield>("playfield")
+}
+
+/// A world with the storages `playfield_snapshot` restores into, for rebuilding replays without starting the game.
+fn replay_world() -> World {
+  let mut world = World::new();
+  world.register::<Paddle>();
+  world.register::<Brick>();
+  world.register::<Ball>();
+  world.register::<Bumper>();
+  world.register::<Gate>();
+  world.register::<Mover>();
+  world.register::<Player>();
+  world.register::<ArcPaddle>();
+  world.register::<SplitBrick>();
+  world.register::<BonusBrick>();
+  world.register::<Armored>();
+  world.register::<Metal>();
+  world.register::<Door>();
+  world.register::<Switch>();
+  world.register::<Points>();
+  world.register::<Transform>();
+  world.register::<Tint>();
+  world
+}
+
+/// Sprites are captured as the sheet they were loaded for and their number, the handles change with every load.
+fn capture_sprites(world: &World) -> Vec<(Entity, String)> {
+  let sprite_sheet_map = world.read_resource::<SpriteSheetMap>();
+  let sprite_renders = world.read_storage::<SpriteRender>();
+  (&world.entities(), &sprite_renders)
+    .join()
+    .filter_map(|(entity, sprite_render)| {
+      let asset_type = sprite_sheet_map
+        .0
+        .iter()
+        .find(|(_, handle)| **handle == sprite_render.sprite_sheet)
+        .map(|(asset_type, _)| *asset_type)?;
+      ron::ser::to_string(&(asset_type, sprite_render.sprite_number))
+        .ok()
+        .map(|contents| (entity, contents))
+    })
+    .collect()
+}
+
+fn restore_sprite(world: &mut World, entity: Entity, contents: &str) -> Result<(), String> {
+  let (asset_type, sprite_number) = ron::de::from_str::<(AssetType, usize)>(contents).map_err(|e| e.to_string())?;
+  let sprite_sheet_handle = world
+    .read_resource::<SpriteSheetMap>()
+    .0
+    .get(&asset_type)
+    .cloned()
+    .ok_or_else(|| "the sprite sheet isn't loaded".to_string())?;
+  world
+    .write_storage::<SpriteRender>()
+    .insert(entity, SpriteRender::new(sprite_sheet_handle, sprite_number))
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+/// The custom level after the one named `current` in the order of the files, the first one if `current` isn't a
+/// custom level.
+fn next_custom_level(current: &str) -> Option<Level> {
+  let dir = application_root_dir().ok()?.join(CUSTOM_LEVELS_DIR);
+  let mut levels = load_custom_levels(&dir);
+  levels.sort_by(|(a, _), (b, _)| a.cmp(b));
+  let levels = levels
+    .into_iter()
+    .filter_map(|(_, level)| level.ok())
+    .collect::<Vec<_>>();
+  let next = levels
+    .iter()
+    .position(|level| level.name == current)
+    .map_or(0, |index| index + 1);
+  levels.into_iter().nth(next)
+}
+
+/// What the replay recorder needs of the playfield right now, see `FrameCapture`.
+fn capture_frame(world: &World) -> FrameCapture {
+  world.exec(
+    |(entities, paddles, bricks, balls, movers, transforms, rotation): (
+      Entities,
+      ReadStorage<Paddle>,
+      ReadStorage<Brick>,
+      ReadStorage<Ball>,
+      ReadStorage<Mover>,
+      ReadStorage<Transform>,
+      Read<FieldRotation>,
+    )| {
+      let moving = (&entities, &transforms, paddles.maybe(), balls.maybe(), movers.maybe())
+        .join()
+        .filter(|(_, _, paddle, ball, mover)| paddle.is_some() || ball.is_some() || mover.is_some())
+        .map(|(entity, transform, ..)| {
+          let (translation, angle) = (transform.translation(), transform.rotation().euler_angles().2);
+          (entity, (translation.x, translation.y, angle))
+        })
+        .collect::<Vec<_>>();
+      let mut cast = (&entities, &bricks)
+        .join()
+        .map(|(entity, brick)| (entity, brick.hp))
+        .chain(moving.iter().map(|&(entity, _)| (entity, 0)))
+        .collect::<Vec<_>>();
+      cast.sort_unstable();
+      FrameCapture {
+        cast,
+        moving,
+        field_angle: rotation.angle,
+      }
+    },
+  )
+}
+
+/// Keeps the playfield as it is right now in the gallery, under `title`.
+fn capture_highlight(world: &World, title: &str, level: &str) {
+  let (bricks, movers) = capture_rects(world);
+  let rects = bricks.into_iter().chain(movers).collect();
+  Highlight::new(title, level, world.read_resource::<Score>().points, rects).save();
+}
+
+/// Deletes a save file, a file that doesn't exist is already reset.
+fn remove_save_file(path: &Path) -> std::io::Result<()> {
+  match fs::remove_file(path) {
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+    result => result,
+  }
+}
+
+fn set_hidden(world: &World, entity: Option<Entity>, hidden: bool) {
+  if let Some(entity) = entity {
+    let mut hidden_storage = world.write_storage::<HiddenPropagate>();
+    if hidden {
+      hidden_storage
+        .insert(entity, HiddenPropagate::new())
+        .expect("Couldn't hide ui entity!");
+    } else {
+      hidden_storage.remove(entity);
+    }
+  }
+}
+
+/// Replaces `thumbnail` with the thumbnail of `level`, shown in the top right corner of the level menus.
+fn show_thumbnail(world: &mut World, thumbnail: &mut Option<Entity>, level: Option<&Level>) {
+  if let Some(e) = thumbnail.take() {
+    world.delete_entity(e).expect("Failed to remove level thumbnail!");
+  }
+  let level = match level {
+    Some(level) => level,
+    None => return,
+  };
+  let path = thumbnail::path(level, |brick| match brick {
+    SPLIT_BRICK => SPLIT_BRICK_COLOR,
+    ARMORED_BRICK => ARMORED_BRICK_COLOR,
+    BUMPER => BUMPER_COLOR,
+    GATE_UP | GATE_DOWN => GATE_COLOR,
+    DOOR => DOOR_COLOR,
+    SWITCH_BRICK => SWITCH_BRICK_COLOR,
+    METAL_BRICK => METAL_BRICK_COLOR,
+    _ => [TEXT_COLOR[0], TEXT_COLOR[1], TEXT_COLOR[2]],
+  });
+  let path = match path {
+    Some(path) => path,
+    None => return,
+  };
+  let texture = {
+    let loader = world.read_resource::<Loader>();
+    loader.load(
+      path,
+      ImageFormat::default(),
+      (),
+      &world.read_resource::<AssetStorage<Texture>>(),
+    )
+  };
+  let (width, height) = thumbnail::dimensions(level);
+  let transform = UiTransform::new(
+    "level_thumbnail".to_string(),
+    Anchor::TopRight,
+    Anchor::TopRight,
+    -20.,
+    -20.,
+    1.,
+    width as f32 * THUMBNAIL_SCALE,
+    height as f32 * THUMBNAIL_SCALE,
+  );
+  *thumbnail = Some(
+    world
+      .create_entity()
+      .with(transform)
+      .with(UiImage::Texture(texture))
+      .build(),
+  );
+}
+
+/// Removes everything a `PlayState` spawned and moves the camera back to the bottom of the playfield.
+fn clear_playfield(world: &mut World) {
+  world.exec(
+    |(
+      entities,
+      paddles,
+      bricks,
+      balls,
+      bumpers,
+      gates,
+      movers,
+      ghosts,
+      intercepts,
+      aims,
+      glows,
+      cameras,
+      backgrounds,
+      mut transforms,
+      mut tints,
+    ): (
+      Entities,
+      ReadStorage<Paddle>,
+      ReadStorage<Brick>,
+      ReadStorage<Ball>,
+      ReadStorage<Bumper>,
+      ReadStorage<Gate>,
+      ReadStorage<Mover>,
+      ReadStorage<Ghost>,
+      ReadStorage<InterceptMarker>,
+      ReadStorage<AimMarker>,
+      ReadStorage<Glow>,
+      ReadStorage<Camera>,
+      ReadStorage<Background>,
+      WriteStorage<Transform>,
+      WriteStorage<Tint>,
+    )| {
+      for (e, _) in (&*entities, &paddles).join() {
+        entities.delete(e).expect("Couldn't delete paddle!");
+      }
+      for (e, _) in (&*entities, &bricks).join() {
+        entities.delete(e).expect("Couldn't delete brick!");
+      }
+      for (e, _) in (&*entities, &balls).join() {
+        entities.delete(e).expect("Couldn't delete ball!");
+      }
+      for (e, _) in (&*entities, &bumpers).join() {
+        entities.delete(e).expect("Couldn't delete bumper!");
+      }
+      for (e, _) in (&*entities, &gates).join() {
+        entities.delete(e).expect("Couldn't delete gate!");
+      }
+      for (e, _) in (&*entities, &movers).join() {
+        entities.delete(e).expect("Couldn't delete mover!");
+      }
+      for (e, _) in (&*entities, &ghosts).join() {
+        entities.delete(e).expect("Couldn't delete ghost!");
+      }
+      for (e, _) in (&*entities, &intercepts).join() {
+        entities.delete(e).expect("Couldn't delete intercept marker!");
+      }
+      for (e, _) in (&*entities, &aims).join() {
+        entities.delete(e).expect("Couldn't delete aim marker!");
+      }
+      for (e, _) in (&*entities, &glows).join() {
+        entities.delete(e).expect("Couldn't delete glow!");
+      }
+      for (transform, _) in (&mut transforms, &cameras).join() {
+        transform.set_translation_x(VIRTUAL_WIDTH / 2.);
+        transform.set_translation_y(VIRTUAL_HEIGHT / 2.);
+        transform.set_scale(Vector3::new(1., 1., 1.));
+      }
+      for (e, background) in (&*entities, &backgrounds).join() {
+        tints.remove(e);
+        if let Some(transform) = transforms.get_mut(e) {
+          transform.set_translation_x(VIRTUAL_WIDTH / 2.);
+          transform.set_translation_y(VIRTUAL_HEIGHT / 2.);
+          transform.set_scale(Vector3::new(background.scale.0, background.scale.1, 1.));
+        }
+      }
+    },
+  );
+  world.maintain();
+  world.insert(Playfield::default());
+}
+
+fn export_level(world: &World) {
+  let level = world.read_resource::<Level>();
+  match level.to_code() {
+    Ok(code) => {
+      info!("Level code for '{}': {}", level.name, code);
+      if let Err(e) = ClipboardContext::new().and_then(|mut ctx| ctx.set_contents(code)) {
+        warn!("Couldn't copy the level code to the clipboard: {}", e);
+      }
+      play_sound_in_state(world, SoundType::Confirm);
+    }
+    Err(e) => warn!("Couldn't export level '{}': {}", level.name, e),
+  }
+}
+
+/// Feeds a window event into the `TouchInput` resource and returns the position in virtual coordinates when the
+/// event finished a tap.
+fn handle_touch(world: &World, event: &Event) -> Option<(f32, f32)> {
+  let touch = match event {
+    Event::WindowEvent {
+      event: WindowEvent::Touch(touch),
+      ..
+    } => touch,
+    _ => return None,
+  };
+  let Touch {
+    id, phase, location, ..
+  } = *touch;
+  let (x, y) = {
+    let dimensions = world.read_resource::<ScreenDimensions>();
+    let position = location.to_physical(dimensions.hidpi_factor());
+    (
+      position.x as f32 / dimensions.width() * VIRTUAL_WIDTH,
+      VIRTUAL_HEIGHT - position.y as f32 / dimensions.height() * VIRTUAL_HEIGHT,
+    )
+  };
+  let mut touch_input = world.write_resource::<TouchInput>();
+
+  match phase {
+    TouchPhase::Started => {
+      touch_input.tap_start = Some((id, x, y));
+      if y < VIRTUAL_HEIGHT / 2. {
+        touch_input.drag_x = Some(x);
+      }
+      None
+    }
+    TouchPhase::Moved => {
+      if touch_input.drag_x.is_some() || y < VIRTUAL_HEIGHT / 2. {
+        touch_input.drag_x = Some(x);
+      }
+      None
+    }
+    TouchPhase::Ended | TouchPhase::Cancelled => {
+      touch_input.drag_x = None;
+      match touch_input.tap_start.take() {
+        Some((start_id, start_x, start_y))
+          if start_id == id
+            && phase == TouchPhase::Ended
+            && (x - start_x).abs() < TOUCH_TAP_DISTANCE
+            && (y - start_y).abs() < TOUCH_TAP_DISTANCE =>
+        {
+          Some((x, y))
+        }
+        _ => None,
+      }
+    }
+  }
+}
+
+/// Angle in radians from straight up of a serve held for `wind_up` seconds, the aim sweeps from side to side.
+fn serve_aim(wind_up: f32) -> f32 {
+  SERVE_AIM_SWING * (wind_up / SERVE_AIM_PERIOD * std::f32::consts::PI * 2.).sin()
+}
+
+/// How much of `SERVE_SPEED_BONUS` a serve held for `wind_up` seconds gets.
+fn serve_power(wind_up: f32) -> f32 {
+  (wind_up / SERVE_CHARGE_SECONDS).min(1.)
+}
+
+fn get_brick_sprite(world: &World) -> Option<(SpriteSheetHandle, usize)> {
+  let sprite_sheet_map = world.read_resource::<SpriteSheetMap>();
+  sprite_sheet_map
+    .0
+    .iter()
+    .find_map(|(asset_type, handle)| match asset_type {
+      AssetType::PaddleSmall(sprite_pos) => Some((handle.clone(), *sprite_pos)),
+      _ => None,
+    })
+}
+
+/// The sheet holding every paddle and ball skin.
+fn get_skin_sprite_sheet(world: &World) -> Option<SpriteSheetHandle> {
+  let sprite_sheet_map = world.read_resource::<SpriteSheetMap>();
+  sprite_sheet_map
+    .0
+    .iter()
+    .find_map(|(asset_type, handle)| match asset_type {
+      AssetType::Ball(_) => Some(handle.clone()),
+      _ => None,
+    })
+}
+
+/// Center of the brick in `column` and `row`, rows are counted from the top of the playfield and the columns are
+/// centered horizontally.
+fn brick_translation(
+  playfield_width: f32,
+  playfield_height: f32,
+  columns: usize,
+  column: usize,
+  row: usize,
+  width: f32,
+  height: f32,
+) -> (f32, f32) {
+  let columns = columns as f32;
+  let left = (playfield_width - columns * width - (columns - 1.) * 2.) / 2. + width / 2.;
+  (
+    left + column as f32 * (width + 2.),
+    playfield_height - BRICK_TOP_OFFSET - row as f32 * (height + 4.),
+  )
+}
+
+/// Creates the bricks of `level` on a playfield `playfield_width` by `playfield_height`, plain bricks are tinted
+/// with `color`.
+fn spawn_bricks(
+  world: &mut World,
+  level: &Level,
+  playfield_width: f32,
+  playfield_height: f32,
+  color: [f32; 3],
+  sprite_sheet_handle: &SpriteSheetHandle,
+  sprite_pos: usize,
+) {
+  let (width, height) = get_texture_dimensions(world, sprite_sheet_handle, sprite_pos);
+  let multiplier = world.read_resource::<GameMode>().mode.score_multiplier();
+  // Bumpers fill the height of a row, gap included.
+  let bumper_radius = (height + 4.) / 2.;
+  let bumper_sprite = get_skin_sprite_sheet(world).map(|sheet| {
+    let sprite = skins::BALL_SKINS[0].sprite;
+    let (sprite_width, _) = get_texture_dimensions(world, &sheet, sprite);
+    (sheet, sprite, bumper_radius * 2. / sprite_width)
+  });
+  for (x, y, brick) in level.bricks() {
+    let (brick_x, brick_y) = brick_translation(playfield_width, playfield_height, level.columns(), x, y, width, height);
+    let [r, g, b] = match brick {
+      SPLIT_BRICK => SPLIT_BRICK_COLOR,
+      ARMORED_BRICK => ARMORED_BRICK_COLOR,
+      BUMPER => BUMPER_COLOR,
+      GATE_UP | GATE_DOWN => GATE_COLOR,
+      DOOR => DOOR_COLOR,
+      SWITCH_BRICK => SWITCH_BRICK_COLOR,
+      METAL_BRICK => METAL_BRICK_COLOR,
+      _ => color,
+    };
+    let properties = level.properties.get(&(x, y)).cloned().unwrap_or_default();
+    let points = properties.score * multiplier;
+    if brick == BUMPER {
+      if let Some((sheet, sprite, scale)) = &bumper_sprite {
+        let mut transform = Layer::Playfield.transform(brick_x, brick_y);
+        transform.set_scale(Vector3::new(*scale, *scale, 1.));
+        world
+          .create_entity()
+          .with(Bumper {
+            radius: bumper_radius,
+            scale: *scale,
+            flash: 0.,
+          })
+          .with(SpriteRender::new(sheet.clone(), *sprite))
+          .with(transform)
+          .with(Tint(Srgba::new(r, g, b, 1.)))
+          .with(Points(points))
+          .build();
+      }
+      continue;
+    }
+    if brick == GATE_UP || brick == GATE_DOWN {
+      let mut transform = Layer::Playfield.transform(brick_x, brick_y);
+      // Gates are drawn as half height bricks.
+      transform.set_scale(Vector3::new(1., 0.5, 1.));
+      world
+        .create_entity()
+        .with(Gate {
+          direction: if brick == GATE_UP { 1. } else { -1. },
+          width,
+          height: height * 0.5,
+        })
+        .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
+        .with(transform)
+        .with(Tint(Srgba::new(r, g, b, 0.6)))
+        .build();
+      continue;
+    }
+    let mut builder = world
+      .create_entity()
+      .with(Brick {
+        width,
+        height,
+        tier: brick,
+        hp: properties.hp,
+        score: points,
+      })
+      .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
+      .with(Layer::Playfield.transform(brick_x, brick_y))
+      .with(Tint(Srgba::new(r, g, b, 1.)));
+    match brick {
+      SPLIT_BRICK => builder = builder.with(SplitBrick),
+      ARMORED_BRICK => builder = builder.with(Armored),
+      DOOR => builder = builder.with(Door { link: properties.link }),
+      SWITCH_BRICK => builder = builder.with(Switch { link: properties.link }),
+      METAL_BRICK => builder = builder.with(Metal),
+      _ => {}
+    }
+    builder.build();
+  }
+}
+
+/// Creates the mini-paddles of a bonus stage on a playfield `playfield_width` by `playfield_height`, drawn as
+/// flattened bricks.
+fn spawn_movers(
+  world: &mut World,
+  movers: &[MoverPath],
+  playfield_width: f32,
+  playfield_height: f32,
+  sprite_sheet_handle: &SpriteSheetHandle,
+  sprite_pos: usize,
+) {
+  let (width, height) = get_texture_dimensions(world, sprite_sheet_handle, sprite_pos);
+  let [r, g, b] = MOVER_COLOR;
+  for path in movers {
+    let mover = Mover {
+      points: path
+        .points
+        .iter()
+        .map(|&(x, y)| (x * playfield_width, y * playfield_height))
+        .collect(),
+      speed: path.speed,
+      width: path.width,
+      height: height * 0.75,
+      travelled: 0.,
+      velocity: (0., 0.),
+    };
+    let (x, y) = mover.position();
+    let mut transform = Layer::Playfield.transform(x, y);
+    transform.set_scale(Vector3::new(path.width / width, 0.75, 1.));
+    world
+      .create_entity()
+      .with(mover)
+      .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
+      .with(transform)
+      .with(Tint(Srgba::new(r, g, b, 1.)))
+      .build();
+  }
+}
+
+/// Creates the brick rings of wave `number` of the radial mode, tinted with `color`.
+fn spawn_rings(
+  world: &mut World,
+  number: u32,
+  color: [f32; 3],
+  sprite_sheet_handle: &SpriteSheetHandle,
+  sprite_pos: usize,
+) {
+  let (width, height) = get_texture_dimensions(world, sprite_sheet_handle, sprite_pos);
+  let properties = BrickProperties::default();
+  let points = properties.score * world.read_resource::<GameMode>().mode.score_multiplier();
+  let [r, g, b] = color;
+  for (x, y, angle) in radial::rings(RADIAL_CENTER, width, number) {
+    let mut transform = Layer::Playfield.transform(x, y);
+    transform.set_rotation_2d(angle);
+    world
+      .create_entity()
+      .with(Brick {
+        width,
+        height,
+        tier: PLAIN_BRICK,
+        hp: properties.hp,
+        score: points,
+      })
+      .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
+      .with(transform)
+      .with(Tint(Srgba::new(r, g, b, 1.)))
+      .build();
+  }
+}
+
+fn get_texture_dimensions(world: &World, sprite_sheet_handle: &SpriteSheetHandle, sprite_pos: usize) -> (f32, f32) {
+  let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
+  let spritesheet = sprite_sheet_store
+    .get(&sprite_sheet_handle)
+    .expect("Couldn't find the handle for the paddle sprite!");
+  (
+    spritesheet.sprites[sprite_pos].width,
+    spritesheet.sprites[sprite_pos].height,
+  )
+}
+
+///
+/// systems
+///
+
+#[derive(Default, SystemDesc)]
+struct PaddleSystem {
+  clock: f32,
+  last_tap: Option<(f32, f32)>,
+  previous_horizontal: f32,
+  dash_was_down: bool,
+}
+
+impl PaddleSystem {
+  /// Returns the direction of a dash started by double tapping a direction or by the dedicated dash key.
+  fn dash_trigger(&mut self, horizontal: f32, dash_down: bool, velocity: f32) -> Option<f32> {
+    let mut trigger = None;
+    if horizontal != 0.0 && self.previous_horizontal == 0.0 {
+      let direction = horizontal.signum();
+      if let Some((last_direction, last_time)) = self.last_tap {
+        if last_direction == direction && self.clock - last_time <= DASH_DOUBLE_TAP_WINDOW {
+          trigger = Some(direction);
+        }
+      }
+      self.last_tap = Some((direction, self.clock));
+    }
+    if dash_down && !self.dash_was_down {
+      let direction = if horizontal != 0.0 { horizontal } else { velocity };
+      if direction != 0.0 {
+        trigger = Some(direction.signum());
+      }
+    }
+    self.previous_horizontal = horizontal;
+    self.dash_was_down = dash_down;
+    trigger
+  }
+}
+
+impl<'a> System<'a> for PaddleSystem {
+  type SystemData = (
+    WriteStorage<'a, Transform>,
+    WriteStorage<'a, Paddle>,
+    ReadStorage<'a, Player>,
+    WriteStorage<'a, ArcPaddle>,
+    Read<'a, InputHandler<StringBindings>>,
+    Read<'a, InputAssignment>,
+    Read<'a, TouchInput>,
+    Read<'a, GameConfig>,
+    Read<'a, GameMode>,
+    Read<'a, Settings>,
+    Read<'a, Playfield>,
+    ReadExpect<'a, ScreenDimensions>,
+    Write<'a, DashState>,
+    Write<'a, AwayTimers>,
+    Read<'a, Time>,
+  );
+
+  fn run(
+    &mut self,
+    (
+      mut transforms,
+      mut paddles,
+      player,
+      mut arc_paddles,
+      input,
+      assignment,
+      touch_input,
+      config,
+      mode,
+      settings,
+      playfield,
+      dimensions,
+      mut dash,
+      mut away,
+      time,
+    ): Self::SystemData,
+  ) {
+    let config = &config.paddle;
+    let delta_seconds = time.fixed_seconds();
+    self.clock += delta_seconds;
+    dash.active = (dash.active - delta_seconds).max(0.);
+    dash.cooldown = (dash.cooldown - delta_seconds).max(0.);
+    let mouse_x = if settings.controls.follows_mouse() {
+      input
+        .mouse_position()
+        .map(|(x, _)| x / dimensions.width() * VIRTUAL_WIDTH)
+    } else {
+      None
+    };
+
+    for (transform, paddle, player, mut arc) in
+      (&mut transforms, &mut paddles, &player, (&mut arc_paddles).maybe()).join()
+    {
+      let paddle_x = arc.as_ref().map_or(transform.translation().x, |arc| arc.track);
+      let horizontal = assignment.horizontal(&input, player.seat, HORIZONTAL_AXES[player.seat]);
+      // Dashes, the mouse and touch belong to the first player.
+      let first = player.seat == 0;
+      let dash_down = input.action_is_down("dash").unwrap_or(false);
+
+      if first {
+        if let Some(direction) = self.dash_trigger(horizontal, dash_down, paddle.velocity) {
+          if dash.cooldown <= 0. {
+            dash.active = DASH_DURATION;
+            dash.direction = direction;
+            dash.cooldown = DASH_COOLDOWN;
+            self.last_tap = None;
+          }
+        }
+      }
+
+      let velocity = if first && dash.active > 0. {
+        dash.direction * DASH_SPEED
+      } else if let Some(target_x) = touch_input
+        .drag_x
+        .or(mouse_x)
+        .filter(|_| first)
+        .map(|x| playfield.screen_x(x))
+      {
+        ((target_x - paddle_x) / delta_seconds)
+          .min(config.max_speed)
+          .max(-config.max_speed)
+      } else if config.accelerated {
+        let velocity = if horizontal != 0.0 {
+          paddle.velocity + horizontal * config.acceleration * delta_seconds
+        } else {
+          paddle.velocity * (1. - config.friction * delta_seconds).max(0.)
+        };
+        velocity.min(config.max_speed).max(-config.max_speed)
+      } else {
+        horizontal * config.max_speed
+      };
+
+      let (min_x, max_x) = match arc {
+        Some(_) => {
+          let half_length = track_half_length(RADIAL_PADDLE_RADIUS);
+          (RADIAL_CENTER.0 - half_length, RADIAL_CENTER.0 + half_length)
+        }
+        None => {
+          let lane = playfield.width / mode.mode.players() as f32;
+          (
+            lane * player.seat as f32 + paddle.width / 2.,
+            lane * (player.seat + 1) as f32 - paddle.width / 2.,
+          )
+        }
+      };
+      let x = (paddle_x + velocity * delta_seconds).min(max_x).max(min_x);
+      match arc.as_mut() {
+        Some(arc) => {
+          arc.track = x;
+          let (arc_x, arc_y, angle) = arc_placement(RADIAL_CENTER, RADIAL_PADDLE_RADIUS, x - RADIAL_CENTER.0);
+          transform.set_translation_x(arc_x);
+          transform.set_translation_y(arc_y);
+          transform.set_rotation_2d(angle);
+        }
+        None => transform.set_translation_x(x),
+      }
+      paddle.velocity = (x - paddle_x) / delta_seconds;
+
+      let served = assignment.serve(&input, player.seat, SERVE_ACTIONS[player.seat]);
+      if let Some(idle) = away.idle.get_mut(player.seat) {
+        *idle = if horizontal != 0.0 || served || x != paddle_x {
+          0.
+        } else {
+          *idle + delta_seconds
+        };
+      }
+    }
+  }
+}
+
+#[derive(Default, SystemDesc)]
+struct GhostSystem;
+
+impl<'a> System<'a> for GhostSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, Player>,
+    WriteStorage<'a, Transform>,
+    WriteStorage<'a, SpriteRender>,
+    WriteStorage<'a, Ghost>,
+    WriteStorage<'a, Tint>,
+    WriteStorage<'a, Transparent>,
+    Read<'a, DashState>,
+    Read<'a, Time>,
+  );
+
+  fn run(
+    &mut self,
+    (
+      entities,
+      players,
+      mut transforms,
+      mut sprites,
+      mut ghosts,
+      mut tints,
+      mut transparents,
+      dash,
+      time,
+    ): Self::SystemData,
+  ) {
+    for (e, ghost, tint) in (&*entities, &mut ghosts, &mut tints).join() {
+      ghost.lifetime -= time.delta_seconds();
+      if ghost.lifetime <= 0. {
+        entities.delete(e).expect("Couldn't delete ghost!");
+      } else {
+        tint.0.alpha = ghost.lifetime / ghost.duration * 0.5;
+      }
+    }
+
+    if dash.active > 0. {
+      let paddles = (&transforms, &sprites, &players)
+        .join()
+        .map(|(transform, sprite, _)| (transform.clone(), sprite.clone()))
+        .collect::<Vec<_>>();
+      for (mut transform, sprite) in paddles {
+        transform.translation_mut().z = Layer::Effects.z();
+        entities
+          .build_entity()
+          .with(transform, &mut transforms)
+          .with(sprite, &mut sprites)
+          .with(
+            Ghost {
+              lifetime: DASH_GHOST_LIFETIME,
+              duration: DASH_GHOST_LIFETIME,
+            },
+            &mut ghosts,
+          )
+          .with(Tint(Srgba::new(0.4, 1., 1., 0.5)), &mut tints)
+          .with(Transparent, &mut transparents)
+          .build();
+      }
+    }
+  }
+}
+
+/// Destroys the lowest row of bricks when a bomb was triggered, leaving bright ghosts behind.
+#[derive(Default, SystemDesc)]
+struct BombSystem;
+
+impl<'a> System<'a> for BombSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, Brick>,
+    ReadStorage<'a, BonusBrick>,
+    ReadStorage<'a, Armored>,
+    ReadStorage<'a, Metal>,
+    Read<'a, Breaker>,
+    WriteStorage<'a, Transform>,
+    WriteStorage<'a, SpriteRender>,
+    WriteStorage<'a, Ghost>,
+    WriteStorage<'a, Tint>,
+    WriteStorage<'a, Transparent>,
+    Write<'a, EnergyMeter>,
+    Write<'a, RumbleQueue>,
+    Write<'a, HitStop>,
+    Write<'a, Score>,
+    Write<'a, GameEvents>,
+    Read<'a, Settings>,
+    Sounds<'a>,
+  );
+
+  fn run(
+    &mut self,
+    (
+      entities,
+      bricks,
+      bonus_bricks,
+      armored,
+      metal,
+      breaker,
+      mut transforms,
+      mut sprites,
+      mut ghosts,
+      mut tints,
+      mut transparents,
+      mut energy,
+      mut rumble,
+      mut hit_stop,
+      mut score,
+      mut game_events,
+      settings,
+      mut sounds,
+    ): Self::SystemData,
+  ) {
+    if !energy.bomb_pending {
+      return;
+    }
+    energy.bomb_pending = false;
+
+    let targets = (&*entities, &bricks, !&bonus_bricks, !&metal, &transforms, &sprites)
+      .join()
+      .filter(|(e, _, _, _, _, _)| breaker.is_open() || armored.get(*e).is_none())
+      .map(|(e, brick, _, _, transform, sprite)| (e, brick.score, transform.clone(), sprite.clone()))
+      .collect::<Vec<_>>();
+    let row_y = match targets
+      .iter()
+      .map(|(_, _, transform, _)| transform.translation().y)
+      .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+    {
+      Some(row_y) => row_y,
+      None => return,
+    };
+
+    energy.charge = 0.;
+    let mut destroyed = 0;
+    for (e, points, mut transform, sprite) in targets {
+      if (transform.translation().y - row_y).abs() > BOMB_ROW_TOLERANCE {
+        continue;
+      }
+      entities.delete(e).expect("Couldn't delete brick hit by a bomb!");
+      destroyed += 1;
+      // Only the first player can set off the bomb.
+      score.add(0, points);
+      transform.translation_mut().z = Layer::Effects.z();
+      if settings.glow {
+        let mut light = transform.clone();
+        light.set_scale(Vector3::new(GLOW_SCALE, GLOW_SCALE, 1.));
+        entities
+          .build_entity()
+          .with(light, &mut transforms)
+          .with(sprite.clone(), &mut sprites)
+          .with(
+            Ghost {
+              lifetime: BOMB_GHOST_LIFETIME,
+              duration: BOMB_GHOST_LIFETIME,
+            },
+            &mut ghosts,
+          )
+          .with(Tint(Srgba::new(1., 0.7, 0.3, GLOW_MAX_ALPHA)), &mut tints)
+          .with(Transparent, &mut transparents)
+          .build();
+      }
+      entities
+        .build_entity()
+        .with(transform, &mut transforms)
+        .with(sprite, &mut sprites)
+        .with(
+          Ghost {
+            lifetime: BOMB_GHOST_LIFETIME,
+            duration: BOMB_GHOST_LIFETIME,
+          },
+          &mut ghosts,
+        )
+        .with(Tint(Srgba::new(1., 0.9, 0.4, 0.5)), &mut tints)
+        .with(Transparent, &mut transparents)
+        .build();
+    }
+    play_sound_in_system(&mut sounds, SoundType::BrickHit(0));
+    rumble.0.push(RumbleEvent::BrickDestroyed);
+    game_events.0.push(GameEvent::Bomb(destroyed));
+    hit_stop.trigger(&settings);
+  }
+}
+
+/// Draws a soft light sprite over every ball that gets brighter the faster the ball goes. The sprites are alpha
+/// blended, `RenderFlat2D` has no additive blending without a custom render pass.
+#[derive(Default, SystemDesc)]
+struct GlowSystem;
+
+impl<'a> System<'a> for GlowSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, Ball>,
+    WriteStorage<'a, Glow>,
+    WriteStorage<'a, Transform>,
+    WriteStorage<'a, SpriteRender>,
+    WriteStorage<'a, Tint>,
+    WriteStorage<'a, Transparent>,
+    Read<'a, Settings>,
+    Read<'a, GameMode>,
+  );
+
+  fn run(
+    &mut self,
+    (
+      entities,
+      balls,
+      mut glows,
+      mut transforms,
+      mut sprites,
+      mut tints,
+      mut transparents,
+      settings,
+      mode,
+    ): Self::SystemData,
+  ) {
+    let glow_enabled = settings.glow && !mode.mode.calm();
+    for (e, glow) in (&*entities, &glows).join() {
+      let source = transforms
+        .get(glow.source)
+        .filter(|_| glow_enabled && entities.is_alive(glow.source))
+        .map(|transform| *transform.translation());
+      let heat = balls
+        .get(glow.source)
+        .map_or(0., |ball| {
+          (ball.speed - BALL_VELOCITY) / (BALL_MAX_VELOCITY - BALL_VELOCITY)
+        })
+        .max(0.)
+        .min(1.);
+      match source {
+        Some(translation) => {
+          if let Some(transform) = transforms.get_mut(e) {
+            transform.set_translation_xyz(translation.x, translation.y, Layer::Effects.z());
+          }
+          if let Some(tint) = tints.get_mut(e) {
+            tint.0.alpha = GLOW_MIN_ALPHA + (GLOW_MAX_ALPHA - GLOW_MIN_ALPHA) * heat;
+          }
+        }
+        None => entities.delete(e).expect("Couldn't delete glow!"),
+      }
+    }
+
+    if !glow_enabled {
+      return;
+    }
+    let lit = (&glows).join().map(|glow| glow.source).collect::<HashSet<_>>();
+    let new_sources = (&*entities, &balls, &transforms, &sprites)
+      .join()
+      .filter(|(e, _, _, _)| !lit.contains(e))
+      .map(|(e, ball, transform, sprite)| (e, ball.color, *transform.translation(), sprite.clone()))
+      .collect::<Vec<_>>();
+    for (source, [r, g, b], translation, sprite) in new_sources {
+      let mut transform = Layer::Effects.transform(translation.x, translation.y);
+      transform.set_scale(Vector3::new(GLOW_SCALE, GLOW_SCALE, 1.));
+      entities
+        .build_entity()
+        .with(Glow { source }, &mut glows)
+        .with(transform, &mut transforms)
+        .with(sprite, &mut sprites)
+        .with(Tint(Srgba::new(r, g, b, GLOW_MIN_ALPHA)), &mut tints)
+        .with(Transparent, &mut transparents)
+        .build();
+    }
+  }
+}
+
+/// Breaks destroyed bricks into small fragments that fall down and fade. Falling fragments can bounce off the
+/// paddle, only `DEBRIS_PADDLE_CHECKS` of them are tested per frame to keep big explosions cheap.
+#[derive(Default, SystemDesc)]
+struct DebrisSystem;
+
+impl<'a> System<'a> for DebrisSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, Paddle>,
+    ReadStorage<'a, Player>,
+    WriteStorage<'a, Debris>,
+    WriteStorage<'a, Ghost>,
+    WriteStorage<'a, Transform>,
+    WriteStorage<'a, SpriteRender>,
+    WriteStorage<'a, Tint>,
+    WriteStorage<'a, Transparent>,
+    Write<'a, ShatterQueue>,
+    Read<'a, GameConfig>,
+    Read<'a, GameMode>,
+    Read<'a, Time>,
+  );
+
+  fn run(
+    &mut self,
+    (
+      entities,
+      paddles,
+      players,
+      mut debris,
+      mut ghosts,
+      mut transforms,
+      mut sprites,
+      mut tints,
+      mut transparents,
+      mut shatter,
+      config,
+      mode,
+      time,
+    ): Self::SystemData,
+  ) {
+    let mut rng = rand::thread_rng();
+    if mode.mode.calm() {
+      shatter.0.clear();
+    }
+    for (brick_transform, sprite) in shatter.0.drain(..) {
+      for _ in 0..config.debris.pieces {
+        let mut transform = brick_transform.clone();
+        transform.translation_mut().z = Layer::Effects.z();
+        transform.set_scale(Vector3::new(DEBRIS_SCALE, DEBRIS_SCALE, 1.));
+        entities
+          .build_entity()
+          .with(
+            Debris {
+              velocity: Vector3::new(rng.gen_range(-60., 60.), rng.gen_range(20., 80.), 0.),
+            },
+            &mut debris,
+          )
+          .with(
+            Ghost {
+              lifetime: DEBRIS_LIFETIME,
+              duration: DEBRIS_LIFETIME,
+            },
+            &mut ghosts,
+          )
+          .with(transform, &mut transforms)
+          .with(sprite.clone(), &mut sprites)
+          .with(Tint(Srgba::new(1., 1., 1., 0.5)), &mut tints)
+          .with(Transparent, &mut transparents)
+          .build();
+      }
+    }
+
+    let paddle_rect = (&paddles, &players, &transforms)
+      .join()
+      .next()
+      .map(|(paddle, _, transform)| {
+        let (x, y) = (transform.translation().x, transform.translation().y);
+        (
+          x - paddle.width / 2.,
+          y - paddle.height / 2.,
+          x + paddle.width / 2.,
+          y + paddle.height / 2.,
+        )
+      });
+    let mut checks = 0;
+    for (fragment, transform) in (&mut debris, &mut transforms).join() {
+      fragment.velocity.y -= DEBRIS_GRAVITY * time.delta_seconds();
+      transform.prepend_translation(fragment.velocity * time.delta_seconds());
+      if !config.debris.paddle_bounce || fragment.velocity.y >= 0. || checks >= DEBRIS_PADDLE_CHECKS {
+        continue;
+      }
+      checks += 1;
+      if let Some((left, bottom, right, top)) = paddle_rect {
+        let (x, y) = (transform.translation().x, transform.translation().y);
+        if point_in_rect(x, y, left, bottom, right, top) {
+          fragment.velocity.y = -fragment.velocity.y * DEBRIS_BOUNCE;
+        }
+      }
+    }
+  }
+}
+
+/// Draws a row of dots from each ball whose serve is being wound up towards where it will be sent, they get more
+/// opaque as the serve charges.
+#[derive(Default, SystemDesc)]
+struct ServeAimSystem;
+
+impl<'a> System<'a> for ServeAimSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, Ball>,
+    WriteStorage<'a, AimMarker>,
+    WriteStorage<'a, Transform>,
+    WriteStorage<'a, SpriteRender>,
+    WriteStorage<'a, Tint>,
+    WriteStorage<'a, Transparent>,
+  );
+
+  fn run(
+    &mut self,
+    (entities, balls, mut markers, mut transforms, mut sprites, mut tints, mut transparents): Self::SystemData,
+  ) {
+    for (e, marker) in (&*entities, &markers).join() {
+      if balls.get(marker.ball).map_or(true, |ball| ball.wind_up.is_none()) {
+        entities.delete(e).expect("Couldn't delete aim marker!");
+      }
+    }
+
+    let aims = (&*entities, &balls, &transforms, &sprites)
+      .join()
+      .filter_map(|(e, ball, transform, sprite)| {
+        ball.wind_up.map(|wind_up| {
+          (
+            e,
+            *transform.translation(),
+            sprite.clone(),
+            ball.color,
+            ball.radius,
+            wind_up,
+          )
+        })
+      })
+      .collect::<Vec<_>>();
+    for (ball_entity, translation, sprite, [r, g, b], radius, wind_up) in aims {
+      let (sin, cos) = serve_aim(wind_up).sin_cos();
+      let (min_alpha, max_alpha) = SERVE_AIM_ALPHA;
+      let alpha = min_alpha + (max_alpha - min_alpha) * serve_power(wind_up);
+      let dots = (&*entities, &markers)
+        .join()
+        .filter(|(_, marker)| marker.ball == ball_entity)
+        .map(|(e, marker)| (marker.index, e))
+        .collect::<HashMap<_, _>>();
+      for index in 0..SERVE_AIM_DOTS {
+        let distance = radius + SERVE_AIM_SPACING * (index + 1) as f32;
+        let (x, y) = (translation.x + sin * distance, translation.y + cos * distance);
+        if let Some(&e) = dots.get(&index) {
+          if let Some(transform) = transforms.get_mut(e) {
+            transform.set_translation_xyz(x, y, Layer::Effects.z());
+          }
+          if let Some(tint) = tints.get_mut(e) {
+            tint.0.alpha = alpha;
+          }
+          continue;
+        }
+        let mut transform = Layer::Effects.transform(x, y);
+        transform.set_scale(Vector3::new(SERVE_AIM_SCALE, SERVE_AIM_SCALE, 1.));
+        entities
+          .build_entity()
+          .with(
+            AimMarker {
+              ball: ball_entity,
+              index,
+            },
+            &mut markers,
+          )
+          .with(transform, &mut transforms)
+          .with(sprite.clone(), &mut sprites)
+          .with(Tint(Srgba::new(r, g, b, alpha)), &mut tints)
+          .with(Transparent, &mut transparents)
+          .build();
+      }
+    }
+  }
+}
+
+/// Marks where a falling ball will cross the paddle line, an optional aid for beginners.
+#[derive(Default, SystemDesc)]
+struct InterceptSystem;
+
+impl<'a> System<'a> for InterceptSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, Ball>,
+    ReadStorage<'a, Paddle>,
+    ReadStorage<'a, Player>,
+    WriteStorage<'a, InterceptMarker>,
+    WriteStorage<'a, Transform>,
+    WriteStorage<'a, SpriteRender>,
+    WriteStorage<'a, Tint>,
+    WriteStorage<'a, Transparent>,
+    Read<'a, Settings>,
+    Read<'a, GameMode>,
+    Read<'a, Playfield>,
+  );
+
+  fn run(
+    &mut self,
+    (
+      entities,
+      balls,
+      paddles,
+      players,
+      mut markers,
+      mut transforms,
+      mut sprites,
+      mut tints,
+      mut transparents,
+      settings,
+      mode,
+      playfield,
+    ): Self::SystemData,
+  ) {
+    let paddle_top = (&paddles, &players, &transforms)
+      .join()
+      .next()
+      .map(|(paddle, _, transform)| transform.translation().y + paddle.height / 2.);
+    let prediction = (&balls, &transforms, &sprites)
+      .join()
+      .filter(|_| settings.assist && mode.mode.assists())
+      .find_map(|(ball, transform, sprite)| {
+        if ball.stuck {
+          return None;
+        }
+        let line = paddle_top? + ball.radius;
+        let translation = transform.translation();
+        predict_intercept(
+          translation.x,
+          translation.y,
+          &ball.direction,
+          ball.radius,
+          line,
+          playfield.width,
+        )
+        .map(|x| (x, line, sprite.clone(), ball.color))
+      });
+    let marker = (&*entities, &markers).join().next().map(|(e, _)| e);
+
+    match (marker, prediction) {
+      (Some(e), Some((x, y, _, _))) => {
+        if let Some(transform) = transforms.get_mut(e) {
+          transform.set_translation_xyz(x, y, Layer::Effects.z());
+        }
+      }
+      (None, Some((x, y, sprite, [r, g, b]))) => {
+        entities
+          .build_entity()
+          .with(InterceptMarker, &mut markers)
+          .with(Layer::Effects.transform(x, y), &mut transforms)
+          .with(sprite, &mut sprites)
+          .with(Tint(Srgba::new(r, g, b, INTERCEPT_MARKER_ALPHA)), &mut tints)
+          .with(Transparent, &mut transparents)
+          .build();
+      }
+      (Some(e), None) => entities.delete(e).expect("Couldn't delete intercept marker!"),
+      (None, None) => {}
+    }
+  }
+}
+
+/// Follows the ball vertically on levels taller than the screen. The paddle and the background stay anchored to
+/// the bottom of the view. Also zooms the view for the pulse of streamer mode.
+#[derive(Default, SystemDesc)]
+struct CameraFollowSystem;
+
+impl<'a> System<'a> for CameraFollowSystem {
+  type SystemData = (
+    WriteStorage<'a, Transform>,
+    ReadStorage<'a, Camera>,
+    ReadStorage<'a, Ball>,
+    ReadStorage<'a, Player>,
+    ReadStorage<'a, ArcPaddle>,
+    ReadStorage<'a, Background>,
+    Write<'a, Playfield>,
+    Read<'a, HitStop>,
+    Read<'a, Time>,
+  );
+
+  fn run(
+    &mut self,
+    (mut transforms, cameras, balls, players, arc_paddles, backgrounds, mut playfield, hit_stop, time): Self::SystemData,
+  ) {
+    let zoom = hit_stop.zoom() * playfield.zoom();
+    for (transform, _) in (&mut transforms, &cameras).join() {
+      // The mirror mutator flips the camera, the zoom keeps the flip.
+      let flip = transform.scale().x.signum();
+      transform.set_scale(Vector3::new(flip * zoom, zoom, 1.));
+      transform.set_translation_x(playfield.width / 2.);
+    }
+
+    let ball_y = match (&transforms, &balls).join().next() {
+      Some((transform, _)) => transform.translation().y,
+      None => return,
+    };
+    let half_height = playfield.view_height() / 2.;
+    let target_y = ball_y.min(playfield.height - half_height).max(half_height);
+    let follow = 1. - (-CAMERA_FOLLOW_SPEED * time.delta_seconds()).exp();
+
+    for (transform, _) in (&mut transforms, &cameras).join() {
+      let camera_y = transform.translation().y;
+      let camera_y = camera_y + (target_y - camera_y) * follow;
+      transform.set_translation_y(camera_y);
+      playfield.view_bottom = camera_y - half_height;
+    }
+    for (transform, _, _) in (&mut transforms, &players, !&arc_paddles).join() {
+      transform.set_translation_y(playfield.view_bottom + PADDLE_Y);
+    }
+    for (transform, background) in (&mut transforms, &backgrounds).join() {
+      transform.set_translation_x(playfield.width / 2.);
+      transform.set_translation_y(playfield.view_bottom + half_height);
+      transform.set_scale(Vector3::new(
+        background.scale.0 * playfield.zoom(),
+        background.scale.1 * playfield.zoom(),
+        1.,
+      ));
+    }
+  }
+}
+
+/// Shows how many sprites are on screen and how many draw calls `RenderFlat2D` needs for them. Opaque sprites are
+/// batched per texture, transparent ones are drawn back to front and start a new batch whenever the texture changes.
+/// With everything on the breakout atlas this should stay at one batch per pass.
+struct DebugOverlaySystem;
+
+impl<'a> System<'a> for DebugOverlaySystem {
+  type SystemData = (
+    UiFinder<'a>,
+    WriteStorage<'a, UiText>,
+    ReadStorage<'a, SpriteRender>,
+    ReadStorage<'a, Transform>,
+    ReadStorage<'a, Transparent>,
+    ReadStorage<'a, HiddenPropagate>,
+    Read<'a, AssetStorage<SpriteSheet>>,
+    Read<'a, DebugOverlay>,
+  );
+
+  fn run(
+    &mut self,
+    (finder, mut ui_text, sprites, transforms, transparents, hidden, sprite_sheets, overlay): Self::SystemData,
+  ) {
+    let text = match finder.find("debug").and_then(|entity| ui_text.get_mut(entity)) {
+      Some(text) => text,
+      None => return,
+    };
+    if !overlay.visible {
+      text.text.clear();
+      return;
+    }
+
+    let mut sprite_count = 0;
+    let mut opaque = HashSet::new();
+    let mut transparent = Vec::new();
+    for (sprite, transform, is_transparent, _) in (&sprites, &transforms, transparents.maybe(), !&hidden).join() {
+      sprite_count += 1;
+      let texture = sprite_sheets.get(&sprite.sprite_sheet).map(|sheet| sheet.texture.id());
+      if is_transparent.is_some() {
+        transparent.push((transform.translation().z, texture));
+      } else {
+        opaque.insert(texture);
+      }
+    }
+    let textures = opaque
+      .union(&transparent.iter().map(|(_, texture)| *texture).collect::<HashSet<_>>())
+      .count();
+    transparent.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    transparent.dedup_by_key(|(_, texture)| *texture);
+    text.text = format!(
+      "SPRITES {}  TEXTURES {}  DRAW CALLS {}",
+      sprite_count,
+      textures,
+      opaque.len() + transparent.len()
+    );
+  }
+}
+
+#[derive(Default, SystemDesc)]
+struct HudSystem;
+
+impl<'a> System<'a> for HudSystem {
+  type SystemData = (
+    UiFinder<'a>,
+    WriteStorage<'a, UiText>,
+    Read<'a, DashState>,
+    Read<'a, TiltState>,
+    Read<'a, EnergyMeter>,
+    Write<'a, CloseCall>,
+    Write<'a, BonusState>,
+    Read<'a, Score>,
+    Read<'a, GameMode>,
+    Read<'a, ModeClock>,
+    Read<'a, Lives>,
+    Read<'a, Time>,
+  );
+
+  fn run(
+    &mut self,
+    (
+      finder,
+      mut ui_text,
+      dash,
+      tilt,
+      energy,
+      mut close_call,
+      mut bonus,
+      score,
+      game_mode,
+      clock,
+      lives,
+      time,
+    ): Self::SystemData,
+  ) {
+    if let Some(text) = finder.find("dash").and_then(|entity| ui_text.get_mut(entity)) {
+      text.color = if dash.cooldown > 0. {
+        HUD_DIM_COLOR
+      } else {
+        TEXT_SELECTED_COLOR
+      };
+    }
+    if let Some(text) = finder.find("tilt").and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = if tilt.penalty > 0 {
+        format!("TILT x{} -{}", tilt.remaining, tilt.penalty)
+      } else {
+        format!("TILT x{}", tilt.remaining)
+      };
+      text.color = if tilt.remaining == 0 { HUD_DIM_COLOR } else { TEXT_COLOR };
+    }
+    if let Some(text) = finder.find("energy").and_then(|entity| ui_text.get_mut(entity)) {
+      if !game_mode.mode.power_ups() {
+        text.text.clear();
+        text.color = TEXT_COLOR;
+      } else if energy.charge >= 1. {
+        text.text = "BOMB READY (B)".to_string();
+        text.color = TEXT_SELECTED_COLOR;
+      } else {
+        let filled = (energy.charge * 10.) as usize;
+        text.text = format!("ENERGY {}{}", "|".repeat(filled), ".".repeat(10 - filled));
+        text.color = TEXT_COLOR;
+      }
+    }
+    close_call.flash = (close_call.flash - time.delta_seconds()).max(0.);
+    if let Some(text) = finder.find("close_call").and_then(|entity| ui_text.get_mut(entity)) {
+      let [r, g, b, _] = TEXT_SELECTED_COLOR;
+      text.color = [r, g, b, close_call.flash / CLOSE_CALL_SECONDS];
+    }
+    bonus.flash = (bonus.flash - time.delta_seconds()).max(0.);
+    if let Some(text) = finder.find("bonus").and_then(|entity| ui_text.get_mut(entity)) {
+      let [r, g, b] = BONUS_BRICK_COLOR;
+      text.text = bonus.award.to_string();
+      text.color = [r, g, b, bonus.flash / BONUS_FLASH_SECONDS];
+    }
+    if let Some(text) = finder.find("score").and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = if game_mode.mode.calm() {
+        String::new()
+      } else if game_mode.mode.players() > 1 {
+        format!("P1 {}  P2 {}", score.seats[0], score.seats[1])
+      } else {
+        format!("SCORE {}", score.points)
+      };
+      text.color = TEXT_COLOR;
+    }
+    if let Some(text) = finder.find("time").and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = match clock.remaining {
+        Some(remaining) => {
+          let seconds = remaining.max(0.).ceil() as u32;
+          format!("{} {}:{:02}", game_mode.mode.label(), seconds / 60, seconds % 60)
+        }
+        None => match lives.remaining {
+          Some(lives) if game_mode.mode == PlayMode::Normal => format!("LIVES {}", lives),
+          Some(lives) => format!("{} x{}", game_mode.mode.label(), lives),
+          None => game_mode.mode.label().to_string(),
+        },
+      };
+      text.color = match clock.remaining {
+        Some(remaining) if remaining <= MODE_CLOCK_WARNING_SECONDS => TEXT_ERROR_COLOR,
+        _ => TEXT_COLOR,
+      };
+    }
+    if let Some([r, g, b]) = game_mode.mode.hud_tint() {
+      for id in HUD_TINTED_IDS.iter() {
+        if let Some(text) = finder.find(id).and_then(|entity| ui_text.get_mut(entity)) {
+          let [text_r, text_g, text_b, alpha] = text.color;
+          text.color = [text_r * r, text_g * g, text_b * b, alpha];
+        }
+      }
+    }
+  }
+}
+
+/// Shows the caption of gameplay sounds on the side of the screen they came from and fades it out again.
+#[derive(Default, SystemDesc)]
+struct CaptionSystem {
+  /// Seconds left for the left, center and right caption.
+  timers: [f32; 3],
+}
+
+impl<'a> System<'a> for CaptionSystem {
+  type SystemData = (
+    UiFinder<'a>,
+    WriteStorage<'a, UiText>,
+    Write<'a, SoundEvents>,
+    Read<'a, Settings>,
+    Read<'a, Playfield>,
+    Read<'a, Time>,
+  );
+
+  fn run(&mut self, (finder, mut ui_text, mut events, settings, playfield, time): Self::SystemData) {
+    for timer in self.timers.iter_mut() {
+      *timer = (*timer - time.delta_seconds()).max(0.);
+    }
+    for (sound_type, source_x) in events.0.drain(..) {
+      let caption = match sound_type.caption() {
+        Some(caption) if settings.captions => caption,
+        _ => continue,
+      };
+      let side = match source_x {
+        Some(x) if x < playfield.width / 3. => 0,
+        Some(x) if x > playfield.width * 2. / 3. => 2,
+        _ => 1,
+      };
+      if let Some(text) = finder
+        .find(CAPTION_IDS[side])
+        .and_then(|entity| ui_text.get_mut(entity))
+      {
+        text.text = match side {
+          0 => format!("< {}", caption),
+          2 => format!("{} >", caption),
+          _ => caption.to_string(),
+        };
+      }
+      self.timers[side] = CAPTION_SECONDS;
+    }
+    for (id, timer) in CAPTION_IDS.iter().zip(self.timers.iter()) {
+      if let Some(text) = finder.find(id).and_then(|entity| ui_text.get_mut(entity)) {
+        text.color = [1., 1., 1., timer / CAPTION_SECONDS];
+      }
+    }
+  }
+}
+
+#[derive(Default, SystemDesc)]
+struct BallSystem;
+
+impl<'a> System<'a> for BallSystem {
+  type SystemData = (
+    WriteStorage<'a, Transform>,
+    WriteStorage<'a, Ball>,
+    Write<'a, TiltState>,
+    Read<'a, GameMode>,
+    Read<'a, Time>,
+  );
+
+  fn run(&mut self, (mut transforms, mut balls, mut tilt, mode, time): Self::SystemData) {
+    let tilt_angle = if tilt.pending {
+      tilt.pending = false;
+      Some(if rand::thread_rng().gen::<bool>() {
+        TILT_ANGLE
+      } else {
+        -TILT_ANGLE
+      })
+    } else {
+      None
+    };
+
+    for (transform, ball) in (&mut transforms, &mut balls).join() {
+      if ball.stuck {
+        continue;
+      }
+      if let Some(angle) = tilt_angle {
+        let (sin, cos) = angle.sin_cos();
+        let (x, y) = (ball.direction.x, ball.direction.y);
+        ball.direction.x = x * cos - y * sin;
+        ball.direction.y = x * sin + y * cos;
+      }
+      let speed = if mode.new_game_plus {
+        ball.speed * NEW_GAME_PLUS_BALL_SPEED
+      } else {
+        ball.speed
+      } * mode.mode.ball_speed();
+      transform.prepend_translation(ball.direction * time.delta_seconds() * speed);
+    }
+  }
+}
+
+/// Bounces the balls off the walls, paddles, bricks and everything else on the playfield. A ball that hits something
+/// is pushed out of it, and a contact is only handled on the frame it starts, so an overlap that lasts never counts
+/// twice.
+#[derive(Default, SystemDesc)]
+struct CollisionSystem {
+  /// Balls and what they were touching last frame.
+  contacts: HashSet<(Entity, Entity)>,
+}
+
+impl<'a> System<'a> for CollisionSystem {
+  type SystemData = (
+    Entities<'a>,
+    WriteStorage<'a, Ball>,
+    ReadStorage<'a, Paddle>,
+    WriteStorage<'a, Transform>,
+    ReadStorage<'a, Player>,
+    ReadStorage<'a, SpriteRender>,
+    ReadStorage<'a, SplitBrick>,
+    ReadStorage<'a, BonusBrick>,
+    ReadStorage<'a, Armored>,
+    ReadStorage<'a, Points>,
+    Write<'a, ShatterQueue>,
+    Write<'a, SplitQueue>,
+    Sounds<'a>,
+    Write<'a, RumbleQueue>,
+    Write<'a, EnergyMeter>,
+    Write<'a, CloseCall>,
+    Write<'a, LevelStats>,
+    Write<'a, HitStop>,
+    // Nested, the tuple is at the size limit of `SystemData`.
+    (
+      WriteStorage<'a, Brick>,
+      ReadStorage<'a, Metal>,
+      WriteStorage<'a, Tint>,
+      Write<'a, BonusState>,
+      Write<'a, GameEvents>,
+      WriteStorage<'a, Bumper>,
+      ReadStorage<'a, Gate>,
+      ReadStorage<'a, Door>,
+      ReadStorage<'a, Mover>,
+    ),
+    Write<'a, Lightning>,
+    Write<'a, Breaker>,
+    Write<'a, Score>,
+    Read<'a, Settings>,
+    Read<'a, GameConfig>,
+    Read<'a, GameMode>,
+    Read<'a, Playfield>,
+  );
+
+  fn run(
+    &mut self,
+    (
+      entities,
+      mut balls,
+      paddles,
+      mut transforms,
+      players,
+      sprites,
+      split_bricks,
+      bonus_bricks,
+      armored,
+      points,
+      mut shatter,
+      mut splits,
+      mut sounds,
+      mut rumble,
+      mut energy,
+      mut close_call,
+      mut stats,
+      mut hit_stop,
+      (mut bricks, metal, mut tints, mut bonus, mut game_events, mut bumpers, gates, doors, movers),
+      mut lightning,
+      mut breaker,
+      mut score,
+      settings,
+      config,
+      mode,
+      playfield,
+    ): Self::SystemData,
+  ) {
+    let mut bricks_left = (&bricks, !&bonus_bricks, !&metal).join().count();
+    let armored_left = (&bricks, &armored).join().next().is_some();
+    let mut contacts = HashSet::new();
+    // Bricks are only deleted at the end of the frame, another ball must not hit them again before that.
+    let mut destroyed = HashSet::new();
+    // Where balls are moved to get them out of what they hit, applied once every ball was tested.
+    let mut pushes = vec![];
+    for (ball_entity, ball, transform) in (&*entities, &mut balls, &transforms).join() {
+      if ball.stuck {
+        continue;
+      }
+      let ball_x = transform.translation().x;
+      let ball_y = transform.translation().y;
+      let ball_transform = transform;
+
+      if ball_y >= playfield.height - ball.radius && ball.direction.y > 0.0 {
+        play_sound_from(&mut sounds, SoundType::WallHit, Some(ball_x));
+        ball.direction.y = -ball.direction.y;
+        bounce(
+          &mut ball.direction,
+          &mut ball.speed,
+          &config.materials.walls,
+          Vector3::x(),
+        );
+      }
+
+      if (ball_x <= ball.radius && ball.direction.x < 0.0)
+        || (ball_x >= playfield.width - ball.radius && ball.direction.x > 0.0)
+      {
+        play_sound_from(&mut sounds, SoundType::WallHit, Some(ball_x));
+        ball.direction.x = -ball.direction.x;
+        bounce(
+          &mut ball.direction,
+          &mut ball.speed,
+          &config.materials.walls,
+          Vector3::y(),
+        );
+      }
+
+      for (e, paddle, _, transform) in (&*entities, &paddles, &players, &transforms).join() {
+        let contact = match box_contact(
+          ball_transform.translation(),
+          &ball.direction,
+          ball.radius,
+          transform,
+          paddle.width,
+          paddle.height,
+        ) {
+          Some(contact) => contact,
+          None => continue,
+        };
+        contacts.insert((ball_entity, e));
+        if self.contacts.contains(&(ball_entity, e)) {
+          continue;
+        }
+        let (push_x, push_y) = contact.push;
+        pushes.push((ball_entity, push_x, push_y));
+        let material = &config.materials.paddle;
+        // How far inside the paddle edge the ball was caught, measured when it crosses into the paddle.
+        let edge_distance = paddle.width * 0.5 + ball.radius - contact.local_x.abs();
+        // A lagging input leaves the paddle behind by as much as it moves in that time.
+        let close_call_distance = CLOSE_CALL_DISTANCE + paddle.velocity.abs() * settings.input_latency;
+        stats.paddle_hits += 1;
+        if edge_distance < close_call_distance && ball.direction.y < 0. {
+          stats.close_calls += 1;
+          energy.charge = (energy.charge + CLOSE_CALL_ENERGY).min(1.);
+          close_call.flash = CLOSE_CALL_SECONDS;
+          play_sound_from(&mut sounds, SoundType::CloseCall, Some(ball_x));
+        }
+        let direction = paddle_deflection(
+          &(ball_transform.translation() - transform.translation()),
+          &contact.width_axis,
+          paddle.velocity / BALL_VELOCITY * material.spin,
+        );
+        play_sound_from(&mut sounds, SoundType::PaddleHit, Some(ball_x));
+        rumble
+          .0
+          .push(RumbleEvent::PaddleHit(direction.x.abs() / (paddle.width / 2.)));
+        energy.combo = 0;
+        if armored_left && !breaker.is_open() {
+          breaker.hits += 1;
+          if breaker.hits >= BREAKER_HITS {
+            breaker.hits = 0;
+            breaker.open = BREAKER_WINDOW_SECONDS;
+          }
+        }
+        ball.direction = Vector3::new(direction.x, direction.y, ball.direction.z);
+        bounce(&mut ball.direction, &mut ball.speed, material, contact.along);
+      }
+
+      // A ball can overlap several bricks after one step. The one it ran into first decides the bounce, bricks it ran
+      // into at the same moment break along and the others are left for the next frame.
+      let mut hits = vec![];
+      for (e, brick, transform) in (&*entities, &bricks, &transforms).join() {
+        let contact = match box_contact(
+          ball_transform.translation(),
+          &ball.direction,
+          ball.radius,
+          transform,
+          brick.width,
+          brick.height,
+        ) {
+          Some(contact) => contact,
+          None => continue,
+        };
+        if self.contacts.contains(&(ball_entity, e)) || destroyed.contains(&e) {
+          contacts.insert((ball_entity, e));
+        } else {
+          hits.push((e, transform, contact));
+        }
+      }
+      // The join goes by entity, so bricks hit at exactly the same moment always come in the same order.
+      hits.sort_by(|(_, _, a), (_, _, b)| b.depth.partial_cmp(&a.depth).unwrap_or(std::cmp::Ordering::Equal));
+      let earliest = hits.first().map_or(0., |(_, _, contact)| contact.depth);
+      hits.retain(|(_, _, contact)| contact.depth >= earliest - SIMULTANEOUS_HIT_DEPTH);
+
+      let mut bounce_material = None;
+      for (e, transform, _) in &hits {
+        let e = *e;
+        contacts.insert((ball_entity, e));
+        let brick = match bricks.get_mut(e) {
+          Some(brick) => brick,
+          None => continue,
+        };
+        // Metal, doors and armored bricks with their shields up take no harm.
+        let solid =
+          metal.get(e).is_some() || doors.get(e).is_some() || (armored.get(e).is_some() && !breaker.is_open());
+        let material = if solid {
+          &config.materials.armored_brick
+        } else if split_bricks.get(e).is_some() {
+          &config.materials.split_brick
+        } else {
+          &config.materials.brick
+        };
+        bounce_material.get_or_insert(material);
+        if solid {
+          play_sound_from(&mut sounds, SoundType::WallHit, Some(transform.translation().x));
+          continue;
+        }
+        // Sturdy bricks darken with every hit they take before they break.
+        if brick.hp > 1 {
+          brick.hp -= 1;
+          if let Some(tint) = tints.get_mut(e) {
+            tint.0.red *= BRICK_DAMAGE_DIM;
+            tint.0.green *= BRICK_DAMAGE_DIM;
+            tint.0.blue *= BRICK_DAMAGE_DIM;
+          }
+          play_sound_from(&mut sounds, SoundType::BrickDamaged, Some(transform.translation().x));
+          continue;
+        }
+        energy.combo += 1;
+        energy.charge = (energy.charge + ENERGY_PER_COMBO_HIT * energy.combo as f32).min(1.);
+        ball.speed = (ball.speed + BALL_SPEED_UP_PER_BRICK).min(BALL_MAX_VELOCITY);
+        entities
+          .delete(e)
+          .expect("Couldn't delete brick while colliding with ball!");
+        destroyed.insert(e);
+        let lane = (transform.translation().x / playfield.width * mode.mode.players() as f32) as usize;
+        if config.coop.shared_bricks || lane.min(mode.mode.players() - 1) == ball.seat {
+          score.add(ball.seat, brick.score);
+        }
+        if let Some(sprite) = sprites.get(e) {
+          shatter.0.push(((*transform).clone(), sprite.clone()));
+        }
+        if split_bricks.get(e).is_some() {
+          splits.0.push(ball_entity);
+        }
+        if bonus_bricks.get(e).is_some() {
+          let mut rng = rand::thread_rng();
+          bonus.flash = BONUS_FLASH_SECONDS;
+          let power_up = if rng.gen_range(0., 1.) < LIGHTNING_CHANCE {
+            lightning.charged = true;
+            bonus.award = "BONUS: CHAIN LIGHTNING";
+            "CHAIN LIGHTNING"
+          } else if rng.gen() {
+            energy.charge = 1.;
+            bonus.award = "BONUS: BOMB READY";
+            "BOMB"
+          } else {
+            splits.0.push(ball_entity);
+            bonus.award = "BONUS: EXTRA BALL";
+            "EXTRA BALL"
+          };
+          game_events.0.push(GameEvent::PowerUp(power_up));
+          play_sound_from(&mut sounds, SoundType::PowerUp, Some(ball_x));
+        } else {
+          if lightning.charged {
+            lightning.charged = false;
+            lightning.strikes.push(*transform.translation());
+          }
+          bricks_left = bricks_left.saturating_sub(1);
+          if bricks_left == 0 {
+            hit_stop.trigger(&settings);
+          }
+        }
+        play_sound_from(&mut sounds, SoundType::BrickHit(0), Some(transform.translation().x));
+        rumble.0.push(RumbleEvent::BrickDestroyed);
+      }
+
+      if let (Some((_, transform, contact)), Some(material)) = (hits.first(), bounce_material) {
+        let (push_x, push_y) = contact.push;
+        pushes.push((ball_entity, push_x, push_y));
+        match contact.corner {
+          Some(normal) => ball.direction = reflect_off_corner(&ball.direction, &normal),
+          None => {
+            let direction = (ball_transform.translation() - transform.translation()).normalize();
+            ball.direction = Vector3::new(direction.x, direction.y, ball.direction.z);
+          }
+        }
+        bounce(&mut ball.direction, &mut ball.speed, material, contact.along);
+      }
+
+      for (bumper, transform, bumper_points) in (&mut bumpers, &transforms, &points).join() {
+        let offset = ball_transform.translation() - transform.translation();
+        let normal = Vector3::new(offset.x, offset.y, 0.);
+        // A ball already moving away is on its way out after the last hit.
+        if !circles_overlap(&normal, ball.radius, bumper.radius) || ball.direction.dot(&normal) >= 0. {
+          continue;
+        }
+        let normal = normal.normalize();
+        let out = transform.translation() + normal * (bumper.radius + ball.radius);
+        pushes.push((ball_entity, out.x, out.y));
+        ball.direction -= normal * 2. * ball.direction.dot(&normal);
+        bounce(
+          &mut ball.direction,
+          &mut ball.speed,
+          &config.materials.bumper,
+          Vector3::new(-normal.y, normal.x, 0.),
+        );
+        bumper.flash = BUMPER_FLASH_SECONDS;
+        score.add(ball.seat, bumper_points.0);
+        play_sound_from(&mut sounds, SoundType::Bumper, Some(transform.translation().x));
+      }
+
+      for (gate, transform) in (&gates, &transforms).join() {
+        let offset = ball_transform.translation() - transform.translation();
+        if ball.direction.y * gate.direction >= 0.
+          || !point_in_rect(
+            offset.x,
+            offset.y,
+            -gate.width * 0.5 - ball.radius,
+            -gate.height * 0.5 - ball.radius,
+            gate.width * 0.5 + ball.radius,
+            gate.height * 0.5 + ball.radius,
+          )
+        {
+          continue;
+        }
+        play_sound_from(&mut sounds, SoundType::WallHit, Some(transform.translation().x));
+        ball.direction.y = -ball.direction.y;
+        bounce(
+          &mut ball.direction,
+          &mut ball.speed,
+          &config.materials.walls,
+          Vector3::x(),
+        );
+      }
+
+      for (mover, transform) in (&movers, &transforms).join() {
+        let offset = ball_transform.translation() - transform.translation();
+        // A ball already moving away is on its way out after the last hit.
+        if ball.direction.dot(&offset) >= 0.
+          || !point_in_rect(
+            offset.x,
+            offset.y,
+            -mover.width * 0.5 - ball.radius,
+            -mover.height * 0.5 - ball.radius,
+            mover.width * 0.5 + ball.radius,
+            mover.height * 0.5 + ball.radius,
+          )
+        {
+          continue;
+        }
+        let material = &config.materials.paddle;
+        let (velocity_x, velocity_y) = mover.velocity;
+        let direction =
+          (offset.normalize() + Vector3::new(velocity_x, velocity_y, 0.) * (material.spin / BALL_VELOCITY)).normalize();
+        ball.direction = Vector3::new(direction.x, direction.y, ball.direction.z);
+        bounce(&mut ball.direction, &mut ball.speed, material, Vector3::x());
+        play_sound_from(&mut sounds, SoundType::PaddleHit, Some(transform.translation().x));
+      }
+    }
+
+    for (ball_entity, x, y) in pushes {
+      if let Some(transform) = transforms.get_mut(ball_entity) {
+        transform.set_translation_x(x);
+        transform.set_translation_y(y);
+      }
+    }
+    self.contacts = contacts;
+  }
+}
+
+/// Bounces the balls in play off each other like billiard balls of equal weight when the chaos setting is on,
+/// otherwise they pass through each other.
+#[derive(Default, SystemDesc)]
+struct BallCollisionSystem;
+
+impl<'a> System<'a> for BallCollisionSystem {
+  type SystemData = (
+    Entities<'a>,
+    WriteStorage<'a, Ball>,
+    WriteStorage<'a, Transform>,
+    Sounds<'a>,
+    Read<'a, Settings>,
+  );
+
+  fn run(&mut self, (entities, mut balls, mut transforms, mut sounds, settings): Self::SystemData) {
+    if !settings.chaos {
+      return;
+    }
+    let in_play = (&*entities, &balls)
+      .join()
+      .filter(|(_, ball)| !ball.stuck)
+      .map(|(e, _)| e)
+      .collect::<Vec<_>>();
+    for (index, &a) in in_play.iter().enumerate() {
+      for &b in &in_play[index + 1..] {
+        let (position_a, position_b) = match (transforms.get(a), transforms.get(b)) {
+          (Some(a), Some(b)) => (*a.translation(), *b.translation()),
+          _ => continue,
+        };
+        let ((velocity_a, radius_a), (velocity_b, radius_b)) = match (balls.get(a), balls.get(b)) {
+          (Some(a), Some(b)) => ((a.direction * a.speed, a.radius), (b.direction * b.speed, b.radius)),
+          _ => continue,
+        };
+        let offset = position_b - position_a;
+        let offset = Vector3::new(offset.x, offset.y, 0.);
+        if !circles_overlap(&offset, radius_a, radius_b) {
+          continue;
+        }
+        let distance = offset.norm();
+        // Balls right on top of each other are pushed apart sideways.
+        let normal = if distance > f32::EPSILON {
+          offset / distance
+        } else {
+          Vector3::x()
+        };
+        // Each ball moves out by half of the overlap.
+        let overlap = (radius_a + radius_b - distance) / 2.;
+        if let Some(transform) = transforms.get_mut(a) {
+          *transform.translation_mut() -= normal * overlap;
+        }
+        if let Some(transform) = transforms.get_mut(b) {
+          *transform.translation_mut() += normal * overlap;
+        }
+
+        let (velocity_a, velocity_b) = match collide_circles(&velocity_a, &velocity_b, &normal) {
+          Some(velocities) => velocities,
+          None => continue,
+        };
+        if let Some(ball) = balls.get_mut(a) {
+          set_velocity(&mut ball.direction, &mut ball.speed, velocity_a);
+        }
+        if let Some(ball) = balls.get_mut(b) {
+          set_velocity(&mut ball.direction, &mut ball.speed, velocity_b);
+        }
+        play_sound_from(
+          &mut sounds,
+          SoundType::BallHit,
+          Some((position_a.x + position_b.x) / 2.),
+        );
+      }
+    }
+  }
+}
+
+/// Spawns a bonus brick into a free cell of the brick grid every now and then, blinks it faster the closer it gets
+/// to expiring and removes it once its time is up.
+#[derive(Default, SystemDesc)]
+struct BonusSystem;
+
+impl<'a> System<'a> for BonusSystem {
+  type SystemData = (
+    Entities<'a>,
+    WriteStorage<'a, Brick>,
+    ReadStorage<'a, Ball>,
+    WriteStorage<'a, BonusBrick>,
+    WriteStorage<'a, Transform>,
+    WriteStorage<'a, SpriteRender>,
+    WriteStorage<'a, Tint>,
+    WriteStorage<'a, Transparent>,
+    Write<'a, BonusState>,
+    Read<'a, Level>,
+    Read<'a, Playfield>,
+    Read<'a, GameMode>,
+    Read<'a, FieldRotation>,
+    Read<'a, Time>,
+  );
+
+  fn run(
+    &mut self,
+    (
+      entities,
+      mut bricks,
+      balls,
+      mut bonus_bricks,
+      mut transforms,
+      mut sprites,
+      mut tints,
+      mut transparents,
+      mut state,
+      level,
+      playfield,
+      mode,
+      rotation,
+      time,
+    ): Self::SystemData,
+  ) {
+    let delta_seconds = time.delta_seconds();
+    let mut rng = rand::thread_rng();
+    for (e, bonus, tint) in (&*entities, &mut bonus_bricks, &mut tints).join() {
+      bonus.remaining -= delta_seconds;
+      if bonus.remaining <= 0. {
+        entities.delete(e).expect("Couldn't delete expired bonus brick!");
+        continue;
+      }
+      let age = 1. - bonus.remaining / BONUS_LIFETIME;
+      let (slow, fast) = BONUS_BLINK_RATE;
+      let blink = ((BONUS_LIFETIME - bonus.remaining) * (slow + (fast - slow) * age)).fract();
+      tint.0.alpha = if blink < 0.5 { 1. } else { 0.35 };
+    }
+
+    // The free cells of a rotated brick field aren't on the grid anymore.
+    if !mode.mode.power_ups()
+      || rotation.center.is_some()
+      || bonus_bricks.join().next().is_some()
+      || balls.join().all(|ball| ball.stuck)
+    {
+      return;
+    }
+    let (min, max) = BONUS_SPAWN_SECONDS;
+    let next = state.next.get_or_insert_with(|| rng.gen_range(min, max));
+    *next -= delta_seconds;
+    if *next > 0. {
+      return;
+    }
+    state.next = None;
+
+    let (width, height, sprite) = match (&bricks, &sprites)
+      .join()
+      .next()
+      .map(|(brick, sprite)| (brick.width, brick.height, sprite.clone()))
+    {
+      Some(brick) => brick,
+      None => return,
+    };
+    let taken = (&bricks, &transforms)
+      .join()
+      .map(|(_, transform)| *transform.translation())
+      .collect::<Vec<_>>();
+    let free = (0..level.rows.len())
+      .flat_map(|row| (0..level.columns()).map(move |column| (column, row)))
+      .map(|(column, row)| {
+        brick_translation(
+          playfield.width,
+          playfield.height,
+          level.columns(),
+          column,
+          row,
+          width,
+          height,
+        )
+      })
+      .filter(|&(x, y)| {
+        taken
+          .iter()
+          .all(|brick| (brick.x - x).abs() >= width / 2. || (brick.y - y).abs() >= height / 2.)
+      })
+      .collect::<Vec<_>>();
+    if free.is_empty() {
+      return;
+    }
+    let (x, y) = free[rng.gen_range(0, free.len())];
+    let [r, g, b] = BONUS_BRICK_COLOR;
+    entities
+      .build_entity()
+      .with(
+        Brick {
+          width,
+          height,
+          tier: PLAIN_BRICK,
+          hp: 1,
+          score: BONUS_POINTS * mode.mode.score_multiplier(),
+        },
+        &mut bricks,
+      )
+      .with(
+        BonusBrick {
+          remaining: BONUS_LIFETIME,
+        },
+        &mut bonus_bricks,
+      )
+      .with(Layer::Playfield.transform(x, y), &mut transforms)
+      .with(sprite, &mut sprites)
+      .with(Tint(Srgba::new(r, g, b, 1.)), &mut tints)
+      .with(Transparent, &mut transparents)
+      .build();
+  }
+}
+
+/// Swings the bricks of a bonus round back and forth around the center of the brick field, the paddle stays put.
+#[derive(Default, SystemDesc)]
+struct FieldRotationSystem;
+
+impl<'a> System<'a> for FieldRotationSystem {
+  type SystemData = (
+    ReadStorage<'a, Brick>,
+    WriteStorage<'a, Transform>,
+    Write<'a, FieldRotation>,
+    Read<'a, Time>,
+  );
+
+  fn run(&mut self, (bricks, mut transforms, mut rotation, time): Self::SystemData) {
+    let center = match rotation.center {
+      Some(center) => center,
+      None => return,
+    };
+    rotation.elapsed += time.delta_seconds();
+    let angle = BONUS_ROUND_SWING * (rotation.elapsed / BONUS_ROUND_PERIOD * std::f32::consts::PI * 2.).sin();
+    swing_bricks(&bricks, &mut transforms, center, angle - rotation.angle, angle);
+    rotation.angle = angle;
+  }
+}
+
+/// Swings every brick `by` radians around `center` and turns it to `angle`, the angle it is swung to.
+fn swing_bricks(
+  bricks: &ReadStorage<Brick>,
+  transforms: &mut WriteStorage<Transform>,
+  (center_x, center_y): (f32, f32),
+  by: f32,
+  angle: f32,
+) {
+  let (sin, cos) = by.sin_cos();
+  for (_, transform) in (bricks, &mut *transforms).join() {
+    let (x, y) = (
+      transform.translation().x - center_x,
+      transform.translation().y - center_y,
+    );
+    transform.set_translation_x(center_x + x * cos - y * sin);
+    transform.set_translation_y(center_y + x * sin + y * cos);
+    transform.set_rotation_2d(angle);
+  }
+}
+
+/// Moves the mini-paddles of bonus stages along their paths.
+#[derive(Default, SystemDesc)]
+struct MoverSystem;
+
+impl<'a> System<'a> for MoverSystem {
+  type SystemData = (WriteStorage<'a, Mover>, WriteStorage<'a, Transform>, Read<'a, Time>);
+
+  fn run(&mut self, (mut movers, mut transforms, time): Self::SystemData) {
+    let delta_seconds = time.delta_seconds();
+    if delta_seconds <= 0. {
+      return;
+    }
+    for (mover, transform) in (&mut movers, &mut transforms).join() {
+      let (from_x, from_y) = mover.position();
+      mover.travelled += mover.speed * delta_seconds;
+      let (x, y) = mover.position();
+      mover.velocity = ((x - from_x) / delta_seconds, (y - from_y) / delta_seconds);
+      transform.set_translation_x(x);
+      transform.set_translation_y(y);
+    }
+  }
+}
+
+/// Lights up bumpers that were just hit and swells them, both settle back over `BUMPER_FLASH_SECONDS`.
+#[derive(Default, SystemDesc)]
+struct BumperSystem;
+
+impl<'a> System<'a> for BumperSystem {
+  type SystemData = (
+    WriteStorage<'a, Bumper>,
+    WriteStorage<'a, Transform>,
+    WriteStorage<'a, Tint>,
+    Read<'a, Time>,
+  );
+
+  fn run(&mut self, (mut bumpers, mut transforms, mut tints, time): Self::SystemData) {
+    for (bumper, transform, tint) in (&mut bumpers, &mut transforms, &mut tints).join() {
+      bumper.flash = (bumper.flash - time.delta_seconds()).max(0.);
+      let flash = bumper.flash / BUMPER_FLASH_SECONDS;
+      let scale = bumper.scale * (1. + BUMPER_FLASH_GROWTH * flash);
+      transform.set_scale(Vector3::new(scale, scale, 1.));
+      let [r, g, b] = BUMPER_COLOR;
+      let [flash_r, flash_g, flash_b] = BUMPER_FLASH_COLOR;
+      tint.0.red = r + (flash_r - r) * flash;
+      tint.0.green = g + (flash_g - g) * flash;
+      tint.0.blue = b + (flash_b - b) * flash;
+    }
+  }
+}
+
+/// Opens the doors of every link without a switch brick left, bombs and lightning count as well as the ball.
+#[derive(Default, SystemDesc)]
+struct DoorSystem;
+
+impl<'a> System<'a> for DoorSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, Door>,
+    ReadStorage<'a, Switch>,
+    ReadStorage<'a, Transform>,
+    Sounds<'a>,
+    Write<'a, GameEvents>,
+  );
+
+  fn run(&mut self, (entities, doors, switches, transforms, mut sounds, mut game_events): Self::SystemData) {
+    let links = (&*entities, &switches)
+      .join()
+      .filter(|(e, _)| entities.is_alive(*e))
+      .map(|(_, switch)| switch.link)
+      .collect::<HashSet<_>>();
+    let mut opened = 0;
+    let mut x = None;
+    for (e, door, transform) in (&*entities, &doors, &transforms).join() {
+      if links.contains(&door.link) || !entities.is_alive(e) {
+        continue;
+      }
+      entities.delete(e).expect("Couldn't delete door!");
+      opened += 1;
+      x = Some(transform.translation().x);
+    }
+    if opened > 0 {
+      play_sound_from(&mut sounds, SoundType::DoorOpened, x);
+      game_events.0.push(GameEvent::DoorsOpened(opened));
+    }
+  }
+}
+
+/// Counts down the open breaker, colors armored bricks by whether their shields are up and shows the meter.
+#[derive(Default, SystemDesc)]
+struct BreakerSystem;
+
+impl<'a> System<'a> for BreakerSystem {
+  type SystemData = (
+    ReadStorage<'a, Armored>,
+    WriteStorage<'a, Tint>,
+    Write<'a, Breaker>,
+    Read<'a, Time>,
+    UiFinder<'a>,
+    WriteStorage<'a, UiText>,
+  );
+
+  fn run(&mut self, (armored, mut tints, mut breaker, time, finder, mut ui_text): Self::SystemData) {
+    breaker.open = (breaker.open - time.delta_seconds()).max(0.);
+    let [r, g, b] = if breaker.is_open() {
+      ARMORED_OPEN_COLOR
+    } else {
+      ARMORED_BRICK_COLOR
+    };
+    let mut armored_left = false;
+    for (_, tint) in (&armored, &mut tints).join() {
+      armored_left = true;
+      tint.0.red = r;
+      tint.0.green = g;
+      tint.0.blue = b;
+    }
+
+    if let Some(text) = finder.find("breaker").and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = if !armored_left {
+        String::new()
+      } else if breaker.is_open() {
+        format!("SHIELDS DOWN {:.0}", breaker.open.ceil())
+      } else {
+        let hits = breaker.hits as usize;
+        format!(
+          "BREAKER {}{}",
+          "|".repeat(hits),
+          ".".repeat(BREAKER_HITS as usize - hits)
+        )
+      };
+      text.color = if breaker.is_open() {
+        TEXT_SELECTED_COLOR
+      } else {
+        TEXT_COLOR
+      };
+    }
+  }
+}
+
+/// Arcs chain lightning from the bricks queued by `CollisionSystem` to the `LIGHTNING_TARGETS` nearest bricks and
+/// destroys those too. The arcs are drawn as jagged debug lines that are rebuilt every frame, so they flicker.
+#[derive(Default, SystemDesc)]
+struct LightningSystem;
+
+impl<'a> System<'a> for LightningSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, Brick>,
+    ReadStorage<'a, BonusBrick>,
+    ReadStorage<'a, Armored>,
+    ReadStorage<'a, Metal>,
+    Read<'a, Breaker>,
+    ReadStorage<'a, Transform>,
+    ReadStorage<'a, SpriteRender>,
+    Write<'a, Lightning>,
+    Write<'a, ShatterQueue>,
+    Write<'a, DebugLines>,
+    Write<'a, HitStop>,
+    Write<'a, Score>,
+    Write<'a, GameEvents>,
+    Sounds<'a>,
+    Read<'a, Settings>,
+    Read<'a, Time>,
+  );
+
+  fn run(
+    &mut self,
+    (
+      entities,
+      bricks,
+      bonus_bricks,
+      armored,
+      metal,
+      breaker,
+      transforms,
+      sprites,
+      mut lightning,
+      mut shatter,
+      mut debug_lines,
+      mut hit_stop,
+      mut score,
+      mut game_events,
+      mut sounds,
+      settings,
+      time,
+    ): Self::SystemData,
+  ) {
+    let strikes = lightning.strikes.drain(..).collect::<Vec<_>>();
+    for strike in strikes {
+      let mut targets = (&*entities, &bricks, !&bonus_bricks, !&metal, &transforms)
+        .join()
+        .filter(|(e, _, _, _, transform)| {
+          entities.is_alive(*e)
+            && (breaker.is_open() || armored.get(*e).is_none())
+            && (transform.translation() - strike).norm() > f32::EPSILON
+        })
+        .map(|(e, brick, _, _, transform)| (e, brick.score, transform, (transform.translation() - strike).norm()))
+        .collect::<Vec<_>>();
+      targets.sort_by(|(_, _, _, a), (_, _, _, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+      let bricks_left = targets.len();
+      if bricks_left > 0 {
+        game_events
+          .0
+          .push(GameEvent::Lightning(bricks_left.min(LIGHTNING_TARGETS)));
+      }
+      for (e, points, transform, _) in targets.into_iter().take(LIGHTNING_TARGETS) {
+        entities.delete(e).expect("Couldn't delete brick hit by lightning!");
+        score.add(0, points);
+        if let Some(sprite) = sprites.get(e) {
+          shatter.0.push((transform.clone(), sprite.clone()));
+        }
+        lightning
+          .arcs
+          .push((strike, *transform.translation(), LIGHTNING_ARC_SECONDS));
+        play_sound_from(&mut sounds, SoundType::BrickHit(0), Some(transform.translation().x));
+      }
+      play_sound_from(&mut sounds, SoundType::Lightning, Some(strike.x));
+      if bricks_left <= LIGHTNING_TARGETS {
+        hit_stop.trigger(&settings);
+      }
+    }
+
+    let delta_seconds = time.delta_real_seconds();
+    let mut rng = rand::thread_rng();
+    let [r, g, b] = LIGHTNING_COLOR;
+    for (from, to, remaining) in lightning.arcs.iter_mut() {
+      *remaining -= delta_seconds;
+      let along = *to - *from;
+      let side = Vector3::new(-along.y, along.x, 0.).normalize();
+      let alpha = (*remaining / LIGHTNING_ARC_SECONDS).max(0.);
+      let mut previous = *from;
+      for step in 1..=LIGHTNING_SEGMENTS {
+        let mut point = *from + along * (step as f32 / LIGHTNING_SEGMENTS as f32);
+        if step < LIGHTNING_SEGMENTS {
+          point += side * rng.gen_range(-LIGHTNING_JITTER, LIGHTNING_JITTER);
+        }
+        debug_lines.draw_line(
+          Point3::new(previous.x, previous.y, Layer::Effects.z()),
+          Point3::new(point.x, point.y, Layer::Effects.z()),
+          Srgba::new(r, g, b, alpha),
+        );
+        previous = point;
+      }
+    }
+    lightning.arcs.retain(|(_, _, remaining)| *remaining > 0.);
+  }
+}
+
+/// Splits the balls queued by `CollisionSystem`: the ball turns `split_angle` to one side and a copy with the same
+/// speed and skin is sent to the other, as long as fewer than `max_balls` are in play.
+#[derive(Default, SystemDesc)]
+struct SplitSystem;
+
+impl<'a> System<'a> for SplitSystem {
+  type SystemData = (
+    Entities<'a>,
+    WriteStorage<'a, Ball>,
+    WriteStorage<'a, Transform>,
+    WriteStorage<'a, SpriteRender>,
+    WriteStorage<'a, Tint>,
+    Write<'a, SplitQueue>,
+    Read<'a, GameConfig>,
+  );
+
+  fn run(
+    &mut self,
+    (entities, mut balls, mut transforms, mut sprites, mut tints, mut splits, config): Self::SystemData,
+  ) {
+    let mut ball_count = balls.join().count();
+    for ball_entity in splits.0.drain(..) {
+      if ball_count >= config.multi_ball.max_balls || !entities.is_alive(ball_entity) {
+        continue;
+      }
+      let (transform, sprite, tint) = match (
+        transforms.get(ball_entity),
+        sprites.get(ball_entity),
+        tints.get(ball_entity),
+      ) {
+        (Some(transform), Some(sprite), Some(tint)) => (transform.clone(), sprite.clone(), *tint),
+        _ => continue,
+      };
+      let ball = match balls.get_mut(ball_entity) {
+        Some(ball) => ball,
+        None => continue,
+      };
+      let angle = config.multi_ball.split_angle.to_radians();
+      let direction = ball.direction;
+      let turned = |angle: f32| {
+        let (sin, cos) = angle.sin_cos();
+        Vector3::new(
+          direction.x * cos - direction.y * sin,
+          direction.x * sin + direction.y * cos,
+          direction.z,
+        )
+      };
+      ball.direction = turned(angle);
+      let split = Ball {
+        direction: turned(-angle),
+        radius: ball.radius,
+        speed: ball.speed,
+        color: ball.color,
+        stuck: false,
+        seat: ball.seat,
+        wind_up: None,
+        waiting: 0.,
+      };
+      entities
+        .build_entity()
+        .with(split, &mut balls)
+        .with(transform, &mut transforms)
+        .with(sprite, &mut sprites)
+        .with(tint, &mut tints)
+        .build();
+      ball_count += 1;
+    }
+  }
+}
+
+/// Follows focus and size changes of the window. winit reports a minimized window as resized to zero, it has no
+/// event for a covered one. Rendering runs in the same dispatcher that polls the window events, so it is throttled
+/// along with everything else instead of being skipped.
+#[derive(Default)]
+struct WindowActivitySystem {
+  reader: Option<ReaderId<Event>>,
+  focused: bool,
+  minimized: bool,
+}
+
+impl<'a> System<'a> for WindowActivitySystem {
+  type SystemData = (
+    Read<'a, EventChannel<Event>>,
+    Write<'a, WindowActivity>,
+    WriteExpect<'a, FrameLimiter>,
+    Read<'a, Settings>,
+  );
+
+  fn setup(&mut self, world: &mut World) {
+    Self::SystemData::setup(world);
+    self.focused = true;
+    self.reader = Some(world.fetch_mut::<EventChannel<Event>>().register_reader());
+  }
+
+  fn run(&mut self, (events, mut activity, mut frame_limiter, settings): Self::SystemData) {
+    let reader = self.reader.as_mut().expect("WindowActivitySystem wasn't set up!");
+    for event in events.read(reader) {
+      match event {
+        Event::WindowEvent {
+          event: WindowEvent::Focused(focused),
+          ..
+        } => self.focused = *focused,
+        Event::WindowEvent {
+          event: WindowEvent::Resized(size),
+          ..
+        } => self.minimized = size.width <= 0. || size.height <= 0.,
+        _ => {}
+      }
+    }
+
+    let background = !self.focused || self.minimized;
+    if background == activity.background {
+      return;
+    }
+    activity.background = background;
+    if background {
+      info!("Window in the background, throttling to {} fps", BACKGROUND_FPS);
+      frame_limiter.set_rate(FrameRateLimitStrategy::Sleep, BACKGROUND_FPS);
+    } else {
+      let (strategy, fps) = settings.frame_limit();
+      frame_limiter.set_rate(strategy, fps);
+    }
+  }
+}
+
+/// Sets the time scale from the effects that bend time: hit-stops freeze the game, a lost ball slows it down.
+#[derive(Default, SystemDesc)]
+struct TimeScaleSystem;
+
+impl<'a> System<'a> for TimeScaleSystem {
+  type SystemData = (Write<'a, HitStop>, Read<'a, BallLost>, Write<'a, Time>);
+
+  fn run(&mut self, (mut hit_stop, lost, mut time): Self::SystemData) {
+    hit_stop.remaining = (hit_stop.remaining - time.delta_real_seconds()).max(0.);
+    hit_stop.zoom_pulse = (hit_stop.zoom_pulse - time.delta_real_seconds()).max(0.);
+    let slow_motion = lost
+      .elapsed
+      .map_or(false, |elapsed| elapsed < BALL_LOST_SLOW_MOTION_SECONDS);
+    let scale = if hit_stop.remaining > 0. {
+      0.
+    } else if slow_motion {
+      BALL_LOST_TIME_SCALE
+    } else {
+      1.
+    };
+    time.set_time_scale(scale);
+  }
+}
+
+/// Slows down time once the ball fell below the playfield, flashes the screen edges and shows a banner, then puts
+/// the ball back on the paddle until it is served again.
+#[derive(Default, SystemDesc)]
+struct BallLostSystem;
+
+impl<'a> System<'a> for BallLostSystem {
+  type SystemData = (
+    Entities<'a>,
+    WriteStorage<'a, Ball>,
+    WriteStorage<'a, Transform>,
+    ReadStorage<'a, Paddle>,
+    ReadStorage<'a, Player>,
+    Write<'a, BallLost>,
+    Write<'a, LevelStats>,
+    Write<'a, Lives>,
+    Write<'a, GameEvents>,
+    Read<'a, GameMode>,
+    Read<'a, Time>,
+    Read<'a, InputHandler<StringBindings>>,
+    Read<'a, InputAssignment>,
+    Read<'a, Settings>,
+    Read<'a, Level>,
+    Write<'a, ServeMemory>,
+    Write<'a, ServeBuffer>,
+    UiFinder<'a>,
+    WriteStorage<'a, UiText>,
+    WriteStorage<'a, UiImage>,
+  );
+
+  fn run(
+    &mut self,
+    (
+      entities,
+      mut balls,
+      mut transforms,
+      paddles,
+      players,
+      mut lost,
+      mut stats,
+      mut lives,
+      mut game_events,
+      mode,
+      time,
+      input,
+      assignment,
+      settings,
+      level,
+      mut memory,
+      mut buffer,
+      finder,
+      mut ui_text,
+      mut ui_image,
+    ): Self::SystemData,
+  ) {
+    let mut respawn = false;
+    if lost.elapsed.is_none() {
+      let fallen = (&*entities, &balls, &transforms)
+        .join()
+        .filter(|(_, ball, transform)| !ball.stuck && transform.translation().y < -ball.radius)
+        .map(|(e, ball, _)| (e, ball.seat))
+        .collect::<Vec<_>>();
+      // Calm and co-op modes skip the slow motion and banner, the ball goes straight back to its paddle.
+      let instant = mode.mode.calm() || mode.mode.players() > 1;
+      // Extra balls from split bricks are simply gone, a player only loses the ball with their last one.
+      let mut last = vec![];
+      for seat in 0..mode.mode.players() {
+        let in_play = balls.join().filter(|ball| !ball.stuck && ball.seat == seat).count();
+        let mut seat_fallen = fallen.iter().filter(|(_, ball_seat)| *ball_seat == seat);
+        let e = match seat_fallen.next() {
+          Some(&(e, _)) if seat_fallen.count() + 1 == in_play => e,
+          _ => continue,
+        };
+        last.push(e);
+        if !mode.mode.calm() {
+          stats.balls_lost += 1;
+          if let Some(remaining) = lives.remaining.as_mut() {
+            *remaining = remaining.saturating_sub(1);
+          }
+          game_events.0.push(GameEvent::LifeLost);
+        }
+        if instant {
+          if let Some(ball) = balls.get_mut(e) {
+            ball.stuck = true;
+            ball.speed = BALL_VELOCITY;
+          }
+        } else {
+          lost.elapsed = Some(0.);
+        }
+      }
+      for (e, _) in fallen.into_iter().filter(|(e, _)| !last.contains(e)) {
+        entities.delete(e).expect("Couldn't delete fallen ball!");
+      }
+    }
+
+    if let Some(elapsed) = lost.elapsed {
+      let elapsed = elapsed + time.delta_real_seconds();
+      respawn = elapsed >= BALL_LOST_SECONDS;
+      lost.elapsed = Some(elapsed).filter(|_| !respawn);
+    }
+
+    let paddle_tops = (&paddles, &players, &transforms)
+      .join()
+      .map(|(paddle, player, transform)| {
+        (
+          player.seat,
+          transform.translation().x,
+          transform.translation().y + paddle.height / 2.,
+        )
+      })
+      .collect::<Vec<_>>();
+    for (ball, transform) in (&mut balls, &mut transforms).join() {
+      if respawn {
+        ball.stuck = true;
+        ball.speed = BALL_VELOCITY;
+      }
+      if !ball.stuck {
+        continue;
+      }
+      if let Some(&(_, x, y)) = paddle_tops.iter().find(|(seat, _, _)| *seat == ball.seat) {
+        transform.set_translation_x(x);
+        transform.set_translation_y(y + ball.radius + 1.);
+      }
+      ball.waiting += time.delta_seconds();
+      // Holding the serve winds it up, the ball leaves along the aim once it is released. Quick and auto serves
+      // repeat the last serve angle of the level.
+      let serving = assignment.serve(&input, ball.seat, SERVE_ACTIONS[ball.seat]);
+      // A serve tapped while the ball was still on its way back launches it once it is, like a tap on the paddle.
+      let buffered = buffer.0.get_mut(ball.seat).map_or(false, BufferedPress::take);
+      let quick = ball.seat == 0 && input.action_is_down(QUICK_SERVE_ACTION).unwrap_or(false);
+      let auto = settings.auto_serve.map_or(false, |seconds| ball.waiting >= seconds);
+      let remembered = memory.0.get(&level.name).copied().unwrap_or(0.);
+      let launch = match ball.wind_up {
+        Some(wind_up) if !serving => Some((serve_aim(wind_up), serve_power(wind_up))),
+        Some(wind_up) => {
+          ball.wind_up = Some(wind_up + time.delta_seconds());
+          None
+        }
+        None if quick || auto => Some((remembered, 0.)),
+        None if serving => {
+          ball.wind_up = Some(0.);
+          None
+        }
+        None if buffered => Some((serve_aim(0.), serve_power(0.))),
+        None => None,
+      };
+      if let Some((angle, power)) = launch {
+        stats.serves += 1;
+        ball.stuck = false;
+        ball.wind_up = None;
+        ball.waiting = 0.;
+        let (sin, cos) = angle.sin_cos();
+        ball.direction = Vector3::new(sin, cos, 0.);
+        ball.speed = (ball.speed * (1. + SERVE_SPEED_BONUS * power)).min(BALL_MAX_VELOCITY);
+        if ball.seat == 0 {
+          memory.0.insert(level.name.clone(), angle);
+        }
+      }
+    }
+
+    if let Some(text) = finder.find("ball_lost").and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = if lost.elapsed.is_some() {
+        "BALL LOST".to_string()
+      } else {
+        String::new()
+      };
+    }
+    let flash = lost
+      .elapsed
+      .map_or(0., |elapsed| 1. - (elapsed / BALL_LOST_SLOW_MOTION_SECONDS).min(1.));
+    let [r, g, b] = BALL_LOST_EDGE_COLOR;
+    for id in BALL_LOST_EDGE_IDS.iter() {
+      if let Some(image) = finder.find(id).and_then(|entity| ui_image.get_mut(entity)) {
+        *image = UiImage::SolidColor([r, g, b, flash]);
+      }
+    }
+  }
+}
+
+/// Shifts the ball color from its skin towards `BALL_HOT_COLOR` the closer it gets to `BALL_MAX_VELOCITY`.
+#[derive(Default, SystemDesc)]
+struct BallColorSystem;
+
+impl<'a> System<'a> for BallColorSystem {
+  type SystemData = (ReadStorage<'a, Ball>, WriteStorage<'a, Tint>);
+
+  fn run(&mut self, (balls, mut tints): Self::SystemData) {
+    for (ball, tint) in (&balls, &mut tints).join() {
+      let heat = ((ball.speed - BALL_VELOCITY) / (BALL_MAX_VELOCITY - BALL_VELOCITY))
+        .max(0.)
+        .min(1.);
+      let [r, g, b] = ball.color;
+      let [hot_r, hot_g, hot_b] = BALL_HOT_COLOR;
+      tint.0 = Srgba::new(
+        r + (hot_r - r) * heat,
+        g + (hot_g - g) * heat,
+        b + (hot_b - b) * heat,
+        1.,
+      );
+    }
+  }
+}
+
+///
+/// States
+///
+
+#[derive(Default)]
 struct StartState {
   start_ui_text: Option<Entity>,
+  modes_ui_text: Option<Entity>,
   high_score_ui_text: Option<Entity>,
+  custom_levels_ui_text: Option<Entity>,
+  editor_ui_text: Option<Entity>,
+  import_ui_text: Option<Entity>,
+  customize_ui_text: Option<Entity>,
+  data_ui_text: Option<Entity>,
+  credits_ui_text: Option<Entity>,
+  /// Empty until the update check found a newer release.
+  update_ui_text: Option<Entity>,
+  /// A level was left through the pause menu and can be continued.
+  suspended: bool,
+  ui_root: Option<Entity>,
+  progress_counter: Option<ProgressCounter>,
+  text_selected: TextSelectedType,
+  menu_repeat: MenuRepeat,
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for StartState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let world = data.world;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/start.ron", ())));
+    init_camera(world);
+    init_audio(world);
+    // The gameplay sheets are loaded by the states that need them, the menu only waits for its background.
+    self.progress_counter = Some(init_assets(world, vec![AssetType::Background(0)]));
+    self.suspended = Suspended::exists();
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let world = data.world;
+    if let Some(e) = self.ui_root {
+      world.delete_entity(e).expect("Failed to remove start menu!");
+    }
+    self.ui_root = None;
+    self.start_ui_text = None;
+    self.modes_ui_text = None;
+    self.high_score_ui_text = None;
+    self.custom_levels_ui_text = None;
+    self.editor_ui_text = None;
+    self.import_ui_text = None;
+    self.customize_ui_text = None;
+    self.data_ui_text = None;
+    self.credits_ui_text = None;
+    self.update_ui_text = None;
+  }
+
+  fn on_pause(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    set_hidden(data.world, self.ui_root, true);
+  }
+
+  fn on_resume(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    set_hidden(data.world, self.ui_root, false);
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+      if handle_touch(&world, &event).is_some() {
+        return self.confirm(&world);
+      }
+    }
+
+    if let StateEvent::Input(event) = &event {
+      if let InputEvent::KeyPressed { key_code, .. } = event {
+        match key_code {
+          VirtualKeyCode::Escape => return Trans::Quit,
+          VirtualKeyCode::Up | VirtualKeyCode::Down => {
+            self.menu_repeat.press(*key_code);
+            self.step(&world, *key_code);
+          }
+          VirtualKeyCode::Return => return self.confirm(&world),
+          VirtualKeyCode::L => return Trans::Push(Box::new(CalibrationState::default())),
+          VirtualKeyCode::G => return Trans::Push(Box::new(GalleryState::default())),
+          VirtualKeyCode::R => return Trans::Push(Box::new(ReplayState::default())),
+          VirtualKeyCode::C => {
+            if let Some(suspended) = Suspended::take() {
+              play_sound_in_state(&world, SoundType::Confirm);
+              return Trans::Switch(Box::new(PlayState::suspended(suspended)));
+            }
+          }
+          // Also here and not only in the pause menu, so the narration can be turned on without seeing the screen.
+          VirtualKeyCode::N => {
+            let narration = {
+              let mut settings = world.write_resource::<Settings>();
+              settings.narration = !settings.narration;
+              settings.save();
+              settings.narration
+            };
+            if narration {
+              narrate(&world, "NARRATION ON");
+            }
+          }
+          _ => {}
+        }
+      }
+      if let InputEvent::KeyReleased { key_code, .. } = event {
+        self.menu_repeat.release(*key_code);
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    mut data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = &mut data.world;
+
+    let delta_seconds = world.fetch::<Time>().delta_seconds();
+    if let Some(key_code) = self.menu_repeat.tick(delta_seconds) {
+      self.step(&world, key_code);
+    }
+    if self.start_ui_text.is_none() {
+      world.exec(|finder: UiFinder<'_>| {
+        if let Some(entity) = finder.find("start") {
+          self.start_ui_text = Some(entity);
+        }
+      });
+    }
+    if self.modes_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("modes") {
+          self.modes_ui_text = Some(entity);
+        }
+      });
+    }
+    if self.high_score_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("highscore") {
+          self.high_score_ui_text = Some(entity);
+        }
+      });
+    }
+    if self.custom_levels_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("custom_levels") {
+          self.custom_levels_ui_text = Some(entity);
+        }
+      });
+    }
+    if self.editor_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("editor") {
+          self.editor_ui_text = Some(entity);
+        }
+      });
+    }
+    if self.import_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("import") {
+          self.import_ui_text = Some(entity);
+        }
+      });
+    }
+    if self.customize_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("customize") {
+          self.customize_ui_text = Some(entity);
+        }
+      });
+    }
+    if self.data_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("data") {
+          self.data_ui_text = Some(entity);
+        }
+      });
+    }
+    if self.credits_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("credits") {
+          self.credits_ui_text = Some(entity);
+        }
+      });
+    }
+    if self.update_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("update") {
+          self.update_ui_text = Some(entity);
+        }
+      });
+    }
+    if let Some(latest) = world.read_resource::<UpdateNotice>().latest() {
+      let mut ui_text = world.write_storage::<UiText>();
+      if let Some(text) = self.update_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+        text.text = format!("VERSION {} IS AVAILABLE", latest);
+      }
+    } else if self.suspended {
+      let mut ui_text = world.write_storage::<UiText>();
+      if let Some(text) = self.update_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+        text.text = "C TO CONTINUE THE LAST LEVEL".to_string();
+      }
+    }
+    if let Some(ref progress_counter) = self.progress_counter {
+      if progress_counter.is_complete() {
+        let sprite_sheets_map = {
+          let sprite_sheet_map = world.read_resource::<SpriteSheetMap>();
+          sprite_sheet_map.0.clone()
+        };
+
+        for (asset_type, sprite_sheet_handle) in sprite_sheets_map {
+          if let AssetType::Background(sprite_pos) = asset_type {
+            let (width, height) = {
+              let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
+              let spritesheet = sprite_sheet_store
+                .get(&sprite_sheet_handle)
+                .expect("Couldn't find the handle for the background sprite!");
+              (
+                spritesheet.sprites[sprite_pos].width,
+                spritesheet.sprites[sprite_pos].height,
+              )
+            };
+            let scale = (VIRTUAL_WIDTH / (width - 2.), VIRTUAL_HEIGHT / (height - 2.));
+            let mut transform = Layer::Background.transform(VIRTUAL_WIDTH / 2., VIRTUAL_HEIGHT / 2.);
+            transform.set_scale(Vector3::new(scale.0, scale.1, 1.0));
+            world
+              .create_entity()
+              .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
+              .with(transform)
+              .with(Background { scale })
+              .build();
+          }
+        }
+        self.progress_counter = None;
+        if let Some(LaunchedAt(launched_at)) = world.remove::<LaunchedAt>() {
+          info!("Menu ready {:.2}s after launch", launched_at.elapsed().as_secs_f32());
+        }
+      }
+    }
+    data.data.update(&world, true);
+
+    Trans::None
+  }
+}
+
+impl StartState {
+  fn step(&mut self, world: &World, key_code: VirtualKeyCode) {
+    let text_selected = match key_code {
+      VirtualKeyCode::Up => self.text_selected.previous(),
+      _ => self.text_selected.next(),
+    };
+    self.select_text(world, text_selected);
+    play_sound_in_state(world, SoundType::PaddleHit);
+    narrate(world, text_selected.label());
+  }
+
+  fn confirm<'a, 'b>(&self, world: &World) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    play_sound_in_state(&world, SoundType::Confirm);
+    match self.text_selected {
+      TextSelectedType::Start if world.read_resource::<Campaign>().worlds.is_empty() => {
+        Trans::Switch(Box::new(PlayState::new(Level::default())))
+      }
+      TextSelectedType::Start => Trans::Push(Box::new(CampaignState::default())),
+      TextSelectedType::Modes => Trans::Push(Box::new(ModesState::default())),
+      TextSelectedType::HighScore => Trans::Push(Box::new(HighScoreState::default())),
+      TextSelectedType::CustomLevels => Trans::Push(Box::new(LevelBrowserState::default())),
+      TextSelectedType::Editor => Trans::Push(Box::new(EditorState::default())),
+      TextSelectedType::ImportLevel => Trans::Push(Box::new(ImportLevelState::default())),
+      TextSelectedType::Customize => Trans::Push(Box::new(CustomizeState::default())),
+      TextSelectedType::Data => Trans::Push(Box::new(DataState::default())),
+      TextSelectedType::Credits => Trans::Push(Box::new(CreditsState::default())),
+    }
+  }
+
+  fn select_text(&mut self, world: &World, text_selected: TextSelectedType) {
+    let color = |text_type| {
+      if text_type == text_selected {
+        TEXT_SELECTED_COLOR
+      } else {
+        TEXT_COLOR
+      }
+    };
+    let (
+      start_color,
+      modes_color,
+      high_score_color,
+      custom_levels_color,
+      editor_color,
+      import_color,
+      customize_color,
+      data_color,
+      credits_color,
+    ) = (
+      color(TextSelectedType::Start),
+      color(TextSelectedType::Modes),
+      color(TextSelectedType::HighScore),
+      color(TextSelectedType::CustomLevels),
+      color(TextSelectedType::Editor),
+      color(TextSelectedType::ImportLevel),
+      color(TextSelectedType::Customize),
+      color(TextSelectedType::Data),
+      color(TextSelectedType::Credits),
+    );
+    let mut ui_text = world.write_storage::<UiText>();
+    assign_text_color!(self, start_ui_text, ui_text, start_color);
+    assign_text_color!(self, modes_ui_text, ui_text, modes_color);
+    assign_text_color!(self, high_score_ui_text, ui_text, high_score_color);
+    assign_text_color!(self, custom_levels_ui_text, ui_text, custom_levels_color);
+    assign_text_color!(self, editor_ui_text, ui_text, editor_color);
+    assign_text_color!(self, import_ui_text, ui_text, import_color);
+    assign_text_color!(self, customize_ui_text, ui_text, customize_color);
+    assign_text_color!(self, data_ui_text, ui_text, data_color);
+    assign_text_color!(self, credits_ui_text, ui_text, credits_color);
+    self.text_selected = text_selected;
+  }
+}
+
+#[derive(Default)]
+struct ImportLevelState {
+  ui_root: Option<Entity>,
+  code_ui_text: Option<Entity>,
+  message_ui_text: Option<Entity>,
+  code: String,
+}
+
+impl ImportLevelState {
+  fn set_message(&self, world: &World, message: &str, color: [f32; 4]) {
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.message_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = message.to_string();
+      text.color = color;
+    }
+  }
+
+  fn refresh_code(&self, world: &World) {
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.code_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = format!("{}_", self.code);
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for ImportLevelState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/import.ron", ())));
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root {
+      world.delete_entity(e).expect("Failed to remove import menu!");
+    }
+    self.ui_root = None;
+    self.code_ui_text = None;
+    self.message_ui_text = None;
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+      if let Event::WindowEvent {
+        event: WindowEvent::ReceivedCharacter(c),
+        ..
+      } = event
+      {
+        if c.is_ascii_alphanumeric() || *c == '-' || *c == '_' {
+          self.code.push(*c);
+          self.refresh_code(&world);
+        }
+      }
+    }
+
+    if let StateEvent::Input(event) = &event {
+      if let InputEvent::KeyPressed { key_code, .. } = event {
+        match key_code {
+          VirtualKeyCode::Escape => return Trans::Pop,
+          VirtualKeyCode::Back => {
+            self.code.pop();
+            self.refresh_code(&world);
+          }
+          VirtualKeyCode::V => {
+            let input = world.read_resource::<InputHandler<StringBindings>>();
+            if input.key_is_down(VirtualKeyCode::LControl) || input.key_is_down(VirtualKeyCode::RControl) {
+              let contents = ClipboardContext::new().and_then(|mut ctx| ctx.get_contents());
+              match contents {
+                Ok(contents) => {
+                  self.code = contents.trim().to_string();
+                  self.refresh_code(&world);
+                }
+                Err(e) => warn!("Couldn't read the clipboard: {}", e),
+              }
+            }
+          }
+          VirtualKeyCode::Return => match Level::from_code(&self.code) {
+            Ok(level) => {
+              play_sound_in_state(&world, SoundType::Confirm);
+              return Trans::Sequence(vec![Trans::Pop, Trans::Switch(Box::new(PlayState::new(level)))]);
+            }
+            Err(e) => {
+              play_sound_in_state(&world, SoundType::WallHit);
+              self.set_message(&world, &e.to_string(), TEXT_ERROR_COLOR);
+            }
+          },
+          _ => {}
+        }
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if self.code_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("code") {
+          self.code_ui_text = Some(entity);
+        }
+      });
+    }
+    if self.message_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("message") {
+          self.message_ui_text = Some(entity);
+        }
+      });
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+#[derive(Default)]
+struct LevelBrowserState {
+  ui_root: Option<Entity>,
+  sort_ui_text: Option<Entity>,
+  help_ui_text: Option<Entity>,
+  row_ui_texts: Vec<Entity>,
+  levels: Vec<Level>,
+  personal_bests: PersonalBests,
+  sort: LevelSortType,
+  selected: usize,
+  menu_repeat: MenuRepeat,
+  reset_hold: HoldToConfirm,
+  load_errors: Vec<String>,
+  thumbnail: Option<Entity>,
+  /// Index of the level the thumbnail shows, cleared when the levels are sorted again.
+  thumbnail_shown: Option<usize>,
+}
+
+impl LevelBrowserState {
+  fn step(&mut self, world: &World, key_code: VirtualKeyCode) {
+    self.selected = match key_code {
+      VirtualKeyCode::Up => self.selected.saturating_sub(1),
+      _ => (self.selected + 1).min(self.levels.len().saturating_sub(1)),
+    };
+    play_sound_in_state(world, SoundType::PaddleHit);
+    if let Some(level) = self.levels.get(self.selected) {
+      narrate(world, level.name.clone());
+    }
+    self.refresh(world);
+  }
+
+  fn sort_levels(&mut self) {
+    let personal_bests = &self.personal_bests;
+    match self.sort {
+      LevelSortType::Name => self.levels.sort_by(|a, b| a.name.cmp(&b.name)),
+      LevelSortType::Author => self.levels.sort_by(|a, b| a.author.cmp(&b.author)),
+      LevelSortType::Difficulty => self.levels.sort_by_key(|level| level.difficulty),
+      LevelSortType::PersonalBest => self
+        .levels
+        .sort_by_key(|level| std::cmp::Reverse(personal_bests.get(level))),
+    }
+  }
+
+  fn reset_personal_best(&mut self, world: &World) {
+    let level = match self.levels.get(self.selected) {
+      Some(level) => level,
+      None => return,
+    };
+    if let Ok(app_root) = application_root_dir() {
+      self.personal_bests.reset(level);
+      self.personal_bests.save(&app_root.join(CUSTOM_LEVELS_DIR));
+    }
+    play_sound_in_state(world, SoundType::WallHit);
+  }
+
+  fn refresh(&self, world: &World) {
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.sort_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = format!("SORTED BY {} (TAB)", self.sort.label());
+    }
+    if let Some(text) = self.help_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      if self.reset_hold.is_held() {
+        text.text = format!("RESETTING BEST {}", self.reset_hold.indicator());
+        text.color = TEXT_ERROR_COLOR;
+      } else {
+        text.text = "ENTER TO PLAY - HOLD DEL TO RESET BEST - ESC TO GO BACK".to_string();
+        text.color = TEXT_COLOR;
+      }
+    }
+
+    let offset = (self.selected + 1).saturating_sub(LEVEL_BROWSER_ROWS);
+    for (row, &entity) in self.row_ui_texts.iter().enumerate() {
+      if let Some(text) = ui_text.get_mut(entity) {
+        let index = offset + row;
+        match self.levels.get(index) {
+          Some(level) => {
+            let mut best = self
+              .personal_bests
+              .get(level)
+              .map_or_else(|| "-".to_string(), |best| best.to_string());
+            if self.personal_bests.is_modified() {
+              best.push_str(" (MODIFIED)");
+            }
+            text.text = format!(
+              "{}  BY {}  {}  BEST {}",
+              level.name, level.author, level.difficulty, best
+            );
+            text.color = if index == self.selected {
+              TEXT_SELECTED_COLOR
+            } else {
+              TEXT_COLOR
+            };
+          }
+          None if index == 0 => {
+            text.text = format!("NO LEVELS FOUND IN {}/", CUSTOM_LEVELS_DIR);
+            text.color = TEXT_COLOR;
+          }
+          None => text.text.clear(),
+        }
+      }
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for LevelBrowserState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/levels.ron", ())));
+    if let Ok(app_root) = application_root_dir() {
+      let dir = app_root.join(CUSTOM_LEVELS_DIR);
+      for (path, level) in load_custom_levels(&dir) {
+        match level {
+          Ok(level) => self.levels.push(level),
+          Err(e) => {
+            let file = path
+              .file_name()
+              .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+            self.load_errors.push(format!("{}: {}", file, e));
+          }
+        }
+      }
+      self.personal_bests = PersonalBests::load(&dir);
+    }
+    self.sort_levels();
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root {
+      world.delete_entity(e).expect("Failed to remove level browser!");
+    }
+    self.ui_root = None;
+    self.sort_ui_text = None;
+    self.help_ui_text = None;
+    self.row_ui_texts.clear();
+    show_thumbnail(world, &mut self.thumbnail, None);
+    self.thumbnail_shown = None;
+  }
+
+  fn on_pause(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    set_hidden(data.world, self.ui_root, true);
+    set_hidden(data.world, self.thumbnail, true);
+  }
+
+  fn on_resume(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    set_hidden(data.world, self.ui_root, false);
+    set_hidden(data.world, self.thumbnail, false);
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(event) = &event {
+      if let InputEvent::KeyReleased { key_code, .. } = event {
+        self.menu_repeat.release(*key_code);
+        self.reset_hold.release(*key_code);
+        self.refresh(&world);
+      }
+      if let InputEvent::KeyPressed { key_code, .. } = event {
+        match key_code {
+          VirtualKeyCode::Escape => return Trans::Pop,
+          VirtualKeyCode::Up | VirtualKeyCode::Down => {
+            self.menu_repeat.press(*key_code);
+            self.step(&world, *key_code);
+          }
+          VirtualKeyCode::Delete => self.reset_hold.press(*key_code),
+          VirtualKeyCode::Tab => {
+            self.sort = self.sort.next();
+            self.sort_levels();
+            self.selected = 0;
+            self.thumbnail_shown = None;
+            play_sound_in_state(&world, SoundType::PaddleHit);
+          }
+          VirtualKeyCode::Return => {
+            if let Some(level) = self.levels.get(self.selected).cloned() {
+              play_sound_in_state(&world, SoundType::Confirm);
+              return Trans::Sequence(vec![Trans::Pop, Trans::Switch(Box::new(PlayState::new(level)))]);
+            }
+          }
+          _ => {}
+        }
+        self.refresh(&world);
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    let delta_seconds = world.fetch::<Time>().delta_seconds();
+    if let Some(key_code) = self.menu_repeat.tick(delta_seconds) {
+      self.step(&world, key_code);
+    }
+    if self.reset_hold.is_held() {
+      if self.reset_hold.tick(delta_seconds).is_some() {
+        self.reset_personal_best(&world);
+      }
+      self.refresh(&world);
+    }
+    if self.sort_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("sort") {
+          self.sort_ui_text = Some(entity);
+        }
+        self.help_ui_text = finder.find("levels_help");
+      });
+    }
+    if self.row_ui_texts.is_empty() {
+      world.exec(|finder: UiFinder| {
+        self.row_ui_texts = (0..LEVEL_BROWSER_ROWS)
+          .map(|row| finder.find(&format!("level_{}", row)))
+          .collect::<Option<Vec<_>>>()
+          .unwrap_or_default();
+      });
+      self.refresh(&world);
+    }
+    if self.thumbnail_shown != Some(self.selected) {
+      show_thumbnail(world, &mut self.thumbnail, self.levels.get(self.selected));
+      self.thumbnail_shown = Some(self.selected);
+    }
+    data.data.update(&world, false);
+
+    if !self.load_errors.is_empty() {
+      let lines = std::mem::replace(&mut self.load_errors, vec![]);
+      return Trans::Push(Box::new(ErrorDialogState::new("SOME LEVELS COULDN'T BE LOADED", lines)));
+    }
+
+    Trans::None
+  }
+}
+
+/// Lists the campaign worlds and their levels, locked worlds are shown but can't be started. Once the campaign
+/// has been cleared tab switches to New Game+.
+#[derive(Default)]
+struct CampaignState {
+  ui_root: Option<Entity>,
+  title_ui_text: Option<Entity>,
+  row_ui_texts: Vec<Entity>,
+  entries: Vec<(CampaignLevel, Level)>,
+  selected: usize,
+  menu_repeat: MenuRepeat,
+  load_errors: Vec<String>,
+  /// Set when the campaign screen replaced the play state instead of being pushed from the start menu.
+  root: bool,
+  new_game_plus: bool,
+  thumbnail: Option<Entity>,
+  thumbnail_shown: Option<usize>,
+}
+
+impl CampaignState {
+  fn root() -> Self {
+    CampaignState {
+      root: true,
+      ..Default::default()
+    }
+  }
+
+  fn step(&mut self, world: &World, key_code: VirtualKeyCode) {
+    self.selected = match key_code {
+      VirtualKeyCode::Up => self.selected.saturating_sub(1),
+      _ => (self.selected + 1).min(self.entries.len().saturating_sub(1)),
+    };
+    play_sound_in_state(world, SoundType::PaddleHit);
+    self.refresh(world);
+  }
+
+  fn refresh(&self, world: &World) {
+    let campaign = world.read_resource::<Campaign>();
+    let progress = world.read_resource::<Progress>();
+    let cleared = progress.cleared(self.new_game_plus);
+    let mut ui_text = world.write_storage::<UiText>();
+
+    if let Some(text) = self.title_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = if self.new_game_plus {
+        "NEW GAME+".to_string()
+      } else {
+        "CAMPAIGN".to_string()
+      };
+    }
+    let offset = (self.selected + 1).saturating_sub(LEVEL_BROWSER_ROWS);
+    for (row, &entity) in self.row_ui_texts.iter().enumerate() {
+      if let Some(text) = ui_text.get_mut(entity) {
+        let index = offset + row;
+        match self.entries.get(index) {
+          Some((at, level)) => {
+            let campaign_world = &campaign.worlds[at.world];
+            let unlocked = campaign.is_unlocked(at.world, cleared);
+            let status = match campaign.file(*at) {
+              _ if !unlocked => "LOCKED",
+              Some(file) if cleared.contains(file) => "CLEARED",
+              _ => "",
+            };
+            let boss = if campaign_world.is_boss(at.level) { "BOSS " } else { "" };
+            text.text = format!(
+              "{} {}  {}{}  {}",
+              campaign_world.name,
+              at.level + 1,
+              boss,
+              level.name,
+              status
+            )
+            .to_uppercase();
+            text.color = if index == self.selected {
+              TEXT_SELECTED_COLOR
+            } else if !unlocked {
+              HUD_DIM_COLOR
+            } else {
+              TEXT_COLOR
+            };
+          }
+          None if index == 0 => {
+            text.text = format!("NO LEVELS FOUND IN {}/", campaign::CAMPAIGN_DIR);
+            text.color = TEXT_COLOR;
+          }
+          None => text.text.clear(),
+        }
+      }
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for CampaignState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/campaign.ron", ())));
+    release_gameplay_assets(world);
+    let campaign = world.read_resource::<Campaign>();
+    for at in campaign.levels() {
+      match campaign.load_level(at) {
+        Some(Ok(level)) => self.entries.push((at, level)),
+        Some(Err(e)) => {
+          let file = campaign.file(at).cloned().unwrap_or_default();
+          self.load_errors.push(format!("{}: {}", file, e));
+        }
+        None => {}
+      }
+    }
+    let resume = *world.read_resource::<Progress>().resume(self.new_game_plus);
+    if let Some(index) = self.entries.iter().position(|(at, _)| Some(*at) == resume) {
+      self.selected = index;
+    }
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove campaign menu!");
+    }
+    self.title_ui_text = None;
+    self.row_ui_texts.clear();
+    show_thumbnail(world, &mut self.thumbnail, None);
+    self.thumbnail_shown = None;
+  }
+
+  fn on_pause(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    set_hidden(data.world, self.ui_root, true);
+    set_hidden(data.world, self.thumbnail, true);
+  }
+
+  fn on_resume(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    set_hidden(data.world, self.ui_root, false);
+    set_hidden(data.world, self.thumbnail, false);
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(event) = &event {
+      if let InputEvent::KeyReleased { key_code, .. } = event {
+        self.menu_repeat.release(*key_code);
+      }
+      if let InputEvent::KeyPressed { key_code, .. } = event {
+        match key_code {
+          VirtualKeyCode::Escape if self.root => return Trans::Quit,
+          VirtualKeyCode::Escape => return Trans::Pop,
+          VirtualKeyCode::Up | VirtualKeyCode::Down => {
+            self.menu_repeat.press(*key_code);
+            self.step(&world, *key_code);
+          }
+          VirtualKeyCode::Tab => {
+            if world.read_resource::<Progress>().new_game_plus {
+              self.new_game_plus = !self.new_game_plus;
+              play_sound_in_state(&world, SoundType::PaddleHit);
+              self.refresh(&world);
+            } else {
+              play_sound_in_state(&world, SoundType::WallHit);
+            }
+          }
+          VirtualKeyCode::Return => {
+            if let Some((at, level)) = self.entries.get(self.selected).cloned() {
+              let unlocked = {
+                let progress = world.read_resource::<Progress>();
+                world
+                  .read_resource::<Campaign>()
+                  .is_unlocked(at.world, progress.cleared(self.new_game_plus))
+              };
+              if !unlocked {
+                play_sound_in_state(&world, SoundType::WallHit);
+                return Trans::None;
+              }
+              play_sound_in_state(&world, SoundType::Confirm);
+              let play = Trans::Switch(campaign_level_state(
+                &world.read_resource::<Campaign>(),
+                level,
+                at,
+                self.new_game_plus,
+              ));
+              if self.root {
+                return play;
+              }
+              return Trans::Sequence(vec![Trans::Pop, play]);
+            }
+          }
+          _ => {}
+        }
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    let delta_seconds = world.fetch::<Time>().delta_seconds();
+    if let Some(key_code) = self.menu_repeat.tick(delta_seconds) {
+      self.step(&world, key_code);
+    }
+    if self.row_ui_texts.is_empty() {
+      world.exec(|finder: UiFinder| {
+        self.title_ui_text = finder.find("campaign_title");
+        self.row_ui_texts = (0..LEVEL_BROWSER_ROWS)
+          .map(|row| finder.find(&format!("campaign_{}", row)))
+          .collect::<Option<Vec<_>>>()
+          .unwrap_or_default();
+      });
+      self.refresh(&world);
+    }
+    if self.thumbnail_shown != Some(self.selected) {
+      let level = self.entries.get(self.selected).map(|(_, level)| level);
+      show_thumbnail(world, &mut self.thumbnail, level);
+      self.thumbnail_shown = Some(self.selected);
+    }
+    data.data.update(&world, false);
+
+    if !self.load_errors.is_empty() {
+      let lines = std::mem::replace(&mut self.load_errors, vec![]);
+      return Trans::Push(Box::new(ErrorDialogState::new(
+        "SOME CAMPAIGN LEVELS COULDN'T BE LOADED",
+        lines,
+      )));
+    }
+
+    Trans::None
+  }
+}
+
+/// Starts a campaign level, the first level of a world is preceded by the intro cutscene of the world.
+fn campaign_level_state<'a, 'b>(
+  campaign: &Campaign,
+  level: Level,
+  at: CampaignLevel,
+  new_game_plus: bool,
+) -> Box<dyn State<BreakoutGameData<'a, 'b>, StateEvent>> {
+  let level = if new_game_plus { level.remixed() } else { level };
+  match campaign.load_intro(at.world).filter(|_| at.level == 0) {
+    Some(cutscene) if !cutscene.cards.is_empty() => {
+      Box::new(CutsceneState::new(cutscene.cards, Some((level, at, new_game_plus))))
+    }
+    _ => Box::new(PlayState::campaign(level, at, new_game_plus)),
+  }
+}
+
+/// Plays the ending cutscene of the campaign followed by the credits, both return to the campaign screen.
+fn campaign_ending_trans<'a, 'b>(campaign: &Campaign) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+  match campaign.load_ending() {
+    Some(cutscene) if !cutscene.cards.is_empty() => Trans::Switch(Box::new(CutsceneState::new(cutscene.cards, None))),
+    _ => campaign_credits_trans(),
+  }
+}
+
+fn campaign_credits_trans<'a, 'b>() -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+  Trans::Sequence(vec![
+    Trans::Switch(Box::new(CampaignState::root())),
+    Trans::Push(Box::new(CreditsState::default())),
+  ])
+}
+
+/// Shows story cards with a fade to black in between, then starts the level it was created for. Space or enter
+/// skips the current card and escape skips the whole cutscene. Without a level it is the campaign ending and rolls
+/// the credits afterwards.
+struct CutsceneState {
+  ui_root: Option<Entity>,
+  text_ui_text: Option<Entity>,
+  fade_ui_image: Option<Entity>,
+  image_entity: Option<Entity>,
+  cards: Vec<Card>,
+  shown: Option<usize>,
+  index: usize,
+  elapsed: f32,
+  next: Option<(Level, CampaignLevel, bool)>,
+}
+
+impl CutsceneState {
+  fn new(cards: Vec<Card>, next: Option<(Level, CampaignLevel, bool)>) -> Self {
+    CutsceneState {
+      ui_root: None,
+      text_ui_text: None,
+      fade_ui_image: None,
+      image_entity: None,
+      cards,
+      shown: None,
+      index: 0,
+      elapsed: 0.,
+      next,
+    }
+  }
+
+  fn show_card(&mut self, world: &mut World) {
+    let card = &self.cards[self.index];
+    {
+      let mut ui_text = world.write_storage::<UiText>();
+      if let Some(text) = self.text_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+        text.text = card.text.to_uppercase();
+      }
+    }
+
+    if let Some(e) = self.image_entity.take() {
+      world.delete_entity(e).expect("Failed to remove cutscene image!");
+    }
+    if let Some(image) = &card.image {
+      let texture = {
+        let loader = world.read_resource::<Loader>();
+        loader.load(
+          image.as_str(),
+          ImageFormat::default(),
+          (),
+          &world.read_resource::<AssetStorage<Texture>>(),
+        )
+      };
+      let transform = UiTransform::new(
+        "cutscene_image".to_string(),
+        Anchor::Middle,
+        Anchor::Middle,
+        0.,
+        100.,
+        1.,
+        640.,
+        360.,
+      );
+      self.image_entity = Some(
+        world
+          .create_entity()
+          .with(transform)
+          .with(UiImage::Texture(texture))
+          .build(),
+      );
+    }
+    self.shown = Some(self.index);
+  }
+
+  fn finish<'a, 'b>(&self) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    match self.next.clone() {
+      Some((level, at, new_game_plus)) => Trans::Switch(Box::new(PlayState::campaign(level, at, new_game_plus))),
+      None => campaign_credits_trans(),
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for CutsceneState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/cutscene.ron", ())));
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    for e in self.ui_root.take().into_iter().chain(self.image_entity.take()) {
+      world.delete_entity(e).expect("Failed to remove cutscene!");
+    }
+    self.text_ui_text = None;
+    self.fade_ui_image = None;
+  }
+
+  fn handle_event(
+    &mut self,
+    _data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      match key_code {
+        VirtualKeyCode::Escape => return self.finish(),
+        VirtualKeyCode::Space | VirtualKeyCode::Return => {
+          if let Some(card) = self.cards.get(self.index) {
+            let fade_out_start = card.total_seconds() - cutscene::CUTSCENE_FADE_SECONDS;
+            self.elapsed = self.elapsed.max(fade_out_start);
+          }
+        }
+        _ => {}
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if self.text_ui_text.is_none() || self.fade_ui_image.is_none() {
+      world.exec(|finder: UiFinder| {
+        self.text_ui_text = finder.find("cutscene_text");
+        self.fade_ui_image = finder.find("cutscene_fade");
+      });
+    }
+    data.data.update(&world, false);
+
+    let card = match self.cards.get(self.index) {
+      Some(card) => card.clone(),
+      None => return self.finish(),
+    };
+    if self.shown != Some(self.index) {
+      self.show_card(world);
+    }
+
+    self.elapsed += world.fetch::<Time>().delta_seconds();
+    {
+      let mut ui_image = world.write_storage::<UiImage>();
+      if let Some(image) = self.fade_ui_image.and_then(|entity| ui_image.get_mut(entity)) {
+        *image = UiImage::SolidColor([0., 0., 0., card.fade(self.elapsed)]);
+      }
+    }
+    if self.elapsed >= card.total_seconds() {
+      self.index += 1;
+      self.elapsed = 0.;
+    }
+
+    Trans::None
+  }
+}
+
+/// Scrolls the credits from the bottom to the top of the screen and returns to the menu once they are gone.
+struct CreditsState {
+  ui_root: Option<Entity>,
+  text_entity: Option<Entity>,
+  speed: f32,
+}
+
+impl Default for CreditsState {
+  fn default() -> Self {
+    CreditsState {
+      ui_root: None,
+      text_entity: None,
+      speed: 1.,
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for CreditsState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/credits.ron", ())));
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove credits!");
+    }
+    self.text_entity = None;
+  }
+
+  fn handle_event(
+    &mut self,
+    _data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      match key_code {
+        VirtualKeyCode::Escape | VirtualKeyCode::Return => return Trans::Pop,
+        VirtualKeyCode::Up => self.speed = (self.speed * 2.).min(CREDITS_MAX_SPEED),
+        VirtualKeyCode::Down => self.speed = (self.speed / 2.).max(CREDITS_MIN_SPEED),
+        _ => {}
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    let screen_height = world.read_resource::<ScreenDimensions>().height();
+    if self.text_entity.is_none() {
+      world.exec(
+        |(finder, mut ui_text, mut ui_transforms): (UiFinder, WriteStorage<UiText>, WriteStorage<UiTransform>)| {
+          if let Some(entity) = finder.find("credits_text") {
+            let lines = credits::load();
+            if let Some(text) = ui_text.get_mut(entity) {
+              text.text = lines.join("\n");
+            }
+            if let Some(transform) = ui_transforms.get_mut(entity) {
+              transform.height = lines.len() as f32 * CREDITS_LINE_HEIGHT;
+              transform.local_y = -(screen_height + transform.height) / 2.;
+            }
+            self.text_entity = Some(entity);
+          }
+        },
+      );
+    }
+    data.data.update(&world, false);
+
+    let delta_seconds = world.fetch::<Time>().delta_seconds();
+    let mut ui_transforms = world.write_storage::<UiTransform>();
+    if let Some(transform) = self.text_entity.and_then(|entity| ui_transforms.get_mut(entity)) {
+      transform.local_y += CREDITS_SCROLL_SPEED * self.speed * delta_seconds;
+      if transform.local_y - transform.height / 2. > screen_height / 2. {
+        return Trans::Pop;
+      }
+    }
+
+    Trans::None
+  }
+}
+
+/// Picks the paddle and ball skins. Locked skins can be previewed but not selected, the selection is saved with
+/// the campaign progress.
+#[derive(Default)]
+struct CustomizeState {
+  ui_root: Option<Entity>,
+  paddle_ui_text: Option<Entity>,
+  ball_ui_text: Option<Entity>,
+  status_ui_text: Option<Entity>,
+  previews: Vec<Entity>,
+  /// `0` is the paddle row and `1` the ball row.
+  row: usize,
+  paddle: usize,
+  ball: usize,
+}
+
+impl CustomizeState {
+  fn skins(&self) -> [(&'static [Skin], usize); 2] {
+    [(&skins::PADDLE_SKINS, self.paddle), (&skins::BALL_SKINS, self.ball)]
+  }
+
+  fn change(&mut self, world: &World, key_code: VirtualKeyCode) {
+    let (count, index) = match self.row {
+      0 => (skins::PADDLE_SKINS.len(), &mut self.paddle),
+      _ => (skins::BALL_SKINS.len(), &mut self.ball),
+    };
+    *index = match key_code {
+      VirtualKeyCode::Left => (*index + count - 1) % count,
+      _ => (*index + 1) % count,
+    };
+    play_sound_in_state(world, SoundType::PaddleHit);
+
+    let (skins, index) = self.skins()[self.row];
+    let skin = skins[index];
+    let unlocked = skin
+      .unlock
+      .is_unlocked(&world.read_resource::<Campaign>(), &world.read_resource::<Progress>());
+    if unlocked {
+      let mut progress = world.write_resource::<Progress>();
+      match self.row {
+        0 => progress.paddle_skin = skin.name.to_string(),
+        _ => progress.ball_skin = skin.name.to_string(),
+      }
+      progress.save();
+    }
+    self.refresh(world);
+  }
+
+  fn refresh(&self, world: &World) {
+    let campaign = world.read_resource::<Campaign>();
+    let progress = world.read_resource::<Progress>();
+    let mut ui_text = world.write_storage::<UiText>();
+
+    let rows = [("PADDLE", self.paddle_ui_text), ("BALL", self.ball_ui_text)];
+    for (row, ((label, entity), (skins, index))) in rows.iter().zip(self.skins().iter()).enumerate() {
+      if let Some(text) = entity.and_then(|entity| ui_text.get_mut(entity)) {
+        text.text = format!("{}  < {} >", label, skins[*index].name).to_uppercase();
+        text.color = if row == self.row {
+          TEXT_SELECTED_COLOR
+        } else {
+          TEXT_COLOR
+        };
+      }
+    }
+
+    let (skins, index) = self.skins()[self.row];
+    let unlock = skins[index].unlock;
+    if let Some(text) = self.status_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      if unlock.is_unlocked(&campaign, &progress) {
+        text.text = "UNLOCKED".to_string();
+        text.color = HUD_DIM_COLOR;
+      } else {
+        text.text = format!("LOCKED - {}", unlock.describe()).to_uppercase();
+        text.color = TEXT_ERROR_COLOR;
+      }
+    }
+
+    let mut sprites = world.write_storage::<SpriteRender>();
+    let mut tints = world.write_storage::<Tint>();
+    for (&entity, (skins, index)) in self.previews.iter().zip(self.skins().iter()) {
+      let skin = skins[*index];
+      let [r, g, b] = skin.tint;
+      let alpha = if skin.unlock.is_unlocked(&campaign, &progress) {
+        1.
+      } else {
+        0.3
+      };
+      if let Some(sprite) = sprites.get_mut(entity) {
+        sprite.sprite_number = skin.sprite;
+      }
+      if let Some(tint) = tints.get_mut(entity) {
+        tint.0 = Srgba::new(r * alpha, g * alpha, b * alpha, 1.);
+      }
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for CustomizeState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/customize.ron", ())));
+    load_assets(world, &AssetType::GAMEPLAY);
+    {
+      let campaign = world.read_resource::<Campaign>();
+      let progress = world.read_resource::<Progress>();
+      self.paddle = skins::selected(&skins::PADDLE_SKINS, &progress.paddle_skin, &campaign, &progress);
+      self.ball = skins::selected(&skins::BALL_SKINS, &progress.ball_skin, &campaign, &progress);
+    }
+    if let Some(sprite_sheet_handle) = get_skin_sprite_sheet(world) {
+      let positions = [VIRTUAL_WIDTH / 2. - 20., VIRTUAL_WIDTH / 2. + 30.];
+      for (x, (skins, index)) in positions.iter().zip(self.skins().iter()) {
+        let preview = world
+          .create_entity()
+          .with(SpriteRender::new(sprite_sheet_handle.clone(), skins[*index].sprite))
+          .with(Layer::Overlay.transform(*x, 50.))
+          .with(Tint(Srgba::new(1., 1., 1., 1.)))
+          .build();
+        self.previews.push(preview);
+      }
+    }
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    for e in self.ui_root.take().into_iter().chain(self.previews.drain(..)) {
+      world.delete_entity(e).expect("Failed to remove customize menu!");
+    }
+    self.paddle_ui_text = None;
+    self.ball_ui_text = None;
+    self.status_ui_text = None;
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      match key_code {
+        VirtualKeyCode::Escape | VirtualKeyCode::Return => return Trans::Pop,
+        VirtualKeyCode::Up | VirtualKeyCode::Down => {
+          self.row = 1 - self.row;
+          play_sound_in_state(&world, SoundType::PaddleHit);
+          self.refresh(&world);
+        }
+        VirtualKeyCode::Left | VirtualKeyCode::Right => self.change(&world, *key_code),
+        _ => {}
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if self.paddle_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        self.paddle_ui_text = finder.find("customize_paddle");
+        self.ball_ui_text = finder.find("customize_ball");
+        self.status_ui_text = finder.find("customize_status");
+      });
+      if self.paddle_ui_text.is_some() {
+        self.refresh(&world);
+      }
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+/// Resets the saved data, every reset has to be confirmed by holding enter. Also shows where the files live.
+#[derive(Default)]
+struct DataState {
+  ui_root: Option<Entity>,
+  row_ui_texts: Vec<Entity>,
+  status_ui_text: Option<Entity>,
+  paths_ui_text: Option<Entity>,
+  selected: usize,
+  reset_hold: HoldToConfirm,
+  message: Option<(String, [f32; 4])>,
+}
+
+impl DataState {
+  fn reset(&mut self, world: &mut World) {
+    let reset = DataResetType::ALL[self.selected];
+    let mut errors = vec![];
+    let mut remove = |path: Option<PathBuf>| {
+      if let Some(path) = path {
+        if let Err(e) = remove_save_file(&path) {
+          errors.push(format!("{}: {}", path.display(), e));
+        }
+      }
+    };
+
+    if reset.resets_high_scores() {
+      remove(
+        application_root_dir()
+          .ok()
+          .map(|app_root| PersonalBests::path(&app_root.join(CUSTOM_LEVELS_DIR))),
+      );
+    }
+    if reset.resets_progress() {
+      remove(Progress::path());
+      world.insert(Progress::default());
+    }
+    if reset.resets_settings() {
+      remove(Settings::path());
+      let settings = Settings::default();
+      if let Some(bindings) = settings.controls.load_bindings() {
+        world.write_resource::<InputHandler<StringBindings>>().bindings = bindings;
+      }
+      world.insert(settings);
+    }
+
+    self.message = Some(if errors.is_empty() {
+      play_sound_in_state(world, SoundType::WallHit);
+      (format!("{} RESET", reset.label()), TEXT_SELECTED_COLOR)
+    } else {
+      for error in &errors {
+        warn!("Couldn't delete {}", error);
+      }
+      (format!("COULDN'T DELETE {}", errors.join(", ")), TEXT_ERROR_COLOR)
+    });
+  }
+
+  fn refresh(&self, world: &World) {
+    let mut ui_text = world.write_storage::<UiText>();
+
+    for (row, &entity) in self.row_ui_texts.iter().enumerate() {
+      if let Some(text) = ui_text.get_mut(entity) {
+        let reset = DataResetType::ALL[row];
+        text.text = format!("RESET {}", reset.label());
+        text.color = if row == self.selected {
+          TEXT_SELECTED_COLOR
+        } else {
+          TEXT_COLOR
+        };
+      }
+    }
+    if let Some(text) = self.status_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      if self.reset_hold.is_held() {
+        text.text = format!("HOLD TO RESET {}", self.reset_hold.indicator());
+        text.color = TEXT_ERROR_COLOR;
+      } else if let Some((message, color)) = &self.message {
+        text.text = message.clone();
+        text.color = *color;
+      } else {
+        text.text.clear();
+      }
+    }
+    if let Some(text) = self.paths_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      let high_scores = application_root_dir()
+        .ok()
+        .map(|app_root| PersonalBests::path(&app_root.join(CUSTOM_LEVELS_DIR)));
+      text.text = [
+        ("SETTINGS", Settings::path()),
+        ("PROGRESS", Progress::path()),
+        ("HIGH SCORES", high_scores),
+      ]
+      .iter()
+      .map(|(label, path)| {
+        let path = path
+          .as_ref()
+          .map_or_else(|| "-".to_string(), |path| path.display().to_string());
+        format!("{}: {}", label, path)
+      })
+      .collect::<Vec<_>>()
+      .join("\n");
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for DataState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/data.ron", ())));
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove save data menu!");
+    }
+    self.row_ui_texts.clear();
+    self.status_ui_text = None;
+    self.paths_ui_text = None;
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(event) = &event {
+      if let InputEvent::KeyReleased { key_code, .. } = event {
+        self.reset_hold.release(*key_code);
+        self.refresh(&world);
+      }
+      if let InputEvent::KeyPressed { key_code, .. } = event {
+        match key_code {
+          VirtualKeyCode::Escape => return Trans::Pop,
+          VirtualKeyCode::Up | VirtualKeyCode::Down if !self.reset_hold.is_held() => {
+            self.selected = match key_code {
+              VirtualKeyCode::Up => self.selected.saturating_sub(1),
+              _ => (self.selected + 1).min(DataResetType::ALL.len() - 1),
+            };
+            self.message = None;
+            play_sound_in_state(&world, SoundType::PaddleHit);
+            narrate(&world, DataResetType::ALL[self.selected].label());
+          }
+          VirtualKeyCode::Return => self.reset_hold.press(*key_code),
+          _ => {}
+        }
+        self.refresh(&world);
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if self.reset_hold.is_held() {
+      let delta_seconds = world.fetch::<Time>().delta_seconds();
+      if self.reset_hold.tick(delta_seconds).is_some() {
+        self.reset(world);
+      }
+      self.refresh(&world);
+    }
+    if self.row_ui_texts.is_empty() {
+      world.exec(|finder: UiFinder| {
+        self.row_ui_texts = (0..DataResetType::ALL.len())
+          .map(|row| finder.find(&format!("data_{}", row)))
+          .collect::<Option<Vec<_>>>()
+          .unwrap_or_default();
+        self.status_ui_text = finder.find("data_status");
+        self.paths_ui_text = finder.find("data_paths");
+      });
+      self.refresh(&world);
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+/// Lists the play modes with the best score recorded for each, enter starts the selected mode.
+#[derive(Default)]
+struct ModesState {
+  ui_root: Option<Entity>,
+  row_ui_texts: Vec<Entity>,
+  mutators_ui_text: Option<Entity>,
+  selected: usize,
+  mutators: Mutators,
+  personal_bests: PersonalBests,
+}
+
+impl ModesState {
+  fn refresh(&self, world: &World) {
+    let mut ui_text = world.write_storage::<UiText>();
+
+    for (row, &entity) in self.row_ui_texts.iter().enumerate() {
+      if let Some(text) = ui_text.get_mut(entity) {
+        let mode = PlayMode::SELECTABLE[row];
+        text.text = match mode.leaderboard() {
+          Some(leaderboard) => {
+            let best = self.personal_bests.best(&leaderboard);
+            let mut line = format!(
+              "{}  BEST {}",
+              mode.label(),
+              best.map_or_else(|| "-".to_string(), |best| best.to_string())
+            );
+            if best.is_some() {
+              for label in self.personal_bests.flags(&leaderboard).labels() {
+                line.push_str("  ");
+                line.push_str(label);
+              }
+            }
+            line
+          }
+          None => mode.label().to_string(),
+        };
+        text.color = if row == self.selected {
+          TEXT_SELECTED_COLOR
+        } else {
+          TEXT_COLOR
+        };
+      }
+    }
+    if let Some(text) = self.mutators_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = self.mutators.label();
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for ModesState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/modes.ron", ())));
+    if let Ok(app_root) = application_root_dir() {
+      self.personal_bests = PersonalBests::load(&app_root.join(CUSTOM_LEVELS_DIR));
+    }
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove modes menu!");
+    }
+    self.row_ui_texts.clear();
+    self.mutators_ui_text = None;
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      match key_code {
+        VirtualKeyCode::Escape => return Trans::Pop,
+        VirtualKeyCode::Up | VirtualKeyCode::Down => {
+          self.selected = match key_code {
+            VirtualKeyCode::Up => self.selected.saturating_sub(1),
+            _ => (self.selected + 1).min(PlayMode::SELECTABLE.len() - 1),
+          };
+          play_sound_in_state(&world, SoundType::PaddleHit);
+          narrate(&world, PlayMode::SELECTABLE[self.selected].label());
+          self.refresh(&world);
+        }
+        VirtualKeyCode::M => {
+          self.mutators.mirrored = !self.mutators.mirrored;
+          play_sound_in_state(&world, SoundType::PaddleHit);
+          self.refresh(&world);
+        }
+        VirtualKeyCode::T => {
+          play_sound_in_state(&world, SoundType::Confirm);
+          return Trans::Push(Box::new(TournamentState::default()));
+        }
+        VirtualKeyCode::Return => {
+          play_sound_in_state(&world, SoundType::Confirm);
+          let mode = PlayMode::SELECTABLE[self.selected];
+          if mode.players() > 1 {
+            return Trans::Push(Box::new(JoinState::new(mode, self.mutators)));
+          }
+          return Trans::Sequence(vec![
+            Trans::Pop,
+            Trans::Switch(Box::new(PlayState::mode(mode, self.mutators))),
+          ]);
+        }
+        _ => {}
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if self.row_ui_texts.is_empty() {
+      world.exec(|finder: UiFinder| {
+        self.row_ui_texts = (0..PlayMode::SELECTABLE.len())
+          .map(|row| finder.find(&format!("modes_{}", row)))
+          .collect::<Option<Vec<_>>>()
+          .unwrap_or_default();
+        self.mutators_ui_text = finder.find("modes_mutators");
+      });
+      self.refresh(&world);
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+/// The best scores of the mode leaderboards with how the runs were played. C switches to the best clean runs and S
+/// sorts by score instead of the order of the modes menu.
+#[derive(Default)]
+struct HighScoreState {
+  ui_root: Option<Entity>,
+  table_ui_text: Option<Entity>,
+  help_ui_text: Option<Entity>,
+  personal_bests: PersonalBests,
+  /// Shows the best clean runs instead of the best of all runs.
+  clean_only: bool,
+  by_score: bool,
+}
+
+impl HighScoreState {
+  fn refresh(&self, world: &World) {
+    let mut rows = PlayMode::SELECTABLE
+      .iter()
+      .filter_map(|mode| mode.leaderboard().map(|leaderboard| (mode, leaderboard)))
+      .map(|(mode, leaderboard)| {
+        if self.clean_only {
+          (mode, self.personal_bests.best_clean(&leaderboard), RunFlags::default())
+        } else {
+          (
+            mode,
+            self.personal_bests.best(&leaderboard),
+            self.personal_bests.flags(&leaderboard),
+          )
+        }
+      })
+      .collect::<Vec<_>>();
+    if self.by_score {
+      // Modes without a record go last.
+      rows.sort_by_key(|&(_, best, _)| std::cmp::Reverse(best));
+    }
+    let table = rows
+      .iter()
+      .map(|(mode, best, flags)| {
+        let mut line = format!(
+          "{}  {}",
+          mode.label(),
+          best.map_or_else(|| "-".to_string(), |best| best.to_string())
+        );
+        if best.is_some() {
+          for label in flags.labels() {
+            line.push_str("  ");
+            line.push_str(label);
+          }
+        }
+        line
+      })
+      .collect::<Vec<_>>()
+      .join("\n");
+
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.table_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = table;
+    }
+    if let Some(text) = self.help_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = format!(
+        "C FOR {} - S TO SORT BY {} - ESC TO GO BACK",
+        if self.clean_only { "ALL RUNS" } else { "CLEAN RUNS" },
+        if self.by_score { "MODE" } else { "SCORE" }
+      );
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for HighScoreState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/high_scores.ron", ())));
+    if let Ok(app_root) = application_root_dir() {
+      self.personal_bests = PersonalBests::load(&app_root.join(CUSTOM_LEVELS_DIR));
+    }
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove high scores!");
+    }
+    self.table_ui_text = None;
+    self.help_ui_text = None;
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      match key_code {
+        VirtualKeyCode::Escape => return Trans::Pop,
+        VirtualKeyCode::C => {
+          self.clean_only = !self.clean_only;
+          play_sound_in_state(&world, SoundType::PaddleHit);
+          narrate(&world, if self.clean_only { "CLEAN RUNS" } else { "ALL RUNS" });
+          self.refresh(&world);
+        }
+        VirtualKeyCode::S => {
+          self.by_score = !self.by_score;
+          play_sound_in_state(&world, SoundType::PaddleHit);
+          narrate(&world, if self.by_score { "BY SCORE" } else { "BY MODE" });
+          self.refresh(&world);
+        }
+        _ => {}
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if self.table_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        self.table_ui_text = finder.find("high_scores_table");
+        self.help_ui_text = finder.find("high_scores_help");
+      });
+      self.refresh(&world);
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+/// Runs a hot-seat tournament on top of the modes menu. The names are entered first, then the standings are shown
+/// between the turns and the podium once everybody played.
+#[derive(Default)]
+struct TournamentState {
+  ui_root: Option<Entity>,
+  title_ui_text: Option<Entity>,
+  table_ui_text: Option<Entity>,
+  help_ui_text: Option<Entity>,
+  /// The running tournament, `None` while the names are entered.
+  tournament: Option<Tournament>,
+  names: Vec<String>,
+  name: String,
+}
+
+impl TournamentState {
+  fn refresh(&self, world: &World) {
+    let streamer_mode = world.read_resource::<Settings>().streamer_mode;
+    let (title, table, help) = match &self.tournament {
+      None => {
+        let mut lines = self
+          .names
+          .iter()
+          .enumerate()
+          .map(|(i, name)| format!("{}. {}", i + 1, name))
+          .collect::<Vec<_>>();
+        let help = if streamer_mode {
+          "ENTER TO ADD A PLAYER - S TO START - ESC TO GO BACK"
+        } else {
+          if self.names.len() < MAX_PLAYERS {
+            lines.push(format!("NAME: {}_", self.name));
+          }
+          "TYPE A NAME - ENTER TO ADD - ENTER ON AN EMPTY NAME TO START - ESC TO GO BACK"
+        };
+        ("TOURNAMENT", lines, help)
+      }
+      Some(tournament) if tournament.is_finished() => {
+        let lines = tournament
+          .standings()
+          .iter()
+          .enumerate()
+          .map(|(i, entrant)| {
+            let place = match i {
+              0 => "1ST".to_string(),
+              1 => "2ND".to_string(),
+              2 => "3RD".to_string(),
+              _ => format!("{}TH", i + 1),
+            };
+            format!("{}  {}  {}", place, entrant.name, entrant.score.unwrap_or(0))
+          })
+          .collect();
+        ("PODIUM", lines, "ENTER TO FINISH")
+      }
+      Some(tournament) => {
+        let mut lines = tournament
+          .standings()
+          .iter()
+          .enumerate()
+          .map(|(i, entrant)| match entrant.score {
+            Some(score) => format!("{}. {}  {}", i + 1, entrant.name, score),
+            None => format!("-  {}", entrant.name),
+          })
+          .collect::<Vec<_>>();
+        if let Some(current) = tournament.current() {
+          lines.push(String::new());
+          lines.push(format!("NEXT UP: {}", current.name));
+        }
+        ("STANDINGS", lines, "ENTER TO PLAY - DEL TO ABANDON - ESC TO GO BACK")
+      }
+    };
+
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.title_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = title.to_string();
+    }
+    if let Some(text) = self.table_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = table.join("\n");
+    }
+    if let Some(text) = self.help_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = help.to_string();
+    }
+  }
+
+  /// Adds the typed name, or starts the tournament on an empty name once there are enough players. Streamer mode
+  /// doesn't take typed names, the players are numbered instead and `start` stands in for the empty name.
+  fn submit_name(&mut self, world: &World, start: bool) {
+    let name = if start {
+      String::new()
+    } else if world.read_resource::<Settings>().streamer_mode {
+      format!("PLAYER {}", self.names.len() + 1)
+    } else {
+      self.name.trim().to_string()
+    };
+    if name.is_empty() && self.names.len() >= MIN_PLAYERS {
+      let tournament = Tournament::new(&self.names);
+      tournament.save();
+      self.tournament = Some(tournament);
+      play_sound_in_state(world, SoundType::Confirm);
+    } else if !name.is_empty() && self.names.len() < MAX_PLAYERS && !self.names.contains(&name) {
+      self.names.push(name);
+      self.name.clear();
+      play_sound_in_state(world, SoundType::PaddleHit);
+    } else {
+      play_sound_in_state(world, SoundType::WallHit);
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for TournamentState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/tournament.ron", ())));
+    // An interrupted tournament continues with the player whose turn it was.
+    self.tournament = Tournament::load();
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove tournament menu!");
+    }
+    self.title_ui_text = None;
+    self.table_ui_text = None;
+    self.help_ui_text = None;
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+      if let Event::WindowEvent {
+        event: WindowEvent::ReceivedCharacter(c),
+        ..
+      } = event
+      {
+        let typed = c.is_ascii_alphanumeric() || (*c == ' ' && !self.name.is_empty());
+        let streamer_mode = world.read_resource::<Settings>().streamer_mode;
+        if self.tournament.is_none() && typed && !streamer_mode && self.name.len() < MAX_NAME_LENGTH {
+          self.name.push(c.to_ascii_uppercase());
+          self.refresh(&world);
+        }
+      }
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      let finished = self.tournament.as_ref().map(|tournament| tournament.is_finished());
+      match (*key_code, finished) {
+        (VirtualKeyCode::Escape, _) => return Trans::Pop,
+        (VirtualKeyCode::Back, None) => {
+          self.name.pop();
+          self.refresh(&world);
+        }
+        (VirtualKeyCode::Return, None) => {
+          self.submit_name(&world, false);
+          self.refresh(&world);
+        }
+        (VirtualKeyCode::S, None) if world.read_resource::<Settings>().streamer_mode => {
+          self.submit_name(&world, true);
+          self.refresh(&world);
+        }
+        (VirtualKeyCode::Return, Some(false)) => {
+          play_sound_in_state(&world, SoundType::Confirm);
+          let level = self.tournament.as_ref().map(Tournament::level).unwrap_or_default();
+          return Trans::Sequence(vec![
+            Trans::Pop,
+            Trans::Pop,
+            Trans::Switch(Box::new(PlayState::tournament(level))),
+          ]);
+        }
+        (VirtualKeyCode::Delete, Some(false)) => {
+          Tournament::clear();
+          self.tournament = None;
+          self.names.clear();
+          play_sound_in_state(&world, SoundType::WallHit);
+          self.refresh(&world);
+        }
+        (VirtualKeyCode::Return, Some(true)) => {
+          Tournament::clear();
+          return Trans::Pop;
+        }
+        _ => {}
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if self.table_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        self.title_ui_text = finder.find("tournament_title");
+        self.table_ui_text = finder.find("tournament_table");
+        self.help_ui_text = finder.find("tournament_help");
+      });
+      self.refresh(&world);
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+/// The lobby in front of a multiplayer mode, every player claims a seat by pressing a key of their keyboard half or
+/// a button of their gamepad.
+#[derive(Default)]
+struct JoinState {
+  ui_root: Option<Entity>,
+  table_ui_text: Option<Entity>,
+  help_ui_text: Option<Entity>,
+  mode: PlayMode,
+  mutators: Mutators,
+  assignment: InputAssignment,
+}
+
+impl JoinState {
+  fn new(mode: PlayMode, mutators: Mutators) -> Self {
+    JoinState {
+      mode,
+      mutators,
+      ..Default::default()
+    }
+  }
+
+  fn is_full(&self) -> bool {
+    self.assignment.devices.len() >= self.mode.players()
+  }
+
+  fn refresh(&self, world: &World) {
+    let table = (0..self.mode.players())
+      .map(|seat| match self.assignment.device(seat) {
+        Some(device) => format!("PLAYER {}  {}", seat + 1, device.label()),
+        None => format!("PLAYER {}  PRESS A BUTTON TO JOIN", seat + 1),
+      })
+      .collect::<Vec<_>>();
+    let help = if self.is_full() {
+      "ENTER TO START - BACKSPACE TO LEAVE - ESC TO GO BACK"
+    } else {
+      "A/D/W OR ARROWS OR A GAMEPAD BUTTON TO JOIN - ESC TO GO BACK"
+    };
+
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.table_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = table.join("\n");
+    }
+    if let Some(text) = self.help_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = help.to_string();
+    }
+  }
+
+  /// Gives the next free seat to `device`, unless it already has one.
+  fn claim(&mut self, world: &World, device: InputDevice) {
+    if self.is_full() || self.assignment.devices.contains(&device) {
+      return;
+    }
+    self.assignment.devices.push(device);
+    play_sound_in_state(world, SoundType::PaddleHit);
+    self.refresh(world);
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for JoinState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/join.ron", ())));
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove join lobby!");
+    }
+    self.table_ui_text = None;
+    self.help_ui_text = None;
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    match &event {
+      StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) => match key_code {
+        VirtualKeyCode::Escape => return Trans::Pop,
+        VirtualKeyCode::Back => {
+          if self.assignment.devices.pop().is_some() {
+            play_sound_in_state(&world, SoundType::WallHit);
+            self.refresh(&world);
+          }
+        }
+        VirtualKeyCode::Return if self.is_full() => {
+          play_sound_in_state(&world, SoundType::Confirm);
+          let play = PlayState {
+            input_assignment: self.assignment.clone(),
+            ..PlayState::mode(self.mode, self.mutators)
+          };
+          return Trans::Sequence(vec![Trans::Pop, Trans::Pop, Trans::Switch(Box::new(play))]);
+        }
+        &key => {
+          if let Some(device) = InputDevice::from_key(key) {
+            self.claim(&world, device);
+          }
+        }
+      },
+      StateEvent::Input(InputEvent::ControllerButtonPressed { which, .. }) => {
+        self.claim(&world, InputDevice::Gamepad(*which));
+      }
+      _ => {}
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if self.table_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        self.table_ui_text = finder.find("join_table");
+        self.help_ui_text = finder.find("join_help");
+      });
+      self.refresh(&world);
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+/// Measures the input latency of the player's setup. The player taps along with a run of beeps and flashes, the
+/// result is only saved once it is confirmed.
+#[derive(Default)]
+struct CalibrationState {
+  ui_root: Option<Entity>,
+  flash_ui_text: Option<Entity>,
+  status_ui_text: Option<Entity>,
+  help_ui_text: Option<Entity>,
+  calibration: Calibration,
+}
+
+impl CalibrationState {
+  fn refresh(&self, world: &World) {
+    let saved = world.read_resource::<Settings>().input_latency;
+    let (status, help) = if !self.calibration.is_finished() {
+      (
+        format!("TAP SPACE WITH EVERY BEEP - {} TAPS", self.calibration.taps()),
+        "ESC TO GO BACK",
+      )
+    } else {
+      match self.calibration.latency() {
+        Some(latency) => (
+          format!("MEASURED {:.0} MS - SAVED {:.0} MS", latency * 1000., saved * 1000.),
+          "ENTER TO SAVE - R TO RETRY - DEL TO RESET - ESC TO GO BACK",
+        ),
+        None => (
+          "TOO FEW TAPS TO TELL".to_string(),
+          "R TO RETRY - DEL TO RESET - ESC TO GO BACK",
+        ),
+      }
+    };
+
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.status_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = status;
+    }
+    if let Some(text) = self.help_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = help.to_string();
+    }
+    if let Some(text) = self.flash_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.color[3] = self.calibration.flash();
+    }
+  }
+
+  fn save(world: &World, latency: f32) {
+    let mut settings = world.write_resource::<Settings>();
+    settings.input_latency = latency.max(0.).min(MAX_LATENCY);
+    settings.save();
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for CalibrationState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/calibration.ron", ())));
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove calibration screen!");
+    }
+    self.flash_ui_text = None;
+    self.status_ui_text = None;
+    self.help_ui_text = None;
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      let finished = self.calibration.is_finished();
+      match (*key_code, finished) {
+        (VirtualKeyCode::Escape, _) => return Trans::Pop,
+        (VirtualKeyCode::Space, false) => self.calibration.tap(),
+        (VirtualKeyCode::Return, true) => {
+          if let Some(latency) = self.calibration.latency() {
+            CalibrationState::save(&world, latency);
+            play_sound_in_state(&world, SoundType::Confirm);
+            return Trans::Pop;
+          }
+        }
+        (VirtualKeyCode::R, true) => self.calibration = Calibration::default(),
+        (VirtualKeyCode::Delete, true) => {
+          CalibrationState::save(&world, 0.);
+          play_sound_in_state(&world, SoundType::WallHit);
+        }
+        _ => {}
+      }
+      self.refresh(&world);
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if self.status_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        self.flash_ui_text = finder.find("calibration_flash");
+        self.status_ui_text = finder.find("calibration_status");
+        self.help_ui_text = finder.find("calibration_help");
+      });
+    }
+    // Real time, the beeps must keep their rhythm whatever the time scale was left at.
+    let delta_seconds = world.fetch::<Time>().delta_real_seconds();
+    if self.calibration.tick(delta_seconds) {
+      play_sound_in_state(&world, SoundType::PaddleHit);
+    }
+    self.refresh(&world);
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+/// Browses the highlights of the gallery, newest first. Each one is drawn again from its rectangles with the brick
+/// sprite.
+#[derive(Default)]
+struct GalleryState {
+  ui_root: Option<Entity>,
+  title_ui_text: Option<Entity>,
+  highlights: Vec<Highlight>,
+  selected: usize,
+  /// Sprites of the shown highlight, `None` until it was drawn.
+  shot_entities: Option<Vec<Entity>>,
+}
+
+impl GalleryState {
+  fn clear(&mut self, world: &mut World) {
+    for e in self.shot_entities.take().unwrap_or_default() {
+      world.delete_entity(e).expect("Failed to remove highlight!");
+    }
+  }
+
+  /// Draws the selected highlight and its title once the brick sprite is loaded.
+  fn draw(&mut self, world: &mut World) {
+    if self.shot_entities.is_some() {
+      return;
+    }
+    let (sprite_sheet_handle, sprite_pos) = match get_brick_sprite(world) {
+      Some(sprite) => sprite,
+      None => return,
+    };
+    if world
+      .read_resource::<AssetStorage<SpriteSheet>>()
+      .get(&sprite_sheet_handle)
+      .is_none()
+    {
+      return;
+    }
+    let (width, height) = get_texture_dimensions(world, &sprite_sheet_handle, sprite_pos);
+
+    let rects = self
+      .highlights
+      .get(self.selected)
+      .map_or(vec![], |highlight| highlight.rects.clone());
+    let entities = rects
+      .into_iter()
+      .map(|rect| {
+        let mut transform = Layer::Playfield.transform(rect.x, rect.y);
+        transform.set_scale(Vector3::new(rect.width / width, rect.height / height, 1.));
+        let [r, g, b, a] = rect.color;
+        world
+          .create_entity()
+          .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
+          .with(transform)
+          .with(Tint(Srgba::new(r, g, b, a)))
+          .with(Transparent)
+          .build()
+      })
+      .collect();
+    self.shot_entities = Some(entities);
+
+    let title = match self.highlights.get(self.selected) {
+      Some(highlight) => format!(
+        "{}/{}  {} - {} - {}",
+        self.selected + 1,
+        self.highlights.len(),
+        highlight.title,
+        highlight.level,
+        highlight.score
+      ),
+      None => "NO HIGHLIGHTS YET".to_string(),
+    };
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.title_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = title;
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for GalleryState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/gallery.ron", ())));
+    load_assets(world, &AssetType::GAMEPLAY);
+    self.highlights = Highlight::load_all();
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.clear(world);
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove gallery!");
+    }
+    self.title_ui_text = None;
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      match key_code {
+        VirtualKeyCode::Escape => return Trans::Pop,
+        VirtualKeyCode::Left | VirtualKeyCode::Right => {
+          self.selected = match key_code {
+            VirtualKeyCode::Left => self.selected.saturating_sub(1),
+            _ => (self.selected + 1).min(self.highlights.len().saturating_sub(1)),
+          };
+          play_sound_in_state(&world, SoundType::PaddleHit);
+          self.clear(world);
+        }
+        _ => {}
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if self.title_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        self.title_ui_text = finder.find("gallery_title");
+      });
+    }
+    if self.title_ui_text.is_some() {
+      self.draw(world);
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+/// The playfield of a replay at one frame, restored from the keyframe before it. Frames after the same keyframe only
+/// move what moved since, a frame after another keyframe restores that one instead.
+#[derive(Default)]
+struct ReplayScene {
+  /// Index of the restored keyframe.
+  keyframe: Option<usize>,
+  /// The restored entities, in the order of the keyframe.
+  entities: Vec<Entity>,
+  /// Angle the restored bricks are swung to.
+  field_angle: f32,
+}
+
+impl ReplayScene {
+  fn show(&mut self, world: &mut World, plugin: &SnapshotPlugin, replay: &Replay, frame: usize) -> Result<(), String> {
+    let (keyframe, snapshot) = replay
+      .keyframe_at(frame)
+      .ok_or_else(|| "there is no keyframe before it".to_string())?;
+    if self.keyframe != Some(keyframe) {
+      self.clear(world);
+      self.entities = plugin.restore_entities(world, snapshot)?;
+      self.keyframe = Some(keyframe);
+      self.field_angle = replay.frames[keyframe].field_angle;
+    }
+
+    let frame = &replay.frames[frame];
+    let center = snapshot
+      .resources
+      .get("field_rotation")
+      .and_then(|contents| ron::de::from_str::<FieldRotation>(contents).ok())
+      .and_then(|rotation| rotation.center);
+    let mut transforms = world.write_storage::<Transform>();
+    if let Some(center) = center {
+      let bricks = world.read_storage::<Brick>();
+      let by = frame.field_angle - self.field_angle;
+      swing_bricks(&bricks, &mut transforms, center, by, frame.field_angle);
+      self.field_angle = frame.field_angle;
+    }
+    for &(index, (x, y, angle)) in &frame.moved {
+      if let Some(transform) = self.entities.get(index).and_then(|&entity| transforms.get_mut(entity)) {
+        transform.set_translation_x(x);
+        transform.set_translation_y(y);
+        transform.set_rotation_2d(angle);
+      }
+    }
+    Ok(())
+  }
+
+  fn clear(&mut self, world: &mut World) {
+    world
+      .delete_entities(&self.entities)
+      .expect("Failed to remove replay frame!");
+    self.entities.clear();
+    self.keyframe = None;
+  }
+}
+
+/// Plays back the saved replays, newest first. Playback can be paused, sped up or slowed down and scrubbed, seeking
+/// rebuilds the playfield from the keyframe before the new position.
+struct ReplayState {
+  ui_root: Option<Entity>,
+  title_ui_text: Option<Entity>,
+  status_ui_text: Option<Entity>,
+  paths: Vec<PathBuf>,
+  selected: usize,
+  replay: Option<Replay>,
+  /// Seconds into the replay.
+  clock: f32,
+  speed: usize,
+  paused: bool,
+  menu_repeat: MenuRepeat,
+  scene: ReplayScene,
+  shown_frame: Option<usize>,
+}
+
+impl Default for ReplayState {
+  fn default() -> Self {
+    ReplayState {
+      ui_root: None,
+      title_ui_text: None,
+      status_ui_text: None,
+      paths: vec![],
+      selected: 0,
+      replay: None,
+      clock: 0.,
+      speed: replay::NORMAL_SPEED,
+      paused: false,
+      menu_repeat: MenuRepeat::default(),
+      scene: ReplayScene::default(),
+      shown_frame: None,
+    }
+  }
+}
+
+impl ReplayState {
+  fn open(&mut self, world: &World) {
+    self.replay = self.paths.get(self.selected).and_then(|path| Replay::load(path));
+    self.clock = 0.;
+    self.paused = false;
+    self.shown_frame = None;
+    let title = match (&self.replay, self.paths.is_empty()) {
+      (_, true) => "NO REPLAYS YET".to_string(),
+      (Some(replay), _) => format!(
+        "{}/{}  {} - {}",
+        self.selected + 1,
+        self.paths.len(),
+        replay.level.to_uppercase(),
+        replay.score
+      ),
+      (None, _) => format!("{}/{}  COULDN'T LOAD THE REPLAY", self.selected + 1, self.paths.len()),
+    };
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.title_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = title;
+    }
+  }
+
+  fn clear(&mut self, world: &mut World) {
+    self.scene.clear(world);
+    self.shown_frame = None;
+  }
+
+  fn seek(&mut self, world: &World, seconds: f32) {
+    if let Some(replay) = &self.replay {
+      self.clock = (self.clock + seconds).max(0.).min(replay.duration());
+      play_sound_in_state(world, SoundType::PaddleHit);
+    }
+  }
+
+  /// Shows the frame at the clock once the sprite sheets are loaded, the entities of the last frame are moved unless
+  /// the frame is past another keyframe.
+  fn draw(&mut self, world: &mut World) {
+    let replay = match &self.replay {
+      Some(replay) => replay,
+      None => return,
+    };
+    let frame = replay.frame_at(self.clock);
+    if self.shown_frame == Some(frame) {
+      return;
+    }
+    let loaded = get_brick_sprite(world).map_or(false, |(sprite_sheet_handle, _)| {
+      world
+        .read_resource::<AssetStorage<SpriteSheet>>()
+        .get(&sprite_sheet_handle)
+        .is_some()
+    });
+    if !loaded {
+      return;
+    }
+
+    self.shown_frame = Some(frame);
+    let keyframe = self.scene.keyframe;
+    if let Err(e) = self.scene.show(world, &gameplay_snapshot(), replay, frame) {
+      warn!("Couldn't show frame {} of the replay: {}", frame, e);
+    }
+    if self.scene.keyframe != keyframe {
+      let mut transparent = world.write_storage::<Transparent>();
+      for &entity in &self.scene.entities {
+        transparent
+          .insert(entity, Transparent)
+          .expect("Couldn't make replay frame transparent!");
+      }
+    }
+  }
+
+  fn update_status(&self, world: &World) {
+    let status = match &self.replay {
+      Some(replay) => {
+        let time = |seconds: f32| format!("{}:{:02}", seconds as u32 / 60, seconds as u32 % 60);
+        format!(
+          "{} / {}  {}X{}",
+          time(self.clock),
+          time(replay.duration()),
+          replay::SPEEDS[self.speed],
+          if self.paused { "  PAUSED" } else { "" }
+        )
+      }
+      None => String::new(),
+    };
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.status_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = status;
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for ReplayState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/replay.ron", ())));
+    load_assets(world, &AssetType::GAMEPLAY);
+    self.paths = Replay::paths();
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.clear(world);
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove replay viewer!");
+    }
+    self.title_ui_text = None;
+    self.status_ui_text = None;
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let world = data.world;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(event) = &event {
+      if let InputEvent::KeyReleased { key_code, .. } = event {
+        self.menu_repeat.release(*key_code);
+      }
+      if let InputEvent::KeyPressed { key_code, .. } = event {
+        match key_code {
+          VirtualKeyCode::Escape => return Trans::Pop,
+          VirtualKeyCode::Space => {
+            // Playing a replay that ran to its end starts it over.
+            let at_end = self
+              .replay
+              .as_ref()
+              .map_or(false, |replay| self.clock >= replay.duration());
+            if self.paused && at_end {
+              self.clock = 0.;
+            }
+            self.paused = !self.paused;
+          }
+          VirtualKeyCode::Up => self.speed = (self.speed + 1).min(replay::SPEEDS.len() - 1),
+          VirtualKeyCode::Down => self.speed = self.speed.saturating_sub(1),
+          VirtualKeyCode::Left | VirtualKeyCode::Right => {
+            self.menu_repeat.press(*key_code);
+            let seconds = if *key_code == VirtualKeyCode::Left { -1. } else { 1. };
+            self.seek(&world, seconds * REPLAY_SEEK_SECONDS);
+          }
+          VirtualKeyCode::Comma | VirtualKeyCode::Period => {
+            self.paused = true;
+            let frames = if *key_code == VirtualKeyCode::Comma { -1. } else { 1. };
+            self.seek(&world, frames / replay::FRAMES_PER_SECOND);
+          }
+          VirtualKeyCode::Home => self.clock = 0.,
+          VirtualKeyCode::Tab if !self.paths.is_empty() => {
+            self.selected = (self.selected + 1) % self.paths.len();
+            play_sound_in_state(&world, SoundType::PaddleHit);
+            self.clear(world);
+            self.open(&world);
+          }
+          _ => {}
+        }
+        self.update_status(&world);
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if self.title_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        self.title_ui_text = finder.find("replay_title");
+        self.status_ui_text = finder.find("replay_status");
+      });
+      if self.title_ui_text.is_some() {
+        self.open(&world);
+      }
+    }
+
+    let delta_seconds = world.fetch::<Time>().delta_real_seconds();
+    if let Some(key_code) = self.menu_repeat.tick(delta_seconds) {
+      let seconds = if key_code == VirtualKeyCode::Left { -1. } else { 1. };
+      self.seek(&world, seconds * REPLAY_SEEK_SECONDS);
+    }
+    if let Some(duration) = self.replay.as_ref().map(Replay::duration) {
+      if !self.paused {
+        self.clock += delta_seconds * replay::SPEEDS[self.speed];
+        if self.clock >= duration {
+          self.clock = duration;
+          self.paused = true;
+        }
+      }
+      self.update_status(&world);
+    }
+    self.draw(world);
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+/// Shows the stats and bonuses of a cleared level on top of the play state until it is dismissed.
+#[derive(Default)]
+struct TallyState {
+  ui_root: Option<Entity>,
+  stats: LevelStats,
+  shown: bool,
+}
+
+impl TallyState {
+  fn new(stats: LevelStats) -> Self {
+    TallyState {
+      stats,
+      ..Default::default()
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for TallyState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/tally.ron", ())));
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove tally!");
+    }
+  }
+
+  fn handle_event(
+    &mut self,
+    _data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      if let VirtualKeyCode::Space | VirtualKeyCode::Return = key_code {
+        return Trans::Pop;
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if !self.shown {
+      world.exec(|(finder, mut ui_text): (UiFinder, WriteStorage<UiText>)| {
+        let stats = finder.find("tally_stats");
+        let bonuses = finder.find("tally_bonuses");
+        if let (Some(stats), Some(bonuses)) = (stats, bonuses) {
+          if let Some(text) = ui_text.get_mut(stats) {
+            text.text = format!(
+              "SERVES {}\nPADDLE HITS {}\nBALLS LOST {}\nCLOSE CALLS {}\nTILTS {}",
+              self.stats.serves,
+              self.stats.paddle_hits,
+              self.stats.balls_lost,
+              self.stats.close_calls,
+              self.stats.tilts
+            );
+          }
+          if let Some(text) = ui_text.get_mut(bonuses) {
+            let bonuses = LevelBonus::earned(&self.stats);
+            text.text = if bonuses.is_empty() {
+              "NO BONUS".to_string()
+            } else {
+              bonuses.iter().map(|bonus| bonus.label()).collect::<Vec<_>>().join("  ")
+            };
+          }
+          self.shown = true;
+        }
+      });
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+/// Congratulates on a cleared level with its score, enter plays the next custom level if there is one and escape goes
+/// back to the start menu.
+#[derive(Default)]
+struct VictoryState {
+  ui_root: Option<Entity>,
+  lines: Vec<String>,
+  next: Option<Level>,
+  shown: bool,
+}
+
+impl VictoryState {
+  fn new(lines: Vec<String>, next: Option<Level>) -> Self {
+    VictoryState {
+      lines,
+      next,
+      ..Default::default()
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for VictoryState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/victory.ron", ())));
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove victory screen!");
+    }
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      match key_code {
+        VirtualKeyCode::Return => {
+          if let Some(next) = self.next.take() {
+            play_sound_in_state(&world, SoundType::Confirm);
+            return Trans::Switch(Box::new(PlayState::new(next)));
+          }
+        }
+        VirtualKeyCode::Escape => {
+          play_sound_in_state(&world, SoundType::Confirm);
+          return Trans::Switch(Box::new(StartState::default()));
+        }
+        _ => {}
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if !self.shown {
+      world.exec(|(finder, mut ui_text): (UiFinder, WriteStorage<UiText>)| {
+        let text = finder.find("victory_score");
+        let hint = finder.find("victory_hint");
+        if let (Some(text), Some(hint)) = (text, hint) {
+          if let Some(text) = ui_text.get_mut(text) {
+            text.text = self.lines.join("\n");
+          }
+          if let Some(hint) = ui_text.get_mut(hint) {
+            hint.text = match &self.next {
+              Some(next) => format!("ENTER TO PLAY {}  ESC FOR THE MENU", next.name.to_uppercase()),
+              None => "ESC FOR THE MENU".to_string(),
+            };
+          }
+          self.shown = true;
+        }
+      });
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+/// Shows the final score of a run that is over, enter plays the same run again and escape goes back to the start
+/// menu.
+#[derive(Default)]
+struct GameOverState {
+  ui_root: Option<Entity>,
+  title: String,
+  lines: Vec<String>,
+  retry: Option<PlayState>,
+  shown: bool,
+}
+
+impl GameOverState {
+  fn new(title: &str, lines: Vec<String>, retry: PlayState) -> Self {
+    GameOverState {
+      title: title.to_string(),
+      lines,
+      retry: Some(retry),
+      ..Default::default()
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for GameOverState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/game_over.ron", ())));
+    play_sound_in_state(&world, SoundType::GameOver);
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove game over screen!");
+    }
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      match key_code {
+        VirtualKeyCode::Return => {
+          if let Some(retry) = self.retry.take() {
+            play_sound_in_state(&world, SoundType::Confirm);
+            return Trans::Switch(Box::new(retry));
+          }
+        }
+        VirtualKeyCode::Escape => {
+          play_sound_in_state(&world, SoundType::Confirm);
+          return Trans::Switch(Box::new(StartState::default()));
+        }
+        _ => {}
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if !self.shown {
+      world.exec(|(finder, mut ui_text): (UiFinder, WriteStorage<UiText>)| {
+        let title = finder.find("game_over_title");
+        let text = finder.find("game_over_score");
+        if let (Some(title), Some(text)) = (title, text) {
+          if let Some(title) = ui_text.get_mut(title) {
+            title.text = self.title.clone();
+          }
+          if let Some(text) = ui_text.get_mut(text) {
+            text.text = self.lines.join("\n");
+          }
+          self.shown = true;
+        }
+      });
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+/// Lists errors or results on top of the previous state until it is dismissed with enter or escape.
+#[derive(Default)]
+struct ErrorDialogState {
   ui_root: Option<Entity>,
-  progress_counter: Option<ProgressCounter>,
-  text_selected: TextSelectedType,
+  title: String,
+  lines: Vec<String>,
+  shown: bool,
 }
 
-impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for StartState {
+impl ErrorDialogState {
+  fn new(title: &str, lines: Vec<String>) -> Self {
+    ErrorDialogState {
+      title: title.to_string(),
+      lines,
+      ..Default::default()
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for ErrorDialogState {
+  fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/dialog.ron", ())));
+    play_sound_in_state(&world, SoundType::WallHit);
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    if let Some(e) = self.ui_root.take() {
+      world.delete_entity(e).expect("Failed to remove error dialog!");
+    }
+  }
+
+  fn handle_event(
+    &mut self,
+    _data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      if let VirtualKeyCode::Escape | VirtualKeyCode::Return = key_code {
+        return Trans::Pop;
+      }
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if !self.shown {
+      world.exec(|(finder, mut ui_text): (UiFinder, WriteStorage<UiText>)| {
+        let title = finder.find("dialog_title");
+        let text = finder.find("dialog_text");
+        if let (Some(title), Some(text)) = (title, text) {
+          if let Some(title) = ui_text.get_mut(title) {
+            title.text = self.title.clone();
+          }
+          if let Some(text) = ui_text.get_mut(text) {
+            text.text = self.lines.join("\n").to_uppercase();
+          }
+          self.shown = true;
+        }
+      });
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+struct EditorState {
+  ui_root: Option<Entity>,
+  status_ui_text: Option<Entity>,
+  brick_entities: Vec<Entity>,
+  cursor_entity: Option<Entity>,
+  cells: Cells,
+  history: History,
+  cursor: Position,
+  anchor: Option<Position>,
+  selection: Option<Selection>,
+  tool: Tool,
+  brick: char,
+  mirror: bool,
+  message: String,
+  inspector_field: InspectorField,
+  inspector_ui_texts: Vec<(InspectorField, Entity)>,
+  brick_size: (f32, f32),
+}
+
+impl Default for EditorState {
+  fn default() -> Self {
+    EditorState {
+      ui_root: None,
+      status_ui_text: None,
+      brick_entities: vec![],
+      cursor_entity: None,
+      cells: editor::empty_cells(),
+      history: History::default(),
+      cursor: (EDITOR_COLUMNS / 2, 0),
+      anchor: None,
+      selection: None,
+      tool: Tool::default(),
+      brick: editor::next_brick(editor::EMPTY),
+      mirror: false,
+      message: String::new(),
+      inspector_field: InspectorField::default(),
+      inspector_ui_texts: vec![],
+      brick_size: (0., 0.),
+    }
+  }
+}
+
+impl EditorState {
+  /// Positions the next command works on, `None` while a two corner tool still waits for its second corner.
+  fn target(&mut self) -> Option<Vec<Position>> {
+    if let Some(selection) = self.selection {
+      return Some(selection.positions().collect());
+    }
+    let from = match (self.tool.needs_anchor(), self.anchor.take()) {
+      (true, None) => {
+        self.anchor = Some(self.cursor);
+        return None;
+      }
+      (_, anchor) => anchor.unwrap_or(self.cursor),
+    };
+    if self.tool == Tool::Select {
+      self.selection = Some(Selection::new(from, self.cursor));
+      return None;
+    }
+    Some(self.tool.positions(&self.cells, from, self.cursor, self.mirror))
+  }
+
+  fn execute(&mut self, command: fn(Vec<Position>, char) -> Command) {
+    if let Some(positions) = self.target() {
+      self.history.execute(command(positions, self.brick), &mut self.cells);
+    }
+  }
+
+  /// Steps the active inspector field of the brick under the cursor, empty cells have nothing to inspect.
+  fn inspect(&mut self, up: bool) {
+    let (x, y) = self.cursor;
+    let cell = &self.cells[y][x];
+    if cell.is_empty() {
+      return;
+    }
+    let properties = self.inspector_field.adjust(&cell.properties, up);
+    self.history.execute(
+      Command::SetProperties {
+        position: self.cursor,
+        properties,
+      },
+      &mut self.cells,
+    );
+  }
+
+  /// The grid cell under a point in virtual coordinates.
+  fn cell_at(&self, x: f32, y: f32) -> Option<Position> {
+    let (width, height) = self.brick_size;
+    (0..EDITOR_ROWS)
+      .flat_map(|row| (0..EDITOR_COLUMNS).map(move |column| (column, row)))
+      .find(|&(column, row)| {
+        let (center_x, center_y) = brick_translation(
+          VIRTUAL_WIDTH,
+          VIRTUAL_HEIGHT,
+          EDITOR_COLUMNS,
+          column,
+          row,
+          width,
+          height,
+        );
+        point_in_rect(
+          x,
+          y,
+          center_x - width / 2. - 1.,
+          center_y - height / 2. - 2.,
+          center_x + width / 2. + 1.,
+          center_y + height / 2. + 2.,
+        )
+      })
+  }
+
+  fn level(&self) -> Level {
+    let author = std::env::var("USER").unwrap_or_else(|_| "Anonymous".to_string());
+    editor::level_from_cells(&self.cells, "Custom Level", &author)
+  }
+
+  fn move_selection(&mut self, offset: (isize, isize)) {
+    if let Some(selection) = self.selection {
+      if let Some(moved) = selection.moved(offset) {
+        self
+          .history
+          .execute(Command::MoveSelection { selection, offset }, &mut self.cells);
+        self.selection = Some(moved);
+      }
+    }
+  }
+
+  fn save(&mut self) {
+    let level = self.level();
+    let result = application_root_dir()
+      .map_err(Box::<dyn std::error::Error>::from)
+      .and_then(|app_root| save_level(&app_root.join(CUSTOM_LEVELS_DIR), &level));
+    self.message = match result {
+      Ok(path) => format!(
+        "SAVED {}",
+        path
+          .file_name()
+          .map_or_else(String::new, |name| name.to_string_lossy().to_uppercase())
+      ),
+      Err(e) => e.to_string().to_uppercase(),
+    };
+  }
+
+  /// Recreates the brick sprites from the cells and moves the cursor, cheap enough for a 13x10 grid.
+  fn refresh(&mut self, world: &mut World) {
+    let (sprite_sheet_handle, sprite_pos) = match get_brick_sprite(world) {
+      Some(sprite) => sprite,
+      None => return,
+    };
+    let (width, height) = get_texture_dimensions(world, &sprite_sheet_handle, sprite_pos);
+    self.brick_size = (width, height);
+
+    for e in self.brick_entities.drain(..) {
+      world.delete_entity(e).expect("Failed to remove editor brick!");
+    }
+    for (y, row) in self.cells.iter().enumerate() {
+      for (x, cell) in row.iter().enumerate().filter(|(_, cell)| !cell.is_empty()) {
+        let (brick_x, brick_y) = brick_translation(VIRTUAL_WIDTH, VIRTUAL_HEIGHT, EDITOR_COLUMNS, x, y, width, height);
+        let selected = self.selection.map_or(false, |selection| selection.contains((x, y)));
+        let [r, g, b, a] = match (selected, cell.brick) {
+          (true, _) => TEXT_SELECTED_COLOR,
+          (false, SPLIT_BRICK) => {
+            let [r, g, b] = SPLIT_BRICK_COLOR;
+            [r, g, b, 1.]
+          }
+          (false, ARMORED_BRICK) => {
+            let [r, g, b] = ARMORED_BRICK_COLOR;
+            [r, g, b, 1.]
+          }
+          (false, BUMPER) => {
+            let [r, g, b] = BUMPER_COLOR;
+            [r, g, b, 1.]
+          }
+          (false, GATE_UP) | (false, GATE_DOWN) => {
+            let [r, g, b] = GATE_COLOR;
+            [r, g, b, 1.]
+          }
+          (false, DOOR) => {
+            let [r, g, b] = DOOR_COLOR;
+            [r, g, b, 1.]
+          }
+          (false, SWITCH_BRICK) => {
+            let [r, g, b] = SWITCH_BRICK_COLOR;
+            [r, g, b, 1.]
+          }
+          (false, METAL_BRICK) => {
+            let [r, g, b] = METAL_BRICK_COLOR;
+            [r, g, b, 1.]
+          }
+          (false, _) => TEXT_COLOR,
+        };
+        let e = world
+          .create_entity()
+          .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
+          .with(Layer::Playfield.transform(brick_x, brick_y))
+          .with(Tint(Srgba::new(r, g, b, a)))
+          .build();
+        self.brick_entities.push(e);
+      }
+    }
+
+    let (cursor_x, cursor_y) = brick_translation(
+      VIRTUAL_WIDTH,
+      VIRTUAL_HEIGHT,
+      EDITOR_COLUMNS,
+      self.cursor.0,
+      self.cursor.1,
+      width,
+      height,
+    );
+    match self.cursor_entity {
+      Some(e) => {
+        if let Some(transform) = world.write_storage::<Transform>().get_mut(e) {
+          transform.set_translation_xyz(cursor_x, cursor_y, Layer::Overlay.z());
+        }
+      }
+      None => {
+        self.cursor_entity = Some(
+          world
+            .create_entity()
+            .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
+            .with(Layer::Overlay.transform(cursor_x, cursor_y))
+            .with(Tint(Srgba::new(0.4, 1., 1., 0.6)))
+            .with(Transparent)
+            .build(),
+        );
+      }
+    }
+
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.status_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      let anchor = if self.anchor.is_some() {
+        " - PICK SECOND CORNER"
+      } else if self.selection.is_some() {
+        " - SHIFT+ARROWS MOVE"
+      } else {
+        ""
+      };
+      let mirror = if self.mirror { "ON" } else { "OFF" };
+      text.text = format!(
+        "{}{}  BRICK {}  MIRROR {}  {}",
+        self.tool.label(),
+        anchor,
+        self.brick,
+        mirror,
+        self.message
+      );
+    }
+    let cell = &self.cells[self.cursor.1][self.cursor.0];
+    for (field, entity) in &self.inspector_ui_texts {
+      if let Some(text) = ui_text.get_mut(*entity) {
+        text.text = if cell.is_empty() {
+          String::new()
+        } else {
+          field.label(&cell.properties)
+        };
+        text.color = if *field == self.inspector_field {
+          TEXT_SELECTED_COLOR
+        } else {
+          TEXT_COLOR
+        };
+      }
+    }
+  }
+}
+
+impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for EditorState {
   fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/editor.ron", ())));
+    load_assets(world, &AssetType::GAMEPLAY);
+    self.refresh(world);
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    let entities = self
+      .brick_entities
+      .drain(..)
+      .chain(self.ui_root.take())
+      .chain(self.cursor_entity.take());
+    for e in entities {
+      world.delete_entity(e).expect("Failed to remove editor!");
+    }
+    self.status_ui_text = None;
+    self.inspector_ui_texts.clear();
+  }
+
+  fn on_pause(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    for e in self.brick_entities.drain(..).chain(self.cursor_entity.take()) {
+      world.delete_entity(e).expect("Failed to remove editor bricks!");
+    }
+    set_hidden(world, self.ui_root, true);
+  }
+
+  fn on_resume(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let StateData { world, .. } = data;
+
+    set_hidden(world, self.ui_root, false);
+    self.refresh(world);
+  }
+
+  fn handle_event(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    event: StateEvent<StringBindings>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
     let world = data.world;
 
-    self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/start.ron", ())));
-    init_camera(world);
-    init_audio(
+    if let StateEvent::Window(event) = &event {
+      if is_close_requested(&event) {
+        return Trans::Quit;
+      }
+    }
+
+    match &event {
+      StateEvent::Input(InputEvent::MouseButtonPressed(MouseButton::Left)) => {
+        let position = {
+          let input = world.read_resource::<InputHandler<StringBindings>>();
+          let dimensions = world.read_resource::<ScreenDimensions>();
+          input.mouse_position().and_then(|(x, y)| {
+            self.cell_at(
+              x / dimensions.width() * VIRTUAL_WIDTH,
+              VIRTUAL_HEIGHT - y / dimensions.height() * VIRTUAL_HEIGHT,
+            )
+          })
+        };
+        if let Some(position) = position {
+          self.cursor = position;
+          self.refresh(world);
+        }
+      }
+      StateEvent::Input(InputEvent::MouseWheelMoved(direction)) => {
+        match direction {
+          ScrollDirection::ScrollUp => self.inspect(true),
+          ScrollDirection::ScrollDown => self.inspect(false),
+          _ => {}
+        }
+        self.refresh(world);
+      }
+      _ => {}
+    }
+
+    if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = &event {
+      let (control, shift) = {
+        let input = world.read_resource::<InputHandler<StringBindings>>();
+        (
+          input.key_is_down(VirtualKeyCode::LControl) || input.key_is_down(VirtualKeyCode::RControl),
+          input.key_is_down(VirtualKeyCode::LShift) || input.key_is_down(VirtualKeyCode::RShift),
+        )
+      };
+      let (x, y) = self.cursor;
+      self.message.clear();
+      match key_code {
+        VirtualKeyCode::Escape if self.anchor.is_some() || self.selection.is_some() => {
+          self.anchor = None;
+          self.selection = None;
+        }
+        VirtualKeyCode::Escape => return Trans::Pop,
+        VirtualKeyCode::Left if shift => self.move_selection((-1, 0)),
+        VirtualKeyCode::Right if shift => self.move_selection((1, 0)),
+        VirtualKeyCode::Up if shift => self.move_selection((0, -1)),
+        VirtualKeyCode::Down if shift => self.move_selection((0, 1)),
+        VirtualKeyCode::Left => self.cursor.0 = x.saturating_sub(1),
+        VirtualKeyCode::Right => self.cursor.0 = (x + 1).min(EDITOR_COLUMNS - 1),
+        VirtualKeyCode::Up => self.cursor.1 = y.saturating_sub(1),
+        VirtualKeyCode::Down => self.cursor.1 = (y + 1).min(EDITOR_ROWS - 1),
+        VirtualKeyCode::Space => self.execute(|positions, brick| Command::Place { positions, brick }),
+        VirtualKeyCode::X => self.execute(|positions, _| Command::Erase { positions }),
+        VirtualKeyCode::C => self.execute(|positions, brick| Command::ChangeType { positions, brick }),
+        VirtualKeyCode::B => self.brick = editor::next_brick(self.brick),
+        VirtualKeyCode::Tab => {
+          self.tool = self.tool.next();
+          self.anchor = None;
+          self.selection = None;
+        }
+        VirtualKeyCode::M => self.mirror = !self.mirror,
+        VirtualKeyCode::I => self.inspector_field = self.inspector_field.next(),
+        VirtualKeyCode::Equals | VirtualKeyCode::Add => self.inspect(true),
+        VirtualKeyCode::Minus | VirtualKeyCode::Subtract => self.inspect(false),
+        VirtualKeyCode::Z if control => {
+          self.history.undo(&mut self.cells);
+          self.selection = None;
+        }
+        VirtualKeyCode::Y if control => {
+          self.history.redo(&mut self.cells);
+          self.selection = None;
+        }
+        VirtualKeyCode::S if control => self.save(),
+        VirtualKeyCode::P => match self.level().validate() {
+          Ok(()) => return Trans::Push(Box::new(PlayState::playtest(self.level()))),
+          Err(e) => self.message = e.to_string().to_uppercase(),
+        },
+        _ => return Trans::None,
+      }
+      play_sound_in_state(&world, SoundType::PaddleHit);
+      self.refresh(world);
+    }
+
+    Trans::None
+  }
+
+  fn update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+
+    if self.status_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        if let Some(entity) = finder.find("editor_status") {
+          self.status_ui_text = Some(entity);
+        }
+        self.inspector_ui_texts = InspectorField::ALL
+          .iter()
+          .filter_map(|&field| {
+            let id = match field {
+              InspectorField::Hp => "inspector_hp",
+              InspectorField::Score => "inspector_score",
+              InspectorField::PowerUp => "inspector_power_up",
+              InspectorField::Path => "inspector_path",
+              InspectorField::Link => "inspector_link",
+            };
+            finder.find(id).map(|entity| (field, entity))
+          })
+          .collect();
+      });
+      if self.status_ui_text.is_some() {
+        self.refresh(world);
+      }
+    }
+    data.data.update(&world, false);
+
+    Trans::None
+  }
+}
+
+#[derive(Default)]
+struct PlayState {
+  hud_root: Option<Entity>,
+  countdown_ui_text: Option<Entity>,
+  away_ui_text: Option<Entity>,
+  /// Seconds left until the game continues after the pause menu was closed.
+  resume_countdown: Option<f32>,
+  /// A pause pressed during the resume countdown, it pauses again once the countdown is over instead of being lost.
+  pause_buffer: BufferedPress,
+  level: Level,
+  playtest: bool,
+  campaign: Option<CampaignLevel>,
+  new_game_plus: bool,
+  /// Set once the tally of a cleared campaign level was shown, the next update moves on.
+  tallied: bool,
+  mode: PlayMode,
+  /// Waves cleared so far in an endless mode.
+  wave: u32,
+  /// Plays the ambient track of calm modes.
+  ambient: Option<AudioSink>,
+  mutators: Mutators,
+  /// The devices claimed in the join lobby of a multiplayer mode.
+  input_assignment: InputAssignment,
+  recorder: ReplayRecorder,
+  /// Replaces the freshly spawned level once it started, set when a suspended level is continued.
+  restore: Option<WorldSnapshot>,
+  /// Score and bricks left last reported to the observer stream.
+  observed: Option<(u32, usize)>,
+  /// Set once the intercept marker was on or a tilt was used, the run doesn't count as clean on the leaderboard
+  /// anymore.
+  assisted: bool,
+}
+
+impl PlayState {
+  fn new(level: Level) -> Self {
+    PlayState {
+      level,
+      ..Default::default()
+    }
+  }
+
+  /// Plays a level straight from the editor, escape returns to the editor instead of quitting.
+  fn playtest(level: Level) -> Self {
+    PlayState {
+      level,
+      playtest: true,
+      ..Default::default()
+    }
+  }
+
+  /// Plays the first wave of an endless or timed mode.
+  fn mode(mode: PlayMode, mutators: Mutators) -> Self {
+    PlayState {
+      level: modes::wave(0),
+      mode,
+      mutators,
+      ..Default::default()
+    }
+  }
+
+  /// Plays the turn of the current player of the running tournament.
+  fn tournament(level: Level) -> Self {
+    PlayState {
+      level,
+      mode: PlayMode::Tournament,
+      ..Default::default()
+    }
+  }
+
+  /// Continues a level that was left through the pause menu where it was left.
+  fn suspended(suspended: Suspended) -> Self {
+    PlayState {
+      level: suspended.level,
+      campaign: suspended.campaign,
+      new_game_plus: suspended.new_game_plus,
+      restore: Some(suspended.snapshot),
+      ..Default::default()
+    }
+  }
+
+  /// Plays a campaign level, clearing it records the progress and moves on to the next level. New Game+ levels
+  /// are expected to be remixed already.
+  fn campaign(level: Level, at: CampaignLevel, new_game_plus: bool) -> Self {
+    PlayState {
+      level,
+      campaign: Some(at),
+      new_game_plus,
+      ..Default::default()
+    }
+  }
+
+  /// Lives of a fresh run, New Game+ has fewer.
+  fn starting_lives(&self) -> Option<u32> {
+    self.mode.lives().map(|lives| {
+      if self.new_game_plus {
+        lives.saturating_sub(NEW_GAME_PLUS_LIFE_PENALTY).max(1)
+      } else {
+        lives
+      }
+    })
+  }
+
+  fn campaign_cleared<'a, 'b>(
+    &self,
+    world: &World,
+    at: CampaignLevel,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let campaign = world.read_resource::<Campaign>();
+    let mut progress = world.write_resource::<Progress>();
+    if let Some(file) = campaign.file(at) {
+      progress.cleared_mut(self.new_game_plus).insert(file.clone());
+    }
+    let finished = campaign.next(at).is_none() && campaign.is_cleared(progress.cleared(self.new_game_plus));
+    if finished {
+      progress.new_game_plus = true;
+    }
+    *progress.resume_mut(self.new_game_plus) = campaign.next(at);
+    *progress.carried_mut(self.new_game_plus) = campaign.next(at).map(|next| CarriedRun {
+      at: next,
+      score: world.read_resource::<Score>().points,
+      lives: world.read_resource::<Lives>().remaining,
+    });
+    progress.save();
+    play_sound_in_state(world, SoundType::Confirm);
+    world
+      .write_resource::<AnnouncerQueue>()
+      .0
+      .push(AnnouncerEvent::LevelClear);
+    narrate(world, "LEVEL CLEAR");
+    if finished {
+      return campaign_ending_trans(&campaign);
+    }
+
+    let next = campaign
+      .next(at)
+      .filter(|next| campaign.is_unlocked(next.world, progress.cleared(self.new_game_plus)))
+      .and_then(|next| campaign.load_level(next).map(|level| (next, level)));
+    match next {
+      Some((next, Ok(level))) => Trans::Switch(campaign_level_state(&campaign, level, next, self.new_game_plus)),
+      Some((_, Err(e))) => {
+        warn!("Couldn't load the next campaign level: {}", e);
+        Trans::Switch(Box::new(CampaignState::root()))
+      }
+      None => Trans::Switch(Box::new(CampaignState::root())),
+    }
+  }
+
+  /// Records the score of a cleared level outside of the campaign and shows the victory screen. Playtests go back to
+  /// the editor with the score instead.
+  fn level_cleared<'a, 'b>(&self, world: &World) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let points = world.read_resource::<Score>().points;
+    observe(
       world,
-      vec![
-        SoundType::PaddleHit,
-        SoundType::Confirm,
-        SoundType::Pause,
-        SoundType::WallHit,
-        SoundType::BrickHit2,
-      ],
+      ObserverEvent::LevelCleared {
+        level: self.level.name.clone(),
+        score: points,
+      },
     );
-    self.progress_counter = Some(init_assets(
-      world,
-      vec![
-        AssetType::Background(0),
-        AssetType::PaddleSmall(0),
-        AssetType::PaddleMedium(1),
-        AssetType::Ball(2),
-      ],
-    ));
+    play_sound_in_state(world, SoundType::Confirm);
+    world
+      .write_resource::<AnnouncerQueue>()
+      .0
+      .push(AnnouncerEvent::LevelClear);
+    narrate(world, "LEVEL CLEAR");
+    let mut lines = vec![format!("SCORE {}", points)];
+    if self.playtest {
+      return Trans::Sequence(vec![
+        Trans::Pop,
+        Trans::Push(Box::new(ErrorDialogState::new("LEVEL CLEAR", lines))),
+      ]);
+    }
+
+    capture_highlight(world, "LEVEL CLEAR", &self.level.name);
+    if let Ok(app_root) = application_root_dir() {
+      let dir = app_root.join(CUSTOM_LEVELS_DIR);
+      let mut personal_bests = PersonalBests::load(&dir);
+      let best = personal_bests.get(&self.level);
+      if personal_bests.submit(&self.level.name, points) {
+        personal_bests.save(&dir);
+        lines.push("NEW BEST".to_string());
+      } else if let Some(best) = best {
+        lines.push(format!("BEST {}", best));
+      }
+    }
+    Trans::Switch(Box::new(VictoryState::new(lines, next_custom_level(&self.level.name))))
   }
 
-  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
-    let world = data.world;
-    if let Some(e) = self.ui_root {
-      world.delete_entity(e).expect("Failed to remove start menu!");
+  /// Refills the cleared playfield of an endless mode with the next wave.
+  fn next_wave(&mut self, world: &mut World) {
+    self.wave += 1;
+    self.level = modes::wave(self.wave);
+    // The rings of the radial mode are round already.
+    let bonus_round = !self.mode.radial() && self.wave % BONUS_ROUND_INTERVAL == BONUS_ROUND_INTERVAL - 1;
+    if bonus_round {
+      self.level.movers = modes::bonus_round_movers();
     }
-    self.ui_root = None;
-    self.start_ui_text = None;
-    self.high_score_ui_text = None;
+    world.insert(self.level.clone());
+    world.insert(FieldRotation::default());
+    world.exec(|(entities, movers): (Entities, ReadStorage<Mover>)| {
+      for (e, _) in (&*entities, &movers).join() {
+        entities.delete(e).expect("Couldn't delete mover!");
+      }
+    });
+    if let Some((sprite_sheet_handle, sprite_pos)) = get_brick_sprite(world) {
+      let (playfield_width, playfield_height) = {
+        let playfield = world.read_resource::<Playfield>();
+        (playfield.width, playfield.height)
+      };
+      if self.mode.radial() {
+        spawn_rings(world, self.wave, [1., 1., 1.], &sprite_sheet_handle, sprite_pos);
+      } else {
+        spawn_bricks(
+          world,
+          &self.level,
+          playfield_width,
+          playfield_height,
+          [1., 1., 1.],
+          &sprite_sheet_handle,
+          sprite_pos,
+        );
+        spawn_movers(
+          world,
+          &self.level.movers,
+          playfield_width,
+          playfield_height,
+          &sprite_sheet_handle,
+          sprite_pos,
+        );
+      }
+    }
+    if bonus_round {
+      let brick_ys = world.exec(|(bricks, transforms): (ReadStorage<Brick>, ReadStorage<Transform>)| {
+        (&bricks, &transforms)
+          .join()
+          .map(|(_, transform)| transform.translation().y)
+          .collect::<Vec<_>>()
+      });
+      let center_y = brick_ys.iter().sum::<f32>() / brick_ys.len().max(1) as f32;
+      let center_x = world.read_resource::<Playfield>().width / 2.;
+      world.insert(FieldRotation {
+        center: Some((center_x, center_y)),
+        ..Default::default()
+      });
+      let mut bonus = world.write_resource::<BonusState>();
+      bonus.award = "BONUS ROUND";
+      bonus.flash = BONUS_FLASH_SECONDS;
+    }
+    play_sound_in_state(world, SoundType::PowerUp);
   }
 
-  fn handle_event(
-    &mut self,
-    data: StateData<'_, BreakoutGameData<'a, 'b>>,
-    event: StateEvent<StringBindings>,
+  /// Ends a timed or single life run, records the score on the leaderboard of the mode and shows it under `title`
+  /// over the start menu.
+  fn run_over<'a, 'b>(
+    &self,
+    world: &World,
+    title: &str,
   ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
-    let world = data.world;
-
-    if let StateEvent::Window(event) = &event {
-      if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
-        return Trans::Quit;
+    if self.mode == PlayMode::Tournament {
+      return self.turn_over(world);
+    }
+    if self.campaign.is_some() {
+      // A retry starts the run over with fresh lives.
+      let mut progress = world.write_resource::<Progress>();
+      if progress.carried(self.new_game_plus).is_some() {
+        *progress.carried_mut(self.new_game_plus) = None;
+        progress.save();
       }
     }
-
-    if let StateEvent::Input(event) = &event {
-      if let InputEvent::KeyPressed { key_code, .. } = event {
-        match key_code {
-          VirtualKeyCode::Up => {
-            let mut ui_text = world.write_storage::<UiText>();
-            assign_text_color!(self, start_ui_text, ui_text, [0.4, 1., 1., 1.]);
-            assign_text_color!(self, high_score_ui_text, ui_text, [1., 1., 1., 1.]);
-            play_sound_in_state(&world, SoundType::PaddleHit);
-            self.text_selected = TextSelectedType::Start;
-          }
-          VirtualKeyCode::Down => {
-            let mut ui_text = world.write_storage::<UiText>();
-            assign_text_color!(self, start_ui_text, ui_text, [1., 1., 1., 1.]);
-            assign_text_color!(self, high_score_ui_text, ui_text, [0.4, 1., 1., 1.]);
-            play_sound_in_state(&world, SoundType::PaddleHit);
-            self.text_selected = TextSelectedType::HighScore;
-          }
-          VirtualKeyCode::Return => {
-            play_sound_in_state(&world, SoundType::Confirm);
-            match self.text_selected {
-              TextSelectedType::Start => {
-                return Trans::Switch(Box::new(PlayState { debounce_timer: None }));
-              }
-              TextSelectedType::HighScore => {}
-            }
-          }
-          _ => {}
-        }
+    let points = world.read_resource::<Score>().points;
+    observe(
+      world,
+      ObserverEvent::RunOver {
+        reason: title.to_string(),
+        score: points,
+      },
+    );
+    let mut lines = vec![format!("SCORE {}", points)];
+    if let (Some(leaderboard), Ok(app_root)) = (self.mode.leaderboard(), application_root_dir()) {
+      let dir = app_root.join(CUSTOM_LEVELS_DIR);
+      let mut personal_bests = PersonalBests::load(&dir);
+      let best = personal_bests.best(&leaderboard);
+      let flags = RunFlags {
+        assisted: self.assisted,
+        mutated: self.mutators != Mutators::default(),
+        // Nothing adjusts the difficulty during a run yet.
+        dynamic_difficulty: false,
+        replay: !self.playtest && self.recorder.is_complete(),
+      };
+      if personal_bests.submit_run(&leaderboard, points, flags) {
+        personal_bests.save(&dir);
+        capture_highlight(world, "NEW BEST", self.mode.label());
+        lines.push("NEW BEST".to_string());
+      } else if let Some(best) = best {
+        lines.push(format!("BEST {}", best));
       }
     }
-
-    Trans::None
+    Trans::Switch(Box::new(GameOverState::new(title, lines, self.retry())))
   }
 
-  fn update(
-    &mut self,
-    mut data: StateData<'_, BreakoutGameData<'a, 'b>>,
-  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
-    let world = &mut data.world;
+  /// A fresh start of the same run, endless modes begin again with the first wave.
+  fn retry(&self) -> PlayState {
+    PlayState {
+      level: if self.mode.endless() {
+        modes::wave(0)
+      } else {
+        self.level.clone()
+      },
+      playtest: self.playtest,
+      campaign: self.campaign,
+      new_game_plus: self.new_game_plus,
+      mode: self.mode,
+      mutators: self.mutators,
+      input_assignment: self.input_assignment.clone(),
+      ..Default::default()
+    }
+  }
 
-    if self.start_ui_text.is_none() {
-      world.exec(|finder: UiFinder<'_>| {
-        if let Some(entity) = finder.find("start") {
-          self.start_ui_text = Some(entity);
-        }
-      });
+  /// Records the points of a tournament turn and shows the standings over the modes menu, where the tournament
+  /// was started from.
+  fn turn_over<'a, 'b>(&self, world: &World) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    observe(
+      world,
+      ObserverEvent::LevelCleared {
+        level: self.level.name.clone(),
+        score: world.read_resource::<Score>().points,
+      },
+    );
+    if let Some(mut tournament) = Tournament::load() {
+      tournament.record(world.read_resource::<Score>().points);
+      tournament.save();
     }
-    if self.high_score_ui_text.is_none() {
+    play_sound_in_state(world, SoundType::Confirm);
+    Trans::Sequence(vec![
+      Trans::Switch(Box::new(StartState::default())),
+      Trans::Push(Box::new(ModesState::default())),
+      Trans::Push(Box::new(TournamentState::default())),
+    ])
+  }
+
+  fn show_countdown(&mut self, world: &World) {
+    if self.countdown_ui_text.is_none() {
       world.exec(|finder: UiFinder| {
-        if let Some(entity) = finder.find("highscore") {
-          self.high_score_ui_text = Some(entity);
-        }
+        self.countdown_ui_text = finder.find("countdown");
       });
     }
-    if let Some(ref progress_counter) = self.progress_counter {
-      if progress_counter.is_complete() {
-        let sprite_sheets_map = {
-          let sprite_sheet_map = world.read_resource::<SpriteSheetMap>();
-          sprite_sheet_map.0.clone()
-        };
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.countdown_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = self
+        .resume_countdown
+        .map_or_else(String::new, |time| (time.ceil() as u32).to_string());
+    }
+  }
 
-        for (asset_type, sprite_sheet_handle) in sprite_sheets_map {
-          if let AssetType::Background(sprite_pos) = asset_type {
-            let (width, height) = {
-              let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
-              let spritesheet = sprite_sheet_store
-                .get(&sprite_sheet_handle)
-                .expect("Couldn't find the handle for the background sprite!");
-              (
-                spritesheet.sprites[sprite_pos].width,
-                spritesheet.sprites[sprite_pos].height,
-              )
-            };
-            let mut transform = Transform::from(Vector3::new(VIRTUAL_WIDTH / 2., VIRTUAL_HEIGHT / 2., 1.1));
-            transform.set_scale(Vector3::new(
-              VIRTUAL_WIDTH / (width - 2.),
-              VIRTUAL_HEIGHT / (height - 2.),
-              1.0,
-            ));
-            world
-              .create_entity()
-              .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
-              .with(transform)
-              .build();
-          }
-        }
-        self.progress_counter = None;
+  /// Width of the playfield the level asks for, else the one of the mode. The radial mode is laid out around the
+  /// center of the screen and always uses its width.
+  fn playfield_width(&self) -> f32 {
+    if self.mode.radial() {
+      return VIRTUAL_WIDTH;
+    }
+    self
+      .level
+      .playfield
+      .map(|(width, _)| width)
+      .or_else(|| self.mode.playfield_width())
+      .unwrap_or(VIRTUAL_WIDTH)
+  }
+
+  /// Reports changes of the score and the bricks left to the observer stream.
+  fn observe_progress(&mut self, world: &World) {
+    let observed = (world.read_resource::<Score>().points, bricks_left(world));
+    if let Some((points, bricks)) = self.observed {
+      if observed.0 != points {
+        observe(world, ObserverEvent::Score { points: observed.0 });
+      }
+      if observed.1 < bricks {
+        observe(
+          world,
+          ObserverEvent::BricksDestroyed {
+            count: bricks - observed.1,
+            left: observed.1,
+          },
+        );
       }
     }
-    data.data.update(&world, true);
+    self.observed = Some(observed);
+  }
 
-    Trans::None
+  /// Warns about a player that stopped playing in a multiplayer mode, returns `true` once the game should pause
+  /// for them.
+  fn check_away(&mut self, world: &World) -> bool {
+    if self.away_ui_text.is_none() {
+      world.exec(|finder: UiFinder| {
+        self.away_ui_text = finder.find("away");
+      });
+    }
+    let away = world.read_resource::<AwayTimers>().longest(self.mode.players());
+    let mut ui_text = world.write_storage::<UiText>();
+    if let Some(text) = self.away_ui_text.and_then(|entity| ui_text.get_mut(entity)) {
+      text.text = match away {
+        Some((seat, idle)) if idle >= AWAY_WARNING_SECONDS => format!(
+          "PLAYER {} AWAY - PAUSING IN {}",
+          seat + 1,
+          (AWAY_PAUSE_SECONDS - idle).max(0.).ceil()
+        ),
+        _ => String::new(),
+      };
+    }
+    away.map_or(false, |(_, idle)| idle >= AWAY_PAUSE_SECONDS)
   }
-}
 
-#[derive(Default)]
-struct PlayState {
-  debounce_timer: Option<f32>,
+  fn tilt(&self, world: &World) {
+    let mut tilt = world.write_resource::<TiltState>();
+    if tilt.remaining == 0 || tilt.pending {
+      return;
+    }
+    tilt.remaining -= 1;
+    tilt.used += 1;
+    tilt.pending = true;
+    tilt.penalty += TILT_PENALTY * tilt.used;
+    play_sound_in_state(world, SoundType::WallHit);
+  }
 }
 
 impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for PlayState {
   fn on_start(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
     let StateData { world, .. } = data;
 
+    if let Some(at) = self.campaign {
+      let mut progress = world.write_resource::<Progress>();
+      if *progress.resume(self.new_game_plus) != Some(at) {
+        *progress.resume_mut(self.new_game_plus) = Some(at);
+        progress.save();
+      }
+    }
+    load_assets(world, &AssetType::GAMEPLAY);
+    world.insert(self.level.clone());
+    world.insert(GameMode {
+      new_game_plus: self.new_game_plus,
+      mode: self.mode,
+    });
+    world.insert(self.input_assignment.clone());
+    if self.mutators.mirrored {
+      // Only the view is flipped, the playfield and the input keep their orientation. `clear_playfield` flips it
+      // back.
+      world.exec(
+        |(cameras, mut transforms): (ReadStorage<Camera>, WriteStorage<Transform>)| {
+          for (transform, _) in (&mut transforms, &cameras).join() {
+            transform.set_scale(Vector3::new(-1., 1., 1.));
+          }
+        },
+      );
+    }
+    // A campaign level cleared before picks up the score and lives of the run, also after the game was restarted.
+    let carried = self.campaign.and_then(|at| {
+      world
+        .read_resource::<Progress>()
+        .carried(self.new_game_plus)
+        .filter(|run| run.at == at)
+    });
+    world.insert(match carried {
+      Some(run) => Score {
+        points: run.score,
+        seats: [run.score, 0],
+      },
+      None => Score::default(),
+    });
+    world.insert(Lives {
+      remaining: carried.map_or_else(|| self.starting_lives(), |run| run.lives),
+    });
+    world.insert(ModeClock {
+      remaining: self.mode.time_limit(),
+    });
+    world.insert(DashState::default());
+    world.insert(ServeBuffer::default());
+    world.insert(AwayTimers::default());
+    world.insert(EnergyMeter::default());
+    world.insert(BallLost::default());
+    world.insert(HitStop {
+      muted: self.mode.calm(),
+      ..Default::default()
+    });
+    world.insert(SplitQueue::default());
+    world.insert(BonusState::default());
+    world.insert(Lightning::default());
+    world.insert(Breaker::default());
+    world.insert(FieldRotation::default());
+    world.insert(CloseCall::default());
+    world.insert(LevelStats {
+      serves: 1,
+      ..Default::default()
+    });
+    let playfield_width = self.playfield_width();
+    // Only as tall as the screen until the bricks are spawned, the paddles are placed by the width.
+    world.insert(Playfield::new(playfield_width, VIRTUAL_HEIGHT));
+    world.insert(TiltState {
+      remaining: if self.mode.assists() { TILT_USES } else { 0 },
+      ..Default::default()
+    });
+    self.hud_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/hud.ron", ())));
+    observe(
+      world,
+      ObserverEvent::LevelStarted {
+        level: self.level.name.clone(),
+        mode: self.mode.label(),
+      },
+    );
+    let theme = self
+      .campaign
+      .and_then(|at| {
+        let campaign = world.read_resource::<Campaign>();
+        campaign
+          .worlds
+          .get(at.world)
+          .map(|campaign_world| campaign_world.theme.clone())
+      })
+      .unwrap_or_default();
+    if let Some([r, g, b]) = theme.background {
+      world.exec(
+        |(entities, backgrounds, mut tints): (Entities, ReadStorage<Background>, WriteStorage<Tint>)| {
+          for (e, _) in (&*entities, &backgrounds).join() {
+            tints
+              .insert(e, Tint(Srgba::new(r, g, b, 1.)))
+              .expect("Couldn't tint the background!");
+          }
+        },
+      );
+    }
+    let [brick_r, brick_g, brick_b] = theme.bricks.unwrap_or([1., 1., 1.]);
+    let (paddle_skin, ball_skin) = {
+      let campaign = world.read_resource::<Campaign>();
+      let progress = world.read_resource::<Progress>();
+      (
+        skins::PADDLE_SKINS[skins::selected(&skins::PADDLE_SKINS, &progress.paddle_skin, &campaign, &progress)],
+        skins::BALL_SKINS[skins::selected(&skins::BALL_SKINS, &progress.ball_skin, &campaign, &progress)],
+      )
+    };
     let sprite_sheets_map = {
       let sprite_sheet_map = world.read_resource::<SpriteSheetMap>();
       sprite_sheet_map.0.clone()
@@ -507,17 +8424,36 @@ impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for PlayState {
 
     for (asset_type, sprite_sheet_handle) in sprite_sheets_map {
       match asset_type {
-        AssetType::PaddleMedium(sprite_pos) => {
+        AssetType::PaddleMedium(_) => {
+          let sprite_pos = paddle_skin.sprite;
           let (width, height) = get_texture_dimensions(world, &sprite_sheet_handle, sprite_pos);
-          world
-            .create_entity()
-            .with(Paddle { width, height })
-            .with(Player)
-            .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
-            .with(Transform::from(Vector3::new(VIRTUAL_WIDTH / 2., 16., 1.2)))
-            .build();
+          let players = self.mode.players();
+          for seat in 0..players {
+            let [r, g, b] = if seat == 0 {
+              paddle_skin.tint
+            } else {
+              SECOND_PLAYER_COLOR
+            };
+            let x = playfield_width / players as f32 * (seat as f32 + 0.5);
+            let mut builder = world
+              .create_entity()
+              .with(Paddle {
+                width,
+                height,
+                velocity: 0.,
+              })
+              .with(Player { seat })
+              .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
+              .with(Tint(Srgba::new(r, g, b, 1.)))
+              .with(Layer::Playfield.transform(x, PADDLE_Y));
+            if self.mode.radial() {
+              builder = builder.with(ArcPaddle { track: x });
+            }
+            builder.build();
+          }
         }
-        AssetType::Ball(sprite_pos) => {
+        AssetType::Ball(_) => {
+          let sprite_pos = ball_skin.sprite;
           let width = {
             let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
             let spritesheet = sprite_sheet_store
@@ -525,63 +8461,207 @@ impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for PlayState {
               .expect("Couldn't find the handle for the ball sprite!");
             spritesheet.sprites[sprite_pos].width
           };
-          world
-            .create_entity()
-            .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
-            .with(Transform::from(Vector3::new(
-              VIRTUAL_WIDTH / 2.,
-              VIRTUAL_HEIGHT / 2.,
-              1.3,
-            )))
-            .with(Ball {
-              direction: Vector3::new(0., -1., 0.),
-              radius: width / 2.,
-            })
-            .build();
+          // Every ball waits on its paddle until it is served.
+          let players = self.mode.players();
+          for seat in 0..players {
+            let color = if seat == 0 { ball_skin.tint } else { SECOND_PLAYER_COLOR };
+            let [r, g, b] = color;
+            world
+              .create_entity()
+              .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
+              .with(Tint(Srgba::new(r, g, b, 1.)))
+              .with(Layer::Ball.transform(playfield_width / 2., VIRTUAL_HEIGHT / 2.))
+              .with(Ball {
+                direction: Vector3::new(0., -1., 0.),
+                radius: width / 2.,
+                speed: BALL_VELOCITY,
+                color,
+                stuck: true,
+                seat,
+                wind_up: None,
+                waiting: 0.,
+              })
+              .build();
+          }
         }
         AssetType::PaddleSmall(sprite_pos) => {
           let (width, height) = get_texture_dimensions(world, &sprite_sheet_handle, sprite_pos);
-          for x in 0..2 {
-            for y in 0..9 {
-              world
-                .create_entity()
-                .with(Paddle { width, height })
-                .with(SpriteRender::new(sprite_sheet_handle.clone(), sprite_pos))
-                .with(Transform::from(Vector3::new(
-                  VIRTUAL_WIDTH / 5.2 + y as f32 * width + y as f32 * 2.,
-                  VIRTUAL_HEIGHT / 1.2 + x as f32 * height + x as f32 * 4.,
-                  1.2,
-                )))
-                .build();
-            }
+          let rows = self.level.rows.len() as f32;
+          let playfield = if self.mode.radial() {
+            Playfield::default()
+          } else {
+            let height = VIRTUAL_HEIGHT + (rows - 2.).max(0.) * (height + 4.);
+            let requested = self.level.playfield.map_or(0., |(_, height)| height);
+            Playfield::new(playfield_width, height.max(requested))
+          };
+          let playfield_height = playfield.height;
+          world.insert(playfield);
+          if self.mode.radial() {
+            spawn_rings(world, 0, [brick_r, brick_g, brick_b], &sprite_sheet_handle, sprite_pos);
+          } else {
+            spawn_bricks(
+              world,
+              &self.level,
+              playfield_width,
+              playfield_height,
+              [brick_r, brick_g, brick_b],
+              &sprite_sheet_handle,
+              sprite_pos,
+            );
+            spawn_movers(
+              world,
+              &self.level.movers,
+              playfield_width,
+              playfield_height,
+              &sprite_sheet_handle,
+              sprite_pos,
+            );
           }
         }
         _ => {}
       }
     }
+
+    if let Some(snapshot) = self.restore.take() {
+      world.exec(
+        |(entities, paddles, bricks, balls, bumpers, gates, movers): (
+          Entities,
+          ReadStorage<Paddle>,
+          ReadStorage<Brick>,
+          ReadStorage<Ball>,
+          ReadStorage<Bumper>,
+          ReadStorage<Gate>,
+          ReadStorage<Mover>,
+        )| {
+          for (e, _) in (&*entities, &paddles).join() {
+            entities.delete(e).expect("Couldn't delete paddle!");
+          }
+          for (e, _) in (&*entities, &bricks).join() {
+            entities.delete(e).expect("Couldn't delete brick!");
+          }
+          for (e, _) in (&*entities, &balls).join() {
+            entities.delete(e).expect("Couldn't delete ball!");
+          }
+          for (e, _) in (&*entities, &bumpers).join() {
+            entities.delete(e).expect("Couldn't delete bumper!");
+          }
+          for (e, _) in (&*entities, &gates).join() {
+            entities.delete(e).expect("Couldn't delete gate!");
+          }
+          for (e, _) in (&*entities, &movers).join() {
+            entities.delete(e).expect("Couldn't delete mover!");
+          }
+        },
+      );
+      world.maintain();
+      if let Err(e) = gameplay_snapshot().restore(world, &snapshot) {
+        warn!("Couldn't continue the suspended level: {}", e);
+      }
+    }
+  }
+
+  fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    let suspend = std::mem::replace(&mut data.world.write_resource::<SuspendRequested>().0, false);
+    // Endless modes, tournaments and playtests are over once they are left.
+    if suspend && self.mode == PlayMode::Normal && !self.playtest {
+      Suspended {
+        level: self.level.clone(),
+        campaign: self.campaign,
+        new_game_plus: self.new_game_plus,
+        snapshot: gameplay_snapshot().capture(data.world),
+      }
+      .save();
+    }
+    if let Some(e) = self.hud_root.take() {
+      data.world.delete_entity(e).expect("Failed to remove hud!");
+    }
+    self.countdown_ui_text = None;
+    self.away_ui_text = None;
+    if let Some(sink) = self.ambient.take() {
+      sink.stop();
+    }
+    data.world.write_resource::<Time>().set_time_scale(1.);
+    let level = if self.mode.endless() {
+      self.mode.label()
+    } else {
+      &self.level.name
+    };
+    self.recorder.finish(level, data.world.read_resource::<Score>().points);
+    observe(data.world, ObserverEvent::LevelLeft);
+    clear_playfield(data.world);
+  }
+
+  fn on_pause(&mut self, _data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    if let Some(sink) = &self.ambient {
+      sink.pause();
+    }
   }
 
-  fn on_resume(&mut self, _data: StateData<'_, BreakoutGameData<'a, 'b>>) {
-    self.debounce_timer = Some(0.25);
+  fn on_resume(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
+    if let Some(sink) = &self.ambient {
+      sink.play();
+    }
+    data.world.insert(AwayTimers::default());
+    if self.tallied {
+      return;
+    }
+    self.resume_countdown = Some(RESUME_COUNTDOWN_SECONDS);
+    self.show_countdown(data.world);
   }
 
   fn handle_event(
     &mut self,
-    _data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
     event: StateEvent<StringBindings>,
   ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
     if let StateEvent::Window(event) = &event {
-      if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
+      if is_close_requested(&event) {
         return Trans::Quit;
       }
+      if let Some((_, y)) = handle_touch(&data.world, &event) {
+        if y >= VIRTUAL_HEIGHT / 2. && self.resume_countdown.is_none() {
+          return Trans::Push(Box::new(PausedState::default()));
+        }
+      }
     }
 
     if let StateEvent::Input(event) = &event {
+      let served = data
+        .world
+        .read_resource::<InputAssignment>()
+        .serve_pressed(event, &SERVE_ACTIONS);
+      if let Some(seat) = served {
+        let latency = data.world.read_resource::<Settings>().input_latency;
+        data.world.write_resource::<ServeBuffer>().0[seat].press(latency);
+      }
+      if let InputEvent::ActionPressed(action) = event {
+        match action.as_str() {
+          "tilt" => self.tilt(&data.world),
+          "bomb" if self.mode.power_ups() => {
+            let mut energy = data.world.write_resource::<EnergyMeter>();
+            if energy.charge >= 1. {
+              energy.bomb_pending = true;
+            }
+          }
+          _ => {}
+        }
+      }
       if let InputEvent::KeyPressed { key_code, .. } = event {
-        if let VirtualKeyCode::Space = key_code {
-          if self.debounce_timer.is_none() {
-            return Trans::Push(Box::new(PausedState::default()));
+        match key_code {
+          VirtualKeyCode::F3 => {
+            let mut overlay = data.world.write_resource::<DebugOverlay>();
+            overlay.visible = !overlay.visible;
+          }
+          VirtualKeyCode::Escape if self.playtest => return Trans::Pop,
+          VirtualKeyCode::Escape => return Trans::Quit,
+          VirtualKeyCode::Space => {
+            if self.resume_countdown.is_none() {
+              return Trans::Push(Box::new(PausedState::default()));
+            }
+            let latency = data.world.read_resource::<Settings>().input_latency;
+            self.pause_buffer.press(latency);
           }
+          _ => {}
         }
       }
     }
@@ -595,14 +8675,123 @@ impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for PlayState {
   ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
     let StateData { world, .. } = data;
 
-    if let Some(mut time) = self.debounce_timer.take() {
-      time -= world.fetch::<Time>().delta_seconds();
-      if time >= 0.0 {
-        self.debounce_timer.replace(time);
+    let delta_seconds = world.fetch::<Time>().delta_real_seconds();
+    self.pause_buffer.tick(delta_seconds);
+    for press in world.write_resource::<ServeBuffer>().0.iter_mut() {
+      press.tick(delta_seconds);
+    }
+    if self.mode.calm() {
+      loop_ambient(&world, &mut self.ambient);
+    }
+    if let Some(time) = self.resume_countdown {
+      let remaining = time - delta_seconds;
+      if remaining > 0. && remaining.ceil() < time.ceil() {
+        play_sound_in_state(&world, SoundType::PaddleHit);
+      }
+      self.resume_countdown = Some(remaining).filter(|&remaining| remaining > 0.);
+      self.show_countdown(&world);
+      // The ball stays frozen until the countdown is over, the paddle can already be moved.
+      if self.resume_countdown.is_some() {
+        data.data.update(&world, false);
+        return Trans::None;
       }
     }
 
     data.data.update(&world, true);
+    if !self.playtest {
+      self.recorder.tick(
+        delta_seconds,
+        || capture_frame(world),
+        || gameplay_snapshot().capture_entities(world),
+      );
+    }
+    self.observe_progress(world);
+    let tilted = world.read_resource::<TiltState>().used > 0;
+    if tilted || (self.mode.assists() && world.read_resource::<Settings>().assist) {
+      self.assisted = true;
+    }
+
+    {
+      let delta_seconds = world.fetch::<Time>().delta_seconds();
+      let mut clock = world.write_resource::<ModeClock>();
+      if let Some(remaining) = clock.remaining.as_mut() {
+        *remaining -= delta_seconds;
+      }
+    }
+    if world
+      .read_resource::<ModeClock>()
+      .remaining
+      .map_or(false, |remaining| remaining <= 0.)
+    {
+      return self.run_over(world, "TIME UP");
+    }
+    let out_of_lives = world.read_resource::<Lives>().remaining == Some(0);
+    // The run ends once the ball lost banner is over.
+    if out_of_lives && world.read_resource::<BallLost>().elapsed.is_none() {
+      return self.run_over(world, "GAME OVER");
+    }
+    if self.mode.endless() {
+      let bricks_left = bricks_left(world);
+      if bricks_left == 0 && world.read_resource::<HitStop>().remaining <= 0. {
+        self.next_wave(world);
+      }
+    }
+    if self.mode == PlayMode::Tournament {
+      let bricks_left = bricks_left(world);
+      if bricks_left == 0 && world.read_resource::<HitStop>().remaining <= 0. {
+        return self.turn_over(world);
+      }
+    }
+
+    if self.mode == PlayMode::Normal && self.campaign.is_none() {
+      let bricks_left = bricks_left(world);
+      if bricks_left == 0 && world.read_resource::<HitStop>().remaining <= 0. {
+        return self.level_cleared(world);
+      }
+    }
+
+    if let Some(at) = self.campaign {
+      let bricks_left = bricks_left(world);
+      if bricks_left == 0 && self.tallied {
+        return self.campaign_cleared(world, at);
+      }
+      // The last brick gets its hit-stop before the tally covers the playfield.
+      if bricks_left == 0 && world.read_resource::<HitStop>().remaining <= 0. {
+        self.tallied = true;
+        capture_highlight(world, "LEVEL CLEAR", &self.level.name);
+        observe(
+          world,
+          ObserverEvent::LevelCleared {
+            level: self.level.name.clone(),
+            score: world.read_resource::<Score>().points,
+          },
+        );
+        let mut stats = *world.read_resource::<LevelStats>();
+        stats.tilts = world.read_resource::<TiltState>().used;
+        return Trans::Push(Box::new(TallyState::new(stats)));
+      }
+    }
+
+    if self.resume_countdown.is_none() && self.pause_buffer.take() {
+      return Trans::Push(Box::new(PausedState::default()));
+    }
+    if self.resume_countdown.is_none() && world.read_resource::<WindowActivity>().background {
+      return Trans::Push(Box::new(PausedState::default()));
+    }
+    // Lives are shared in co-op, so an away player pauses the game instead of forfeiting.
+    if self.mode.players() > 1 && self.check_away(world) {
+      return Trans::Push(Box::new(PausedState::default()));
+    }
+
+    Trans::None
+  }
+
+  fn fixed_update(
+    &mut self,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
+  ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
+    let StateData { world, .. } = data;
+    data.data.fixed_update(&world);
 
     Trans::None
   }
@@ -619,6 +8808,7 @@ impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for PausedState {
 
     self.ui_root = Some(world.exec(|mut creator: UiCreator<'_>| creator.create("ui/pause.ron", ())));
     play_sound_in_state(&world, SoundType::Pause);
+    observe(world, ObserverEvent::Paused);
   }
 
   fn on_stop(&mut self, data: StateData<'_, BreakoutGameData<'a, 'b>>) {
@@ -629,23 +8819,136 @@ impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for PausedState {
     }
     self.ui_root = None;
     play_sound_in_state(&world, SoundType::Pause);
+    observe(world, ObserverEvent::Resumed);
   }
 
   fn handle_event(
     &mut self,
-    _data: StateData<'_, BreakoutGameData<'a, 'b>>,
+    data: StateData<'_, BreakoutGameData<'a, 'b>>,
     event: StateEvent<StringBindings>,
   ) -> Trans<BreakoutGameData<'a, 'b>, StateEvent<StringBindings>> {
     if let StateEvent::Window(event) = &event {
-      if is_close_requested(&event) || is_key_down(&event, VirtualKeyCode::Escape) {
+      if is_close_requested(&event) {
         return Trans::Quit;
       }
+      if handle_touch(&data.world, &event).is_some() {
+        return Trans::Pop;
+      }
     }
 
     if let StateEvent::Input(event) = &event {
       if let InputEvent::KeyPressed { key_code, .. } = event {
-        if let VirtualKeyCode::Space = key_code {
-          return Trans::Pop;
+        match key_code {
+          VirtualKeyCode::Escape => {
+            data.world.write_resource::<SuspendRequested>().0 = true;
+            return Trans::Quit;
+          }
+          VirtualKeyCode::Space => return Trans::Pop,
+          VirtualKeyCode::E => export_level(&data.world),
+          VirtualKeyCode::R => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.rumble = !settings.rumble;
+            settings.save();
+            info!(
+              "Gamepad rumble {}",
+              if settings.rumble { "enabled" } else { "disabled" }
+            );
+          }
+          VirtualKeyCode::K => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.controls = settings.controls.next();
+            settings.save();
+            if let Some(bindings) = settings.controls.load_bindings() {
+              data.world.write_resource::<InputHandler<StringBindings>>().bindings = bindings;
+            }
+            info!("Controls: {}", settings.controls.label());
+          }
+          VirtualKeyCode::C => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.captions = !settings.captions;
+            settings.save();
+          }
+          VirtualKeyCode::L => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.event_log = !settings.event_log;
+            settings.save();
+          }
+          VirtualKeyCode::N => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.narration = !settings.narration;
+            settings.save();
+          }
+          VirtualKeyCode::S => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.streamer_mode = !settings.streamer_mode;
+            settings.save();
+          }
+          VirtualKeyCode::B => {
+            let lines = write_report(&data.world);
+            return Trans::Push(Box::new(ErrorDialogState::new("DIAGNOSTIC BUNDLE", lines)));
+          }
+          VirtualKeyCode::H => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.hit_stop = !settings.hit_stop;
+            settings.save();
+          }
+          VirtualKeyCode::O => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.reduced_motion = !settings.reduced_motion;
+            settings.save();
+          }
+          VirtualKeyCode::G => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.glow = !settings.glow;
+            settings.save();
+          }
+          VirtualKeyCode::A => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.auto_serve = settings.next_auto_serve();
+            settings.save();
+            match settings.auto_serve {
+              Some(seconds) => info!("Auto serve after {} seconds", seconds),
+              None => info!("Auto serve disabled"),
+            }
+          }
+          VirtualKeyCode::I => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.assist = !settings.assist;
+            settings.save();
+          }
+          VirtualKeyCode::X => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.chaos = !settings.chaos;
+            settings.save();
+            info!("Chaos {}", if settings.chaos { "enabled" } else { "disabled" });
+          }
+          VirtualKeyCode::F => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.frame_cap = settings.frame_cap.next();
+            settings.save();
+            apply_frame_limit(&data.world, &settings);
+          }
+          VirtualKeyCode::Y => {
+            let mut settings = data.world.write_resource::<Settings>();
+            settings.vsync = !settings.vsync;
+            settings.save();
+            apply_frame_limit(&data.world, &settings);
+          }
+          VirtualKeyCode::V => {
+            let packs = announcer::packs();
+            let mut settings = data.world.write_resource::<Settings>();
+            let next = match &settings.announcer {
+              Some(pack) => packs.iter().position(|p| p == pack).map_or(0, |index| index + 1),
+              None => 0,
+            };
+            settings.announcer = packs.get(next).cloned();
+            settings.save();
+            info!(
+              "Announcer {}",
+              settings.announcer.as_ref().map_or("none", |pack| pack.as_str())
+            );
+          }
+          _ => {}
         }
       }
     }
@@ -668,13 +8971,53 @@ impl<'a, 'b> State<BreakoutGameData<'a, 'b>, StateEvent> for PausedState {
 /// main
 ///
 fn main() -> amethyst::Result<()> {
-  amethyst::start_logger(Default::default());
-
+  let launched_at = Instant::now();
   let app_root = application_root_dir()?;
-  let display_conf_path = app_root.join("config/display.ron");
-  let bindings_config_path = app_root.join("config/bindings.ron");
+  amethyst::start_logger(LoggerConfig {
+    log_file: Some(app_root.join(report::LOG_PATH)),
+    ..Default::default()
+  });
+
+  // `breakout --export-replay <replay.ron>` renders a replay to a video without opening the game.
+  let args = std::env::args().skip(1).collect::<Vec<_>>();
+  if let ["--export-replay", replay] = args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+    let (mut world, plugin, mut scene) = (replay_world(), playfield_snapshot(), ReplayScene::default());
+    let rects_at = |recording: &Replay, frame: usize| {
+      if let Err(e) = scene.show(&mut world, &plugin, recording, frame) {
+        warn!("Couldn't rebuild frame {}: {}", frame, e);
+      }
+      let (bricks, movers) = capture_rects(&world);
+      bricks.into_iter().chain(movers).collect::<Vec<_>>()
+    };
+    match export::export_replay(Path::new(replay), &app_root, VIRTUAL_WIDTH, VIRTUAL_HEIGHT, rects_at) {
+      Ok(path) => info!("Exported {}", path.display()),
+      Err(e) => error!("Couldn't export {}: {}", replay, e),
+    }
+    return Ok(());
+  }
+
+  let display_config: DisplayConfig = embedded::load_config("config/display.ron")?;
+  sync::sync_configured();
+  let settings = Settings::load();
+  let observer_port = settings.observer_port;
+  let (frame_limit_strategy, frame_limit_fps) = settings.frame_limit();
+  let bindings = settings.controls.load_bindings().unwrap_or_default();
   let asset_dir = app_root.join("assets");
-  let app_builder = Application::build(asset_dir, StartState::default())?;
+  let mut app_builder = Application::build(&asset_dir, StartState::default())?
+    .with_frame_limit(frame_limit_strategy, frame_limit_fps)
+    .with_resource(LaunchedAt(launched_at))
+    .with_resource(SuspendRequested::default())
+    .with_resource(DebugLines::new())
+    .with_resource(DebugLinesParams { line_width: 2. })
+    .with_resource(UpdateNotice::check(settings.update_check_url.as_deref()))
+    .with_resource(settings)
+    .with_resource(GameConfig::load())
+    .with_resource(Campaign::load())
+    .with_resource(Progress::load());
+  app_builder
+    .world
+    .write_resource::<Loader>()
+    .set_default_source(FallbackSource::new(&asset_dir));
   let game_data = BreakoutGameDataBuilder::default()
     .with_base_bundle(TransformBundle::new())
     .with_base_bundle(InputBundle::<StringBindings>::new())
@@ -682,17 +9025,64 @@ fn main() -> amethyst::Result<()> {
     .with_base_bundle(AudioBundle::default())
     .with_base_bundle(
       RenderingBundle::<DefaultBackend>::new()
-        .with_plugin(RenderToWindow::from_config_path(display_conf_path)?.with_clear([0., 0., 0., 1.]))
+        .with_plugin(RenderToWindow::from_config(display_config).with_clear([0., 0., 0., 1.]))
         .with_plugin(RenderFlat2D::default())
+        .with_plugin(RenderDebugLines::default())
         .with_plugin(RenderUi::default()),
     )
-    .with_running_bundle(InputBundle::<StringBindings>::new().with_bindings_from_file(bindings_config_path)?)
-    .with_running(PaddleSystem, "paddle_system", &["input_system"])
-    .with_running(BallSystem, "ball_system", &["paddle_system"])
-    .with_running(CollisionSystem, "collision_system", &["paddle_system", "ball_system"]);
+    .with_base(AnnouncerSystem::default(), "announcer_system", &[])
+    .with_base(NarrationSystem::default(), "narration_system", &[])
+    .with_base(ObserverSystem::new(observer_port), "observer_system", &[])
+    .with_base(WindowActivitySystem::default(), "window_activity_system", &[])
+    .with_running_bundle(InputBundle::<StringBindings>::new().with_bindings(bindings))
+    .with_fixed(PaddleSystem::default(), "paddle_system", &[])
+    .with_running(GhostSystem, "ghost_system", &[])
+    .with_running(HudSystem, "hud_system", &[])
+    .with_running(DebugOverlaySystem, "debug_overlay_system", &[])
+    .with_running(BombSystem, "bomb_system", &[])
+    .with_running(BallSystem, "ball_system", &[])
+    .with_running(CollisionSystem::default(), "collision_system", &["ball_system"])
+    .with_running(CameraFollowSystem, "camera_follow_system", &["ball_system"])
+    .with_running(BallCollisionSystem, "ball_collision_system", &["collision_system"])
+    .with_running(BallColorSystem, "ball_color_system", &["collision_system"])
+    .with_running(
+      CaptionSystem::default(),
+      "caption_system",
+      &["collision_system", "bomb_system"],
+    )
+    .with_running(BallLostSystem, "ball_lost_system", &["collision_system"])
+    .with_running(LightningSystem, "lightning_system", &["collision_system"])
+    .with_running(
+      EventLogSystem::default(),
+      "event_log_system",
+      &[
+        "collision_system",
+        "bomb_system",
+        "lightning_system",
+        "ball_lost_system",
+      ],
+    )
+    .with_running(DebrisSystem, "debris_system", &["collision_system"])
+    .with_running(GlowSystem, "glow_system", &["ball_system", "collision_system"])
+    .with_running(SplitSystem, "split_system", &["collision_system"])
+    .with_running(BonusSystem, "bonus_system", &["collision_system"])
+    .with_running(BreakerSystem, "breaker_system", &["collision_system"])
+    .with_running(BumperSystem, "bumper_system", &["collision_system"])
+    .with_running(DoorSystem, "door_system", &["collision_system", "bomb_system"])
+    .with_running(FieldRotationSystem, "field_rotation_system", &["collision_system"])
+    .with_running(MoverSystem, "mover_system", &["collision_system"])
+    .with_running(
+      TimeScaleSystem,
+      "time_scale_system",
+      &["ball_lost_system", "collision_system", "bomb_system"],
+    )
+    .with_running(InterceptSystem, "intercept_system", &["ball_lost_system"])
+    .with_running(ServeAimSystem, "serve_aim_system", &["ball_lost_system"])
+    .with_running(RumbleSystem::default(), "rumble_system", &["collision_system"]);
 
   let mut game = app_builder.build(game_data)?;
   game.run();
+  sync::sync_configured();
 
   Ok(())
 }
@@ -0,0 +1,246 @@
+use crate::level::{Level, MoverPath, BRICK_CHARS, SPLIT_BRICK};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+///
+/// constants
+///
+
+const SCORE_ATTACK_SECONDS: f32 = 180.;
+/// Ball speed in zen mode relative to the other modes.
+const ZEN_BALL_SPEED: f32 = 0.75;
+const HARDCORE_SCORE_MULTIPLIER: u32 = 2;
+const HARDCORE_HUD_TINT: [f32; 3] = [1., 0.35, 0.35];
+/// Balls that can be lost in a level of the default game and the campaign.
+const NORMAL_LIVES: u32 = 3;
+/// Balls the two players of co-op can lose together before the run is over.
+const COOP_LIVES: u32 = 6;
+/// Co-op is played on a wider playfield, so the two halves aren't cramped.
+const COOP_PLAYFIELD_WIDTH: f32 = 576.;
+/// Balls a player can lose in their turn of a tournament.
+const TOURNAMENT_LIVES: u32 = 3;
+const WAVE_COLUMNS: usize = 13;
+const WAVE_ROWS: usize = 6;
+/// Share of filled cells in the first wave, every wave adds `WAVE_DENSITY_STEP` up to a full grid.
+const WAVE_DENSITY: f32 = 0.5;
+const WAVE_DENSITY_STEP: f32 = 0.1;
+/// Share of the filled cells that are split bricks.
+const WAVE_SPLIT_CHANCE: f32 = 0.05;
+const BONUS_MOVER_SPEED: f32 = 70.;
+const BONUS_MOVER_WIDTH: f32 = 28.;
+
+///
+/// enums
+///
+
+/// Rule sets a level can be played with. Normal play is what the campaign and custom levels use, the others are
+/// picked from the modes menu.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PlayMode {
+  Normal,
+  /// Three minutes to score as much as possible on endless waves of bricks.
+  ScoreAttack,
+  /// Endless waves with a slower ball, muted effects and ambient music. Losing the ball costs nothing.
+  Zen,
+  /// Endless waves on a single ball without assists or power-ups, for double points.
+  Hardcore,
+  /// Experimental: the paddle runs along an arc and the bricks sit in rings, see `radial`.
+  Radial,
+  /// Two players share the screen, each with a paddle on their half, a ball of their own and a shared pool of lives.
+  Coop,
+  /// A turn of a hot-seat tournament, see `tournament`. It ends with the level or the last life.
+  Tournament,
+}
+
+impl Default for PlayMode {
+  fn default() -> Self {
+    PlayMode::Normal
+  }
+}
+
+impl PlayMode {
+  /// The modes listed in the modes menu.
+  pub const SELECTABLE: [PlayMode; 5] = [
+    PlayMode::ScoreAttack,
+    PlayMode::Zen,
+    PlayMode::Hardcore,
+    PlayMode::Radial,
+    PlayMode::Coop,
+  ];
+
+  pub fn label(self) -> &'static str {
+    match self {
+      PlayMode::Normal => "NORMAL",
+      PlayMode::ScoreAttack => "SCORE ATTACK",
+      PlayMode::Zen => "ZEN",
+      PlayMode::Hardcore => "HARDCORE",
+      PlayMode::Radial => "RADIAL",
+      PlayMode::Coop => "CO-OP",
+      PlayMode::Tournament => "TOURNAMENT",
+    }
+  }
+
+  /// Seconds of game time a run lasts, `None` if it only ends with the level.
+  pub fn time_limit(self) -> Option<f32> {
+    match self {
+      PlayMode::ScoreAttack => Some(SCORE_ATTACK_SECONDS),
+      _ => None,
+    }
+  }
+
+  /// Balls that can be lost before the run is over, `None` if a lost ball is simply served again.
+  pub fn lives(self) -> Option<u32> {
+    match self {
+      PlayMode::Normal => Some(NORMAL_LIVES),
+      PlayMode::Hardcore => Some(1),
+      PlayMode::Coop => Some(COOP_LIVES),
+      PlayMode::Tournament => Some(TOURNAMENT_LIVES),
+      _ => None,
+    }
+  }
+
+  /// Width of the playfield when the level doesn't ask for one, `None` for the width of the screen.
+  pub fn playfield_width(self) -> Option<f32> {
+    match self {
+      PlayMode::Coop => Some(COOP_PLAYFIELD_WIDTH),
+      _ => None,
+    }
+  }
+
+  /// A cleared playfield is refilled with the next `wave`.
+  pub fn endless(self) -> bool {
+    match self {
+      PlayMode::Normal | PlayMode::Tournament => false,
+      _ => true,
+    }
+  }
+
+  /// Multiplier of the ball speed.
+  pub fn ball_speed(self) -> f32 {
+    match self {
+      PlayMode::Zen => ZEN_BALL_SPEED,
+      _ => 1.,
+    }
+  }
+
+  /// The intercept marker and tilts, if the settings allow them.
+  pub fn assists(self) -> bool {
+    self != PlayMode::Hardcore
+  }
+
+  /// Bonus bricks and the bomb, both expect the bricks on a grid.
+  pub fn power_ups(self) -> bool {
+    self != PlayMode::Hardcore && !self.radial()
+  }
+
+  /// Paddles on the screen, each player gets an equal lane of the playfield.
+  pub fn players(self) -> usize {
+    match self {
+      PlayMode::Coop => 2,
+      _ => 1,
+    }
+  }
+
+  /// The paddle runs along an arc and the waves are rings instead of rows.
+  pub fn radial(self) -> bool {
+    self == PlayMode::Radial
+  }
+
+  pub fn score_multiplier(self) -> u32 {
+    match self {
+      PlayMode::Hardcore => HARDCORE_SCORE_MULTIPLIER,
+      _ => 1,
+    }
+  }
+
+  /// Color the HUD labels are multiplied with, so runs of the mode are recognizable at a glance.
+  pub fn hud_tint(self) -> Option<[f32; 3]> {
+    match self {
+      PlayMode::Hardcore => Some(HARDCORE_HUD_TINT),
+      _ => None,
+    }
+  }
+
+  /// Calm modes have no hit-stops, glow or debris, no penalty for a lost ball and loop the ambient track.
+  pub fn calm(self) -> bool {
+    self == PlayMode::Zen
+  }
+
+  /// Name the best score of the mode is recorded under, kept apart from level names by the brackets.
+  pub fn leaderboard(self) -> Option<String> {
+    match self {
+      PlayMode::Normal | PlayMode::Zen | PlayMode::Tournament => None,
+      _ => Some(format!("[{}]", self.label())),
+    }
+  }
+}
+
+/// Twists that can be put on top of any mode from the modes menu.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Mutators {
+  /// The playfield is drawn mirrored left to right, the controls and the HUD stay as they are.
+  pub mirrored: bool,
+}
+
+impl Mutators {
+  pub fn label(self) -> String {
+    format!("MIRROR {}", if self.mirrored { "ON" } else { "OFF" })
+  }
+}
+
+/// Share of the brick slots filled in wave `number`.
+pub fn wave_density(number: u32) -> f32 {
+  (WAVE_DENSITY + WAVE_DENSITY_STEP * number as f32).min(1.)
+}
+
+/// The mini-paddles of a bonus round, two of them sweeping across the space between the bricks and the paddle in
+/// opposite directions.
+pub fn bonus_round_movers() -> Vec<MoverPath> {
+  vec![
+    MoverPath {
+      points: vec![(0.1, 0.45), (0.9, 0.45)],
+      speed: BONUS_MOVER_SPEED,
+      width: BONUS_MOVER_WIDTH,
+    },
+    MoverPath {
+      points: vec![(0.9, 0.3), (0.5, 0.38), (0.1, 0.3)],
+      speed: BONUS_MOVER_SPEED,
+      width: BONUS_MOVER_WIDTH,
+    },
+  ]
+}
+
+/// A random layout for endless modes, later waves fill more of the grid.
+pub fn wave(number: u32) -> Level {
+  wave_from(number, &mut rand::thread_rng())
+}
+
+/// The layout of wave `number` that `seed` always produces, so several players can get the same one.
+pub fn seeded_wave(number: u32, seed: u64) -> Level {
+  wave_from(number, &mut StdRng::seed_from_u64(seed))
+}
+
+fn wave_from(number: u32, rng: &mut impl Rng) -> Level {
+  let density = wave_density(number);
+  let mut rows = (0..WAVE_ROWS)
+    .map(|_| {
+      (0..WAVE_COLUMNS)
+        .map(|_| match rng.gen_range(0., 1.) {
+          roll if roll >= density => BRICK_CHARS[0],
+          _ if rng.gen_range(0., 1.) < WAVE_SPLIT_CHANCE => SPLIT_BRICK,
+          _ => BRICK_CHARS[1],
+        })
+        .collect::<String>()
+    })
+    .collect::<Vec<_>>();
+  if rows.iter().all(|row| row.chars().all(|brick| brick == BRICK_CHARS[0])) {
+    let mut middle = BRICK_CHARS[0].to_string().repeat(WAVE_COLUMNS);
+    middle.replace_range(WAVE_COLUMNS / 2..=WAVE_COLUMNS / 2, &BRICK_CHARS[1].to_string());
+    rows[0] = middle;
+  }
+  Level {
+    name: format!("WAVE {}", number + 1),
+    rows,
+    ..Default::default()
+  }
+}
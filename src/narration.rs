@@ -0,0 +1,96 @@
+use crate::settings::Settings;
+use amethyst::{
+  derive::SystemDesc,
+  ecs::prelude::{Read, System, SystemData, Write},
+};
+use std::sync::mpsc::Sender;
+
+///
+/// types
+///
+
+/// Texts that should be spoken, `NarrationSystem` drains it every frame. Menus push the item they select, the game
+/// pushes the events of its event log.
+#[derive(Default)]
+pub struct NarrationQueue(pub Vec<String>);
+
+///
+/// systems
+///
+
+/// Forwards texts to a background thread that owns the platform speech synthesizer, a new text interrupts the one
+/// being spoken. Without the `narration` feature or when no synthesizer is available the queue is simply drained.
+/// Runs in the base dispatcher so the menus are narrated too.
+#[derive(SystemDesc)]
+pub struct NarrationSystem {
+  sender: Option<Sender<String>>,
+}
+
+impl Default for NarrationSystem {
+  fn default() -> Self {
+    NarrationSystem {
+      sender: backend::spawn(),
+    }
+  }
+}
+
+impl<'a> System<'a> for NarrationSystem {
+  type SystemData = (Write<'a, NarrationQueue>, Read<'a, Settings>);
+
+  fn run(&mut self, (mut queue, settings): Self::SystemData) {
+    for text in queue.0.drain(..) {
+      if !settings.narration {
+        continue;
+      }
+      if let Some(sender) = &self.sender {
+        if sender.send(text).is_err() {
+          self.sender = None;
+          break;
+        }
+      }
+    }
+  }
+}
+
+#[cfg(feature = "narration")]
+mod backend {
+  use log::warn;
+  use std::sync::mpsc::{channel, Sender};
+  use std::thread;
+  use tts::Tts;
+
+  pub fn spawn() -> Option<Sender<String>> {
+    let (sender, receiver) = channel::<String>();
+    let spawned = thread::Builder::new().name("narration".to_string()).spawn(move || {
+      let mut tts = match Tts::default() {
+        Ok(tts) => tts,
+        Err(e) => {
+          warn!("Narration is unavailable: {}", e);
+          return;
+        }
+      };
+      for text in receiver {
+        if let Err(e) = tts.speak(text.to_lowercase(), true) {
+          warn!("Couldn't speak \"{}\": {}", text, e);
+        }
+      }
+    });
+
+    match spawned {
+      Ok(_) => Some(sender),
+      Err(e) => {
+        warn!("Couldn't start the narration thread: {}", e);
+        None
+      }
+    }
+  }
+}
+
+#[cfg(not(feature = "narration"))]
+mod backend {
+  use std::sync::mpsc::Sender;
+
+  pub fn spawn() -> Option<Sender<String>> {
+    None
+  }
+}
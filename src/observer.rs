@@ -0,0 +1,168 @@
+use amethyst::{
+  derive::SystemDesc,
+  ecs::prelude::{System, SystemData, Write},
+};
+use serde::Serialize;
+use std::sync::mpsc::Sender;
+
+///
+/// types
+///
+
+/// What the observer stream reports, written as one JSON object per line with the kind in `event`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ObserverEvent {
+  LevelStarted {
+    level: String,
+    mode: &'static str,
+  },
+  Score {
+    points: u32,
+  },
+  BricksDestroyed {
+    count: usize,
+    left: usize,
+  },
+  /// An event of the event log, like a bomb or a power-up.
+  Game {
+    message: String,
+  },
+  Paused,
+  Resumed,
+  LevelCleared {
+    level: String,
+    score: u32,
+  },
+  RunOver {
+    reason: String,
+    score: u32,
+  },
+  LevelLeft,
+}
+
+/// Events for the observer stream, `ObserverSystem` drains it every frame.
+#[derive(Default)]
+pub struct ObserverQueue(pub Vec<ObserverEvent>);
+
+///
+/// systems
+///
+
+/// Streams the queued events to every tool connected to the observer port on localhost. The connections are
+/// served by background threads, without the `observer` feature or a port in the settings the queue is simply
+/// drained. Runs in the base dispatcher so pausing is reported too.
+#[derive(SystemDesc)]
+pub struct ObserverSystem {
+  sender: Option<Sender<String>>,
+}
+
+impl ObserverSystem {
+  pub fn new(port: Option<u16>) -> Self {
+    ObserverSystem {
+      sender: port.and_then(backend::spawn),
+    }
+  }
+}
+
+impl<'a> System<'a> for ObserverSystem {
+  type SystemData = Write<'a, ObserverQueue>;
+
+  fn run(&mut self, mut queue: Self::SystemData) {
+    for event in queue.0.drain(..) {
+      if let Some(sender) = &self.sender {
+        if sender.send(backend::encode(&event)).is_err() {
+          self.sender = None;
+          break;
+        }
+      }
+    }
+  }
+}
+
+#[cfg(feature = "observer")]
+mod backend {
+  use super::ObserverEvent;
+  use log::{info, warn};
+  use std::io::Write;
+  use std::net::{Ipv4Addr, TcpListener, TcpStream};
+  use std::sync::mpsc::{channel, Sender};
+  use std::sync::{Arc, Mutex};
+  use std::thread;
+
+  pub fn encode(event: &ObserverEvent) -> String {
+    serde_json::to_string(event).unwrap_or_default()
+  }
+
+  /// Accepts connections on one thread and writes every line to all of them on another, connections that fail a
+  /// write are dropped.
+  pub fn spawn(port: u16) -> Option<Sender<String>> {
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+      Ok(listener) => listener,
+      Err(e) => {
+        warn!("Couldn't open the observer port {}: {}", port, e);
+        return None;
+      }
+    };
+    let clients = Arc::new(Mutex::new(Vec::<TcpStream>::new()));
+
+    let accepted = Arc::clone(&clients);
+    let spawned = thread::Builder::new()
+      .name("observer_accept".to_string())
+      .spawn(move || {
+        for stream in listener.incoming() {
+          match stream {
+            Ok(stream) => {
+              info!("Observer connected from {:?}", stream.peer_addr());
+              if let Ok(mut clients) = accepted.lock() {
+                clients.push(stream);
+              }
+            }
+            Err(e) => warn!("Couldn't accept an observer: {}", e),
+          }
+        }
+      });
+    if let Err(e) = spawned {
+      warn!("Couldn't start the observer thread: {}", e);
+      return None;
+    }
+
+    let (sender, receiver) = channel::<String>();
+    let spawned = thread::Builder::new().name("observer".to_string()).spawn(move || {
+      for line in receiver {
+        if let Ok(mut clients) = clients.lock() {
+          clients.retain(|mut client| writeln!(client, "{}", line).is_ok());
+        }
+      }
+    });
+    match spawned {
+      Ok(_) => {
+        info!("Streaming game events on localhost:{}", port);
+        Some(sender)
+      }
+      Err(e) => {
+        warn!("Couldn't start the observer thread: {}", e);
+        None
+      }
+    }
+  }
+}
+
+#[cfg(not(feature = "observer"))]
+mod backend {
+  use super::ObserverEvent;
+  use log::warn;
+  use std::sync::mpsc::Sender;
+
+  pub fn encode(_event: &ObserverEvent) -> String {
+    String::new()
+  }
+
+  pub fn spawn(port: u16) -> Option<Sender<String>> {
+    warn!(
+      "Can't stream game events on port {}, the game was built without the observer feature",
+      port
+    );
+    None
+  }
+}
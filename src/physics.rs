@@ -0,0 +1,282 @@
+use crate::config::Material;
+use crate::radial::Placement;
+use amethyst::core::math::Vector3;
+use amethyst::core::Transform;
+
+///
+/// constants
+///
+
+/// How far the paddle can run up its arc to either side in the radial mode, in radians from the bottom.
+const PADDLE_ARC: f32 = 0.9;
+pub const BALL_MAX_VELOCITY: f32 = 260.;
+/// Dead surfaces don't slow the ball down below this.
+pub const BALL_MIN_VELOCITY: f32 = 100.;
+
+///
+/// types
+///
+
+/// Where a ball touches a paddle or a brick, see `box_contact`.
+pub struct BoxContact {
+  /// Position of the ball along the width of the box, from its center.
+  pub local_x: f32,
+  /// Unit vector along the width of the box, which is tilted on the arc of the radial mode.
+  pub width_axis: Vector3<f32>,
+  /// How far back along its direction the ball crossed into the box, the deeper the earlier it hit.
+  pub depth: f32,
+  /// Unit vector pointing away from the corner that was hit, `None` when the ball hit a face.
+  pub corner: Option<Vector3<f32>>,
+  /// Unit vector along the face or corner that was hit.
+  pub along: Vector3<f32>,
+  /// Where the ball is put to be just outside of that face.
+  pub push: (f32, f32),
+}
+
+///
+/// functions
+///
+
+pub fn point_in_rect(x: f32, y: f32, left: f32, bottom: f32, right: f32, top: f32) -> bool {
+  x >= left && x <= right && y >= bottom && y <= top
+}
+
+/// How a ball of `radius` at `ball` moving along `direction` touches a paddle or brick of `width` by `height` placed
+/// by `transform`, `None` if it doesn't.
+pub fn box_contact(
+  ball: &Vector3<f32>,
+  direction: &Vector3<f32>,
+  radius: f32,
+  transform: &Transform,
+  width: f32,
+  height: f32,
+) -> Option<BoxContact> {
+  // Bricks of a bonus round are rotated, the ball is tested in the frame of the box.
+  let (sin, cos) = (-transform.rotation().euler_angles().2).sin_cos();
+  let offset = ball - transform.translation();
+  let local_x = offset.x * cos - offset.y * sin;
+  let local_y = offset.x * sin + offset.y * cos;
+  let (half_width, half_height) = (width * 0.5 + radius, height * 0.5 + radius);
+  if !point_in_rect(local_x, local_y, -half_width, -half_height, half_width, half_height) {
+    return None;
+  }
+  // Past both edges the ball can only touch the corner, which is round from the outside. Otherwise the face that was
+  // hit is the one the ball is deepest behind, relative to the size of the box. The ball is put just outside of it.
+  let (beyond_x, beyond_y) = (local_x.abs() - width * 0.5, local_y.abs() - height * 0.5);
+  let (corner, along, (out_x, out_y)) = if beyond_x > 0. && beyond_y > 0. {
+    let distance = (beyond_x * beyond_x + beyond_y * beyond_y).sqrt();
+    if distance > radius {
+      return None;
+    }
+    let (normal_x, normal_y) = (
+      beyond_x.copysign(local_x) / distance,
+      beyond_y.copysign(local_y) / distance,
+    );
+    let normal = Vector3::new(normal_x * cos + normal_y * sin, -normal_x * sin + normal_y * cos, 0.);
+    (
+      Some(normal),
+      Vector3::new(-normal.y, normal.x, 0.),
+      (
+        (width * 0.5).copysign(local_x) + normal_x * radius,
+        (height * 0.5).copysign(local_y) + normal_y * radius,
+      ),
+    )
+  } else if local_y.abs() / half_height >= local_x.abs() / half_width {
+    (
+      None,
+      Vector3::new(cos, -sin, 0.),
+      (local_x, half_height.copysign(local_y)),
+    )
+  } else {
+    (
+      None,
+      Vector3::new(sin, cos, 0.),
+      (half_width.copysign(local_x), local_y),
+    )
+  };
+  // Swept back along `direction`, the ball leaves the box through the face it came in by.
+  let back = |position: f32, half: f32, speed: f32| {
+    if speed > 0. {
+      (position + half) / speed
+    } else if speed < 0. {
+      (position - half) / speed
+    } else {
+      f32::INFINITY
+    }
+  };
+  let (speed_x, speed_y) = (
+    direction.x * cos - direction.y * sin,
+    direction.x * sin + direction.y * cos,
+  );
+  let depth = back(local_x, half_width, speed_x).min(back(local_y, half_height, speed_y));
+  Some(BoxContact {
+    local_x,
+    depth,
+    width_axis: Vector3::new(cos, -sin, 0.),
+    corner,
+    along,
+    push: (
+      transform.translation().x + out_x * cos + out_y * sin,
+      transform.translation().y - out_x * sin + out_y * cos,
+    ),
+  })
+}
+
+/// Turns a ball moving along `direction` back from a corner, `normal` is the unit vector pointing away from it. The
+/// ball is reflected about the normal, so it goes back on both axes when it comes in diagonally, and keeps going when
+/// it only grazes the corner on its way out.
+pub fn reflect_off_corner(direction: &Vector3<f32>, normal: &Vector3<f32>) -> Vector3<f32> {
+  let towards = direction.dot(normal);
+  if towards < 0. {
+    direction - normal * 2. * towards
+  } else {
+    *direction
+  }
+}
+
+/// Whether two circles `offset` apart from center to center overlap.
+pub fn circles_overlap(offset: &Vector3<f32>, radius: f32, other_radius: f32) -> bool {
+  offset.x * offset.x + offset.y * offset.y < (radius + other_radius) * (radius + other_radius)
+}
+
+/// Velocities of two circles of equal weight after they hit each other, `normal` is the unit vector from the first
+/// to the second. They swap their speeds along the normal, circles that are already moving apart keep going and give
+/// `None`.
+pub fn collide_circles(
+  velocity: &Vector3<f32>,
+  other_velocity: &Vector3<f32>,
+  normal: &Vector3<f32>,
+) -> Option<(Vector3<f32>, Vector3<f32>)> {
+  let closing = (velocity - other_velocity).dot(normal);
+  if closing <= 0. {
+    return None;
+  }
+  Some((velocity - normal * closing, other_velocity + normal * closing))
+}
+
+/// Where a falling ball at `x`, `y` reaches `target_y`, bouncing off the side walls of a playfield `width` wide on
+/// the way. Rising balls hit bricks first, so there is no prediction for them.
+pub fn predict_intercept(
+  x: f32,
+  y: f32,
+  direction: &Vector3<f32>,
+  radius: f32,
+  target_y: f32,
+  width: f32,
+) -> Option<f32> {
+  if direction.y >= 0. || y < target_y {
+    return None;
+  }
+  let travel_x = (target_y - y) / direction.y * direction.x;
+  let span = width - 2. * radius;
+  // Unfold the reflections: the ball moves back and forth between `radius` and `width - radius`.
+  let offset = (x - radius + travel_x).rem_euclid(2. * span);
+  Some(radius + if offset > span { 2. * span - offset } else { offset })
+}
+
+/// Applies `material` to a ball that was just turned away from a surface, `along` is a unit vector along the
+/// surface.
+pub fn bounce(direction: &mut Vector3<f32>, speed: &mut f32, material: &Material, along: Vector3<f32>) {
+  let tangent = direction.dot(&along);
+  let velocity = (*direction - along * tangent * material.damping) * *speed * material.restitution;
+  set_velocity(direction, speed, velocity);
+}
+
+/// Sets the `direction` and `speed` of a ball from `velocity`, keeping the speed within the limits of the ball.
+pub fn set_velocity(direction: &mut Vector3<f32>, speed: &mut f32, velocity: Vector3<f32>) {
+  let norm = velocity.norm();
+  if norm > 0. {
+    *direction = velocity / norm;
+  }
+  *speed = norm.max(BALL_MIN_VELOCITY).min(BALL_MAX_VELOCITY);
+}
+
+/// Half the length of the arc the paddle of the radial mode runs along.
+pub fn track_half_length(radius: f32) -> f32 {
+  radius * PADDLE_ARC
+}
+
+/// The paddle `track` units along an arc of `radius` below `center`, `0.` is the bottom of the arc. The paddle is
+/// turned to stay tangent to the arc.
+pub fn arc_placement(center: (f32, f32), radius: f32, track: f32) -> Placement {
+  let angle = track / radius;
+  (center.0 + radius * angle.sin(), center.1 - radius * angle.cos(), angle)
+}
+
+/// Direction a ball leaves the paddle in: away from the center of the paddle along `offset`, pushed by `spin` along
+/// `width_axis`, the unit vector along the paddle. On the arc of the radial mode the paddle is tilted, so the spin
+/// follows the paddle instead of the x axis.
+pub fn paddle_deflection(offset: &Vector3<f32>, width_axis: &Vector3<f32>, spin: f32) -> Vector3<f32> {
+  (offset.normalize() + width_axis * spin).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const CORNERS: [(f32, f32); 4] = [(1., 1.), (-1., 1.), (-1., -1.), (1., -1.)];
+
+  fn assert_close(actual: &Vector3<f32>, expected: &Vector3<f32>) {
+    assert!((actual - expected).norm() < 1e-5, "{:?} != {:?}", actual, expected);
+  }
+
+  /// Contact of a ball of radius `2.` just past the corner of a `20.` by `10.` box at the origin, on the side of
+  /// `x`, `y`.
+  fn corner_contact(x: f32, y: f32) -> BoxContact {
+    let ball = Vector3::new(x * 11., y * 6., 0.);
+    let direction = Vector3::new(-x, -y, 0.).normalize();
+    box_contact(&ball, &direction, 2., &Transform::default(), 20., 10.).expect("the ball touches the corner")
+  }
+
+  #[test]
+  fn corner_normals_point_away_from_each_corner() {
+    for &(x, y) in &CORNERS {
+      let normal = corner_contact(x, y).corner.expect("a corner was hit");
+      assert_close(&normal, &Vector3::new(x, y, 0.).normalize());
+    }
+  }
+
+  #[test]
+  fn corner_along_is_perpendicular_to_the_normal() {
+    for &(x, y) in &CORNERS {
+      let contact = corner_contact(x, y);
+      assert!(contact.along.dot(&contact.corner.unwrap()).abs() < 1e-5);
+    }
+  }
+
+  #[test]
+  fn diagonal_approach_reverses_both_axes() {
+    for &(x, y) in &CORNERS {
+      let normal = corner_contact(x, y).corner.unwrap();
+      let direction = Vector3::new(-x, -y, 0.).normalize();
+      assert_close(&reflect_off_corner(&direction, &normal), &-direction);
+    }
+  }
+
+  #[test]
+  fn grazing_approach_keeps_the_direction() {
+    for &(x, y) in &CORNERS {
+      let normal = corner_contact(x, y).corner.unwrap();
+      // Along the corner and away from it, `towards` is `0.` and above.
+      for direction in &[Vector3::new(-y, x, 0.).normalize(), Vector3::new(x, y, 0.).normalize()] {
+        assert!(direction.dot(&normal) >= 0.);
+        assert_close(&reflect_off_corner(direction, &normal), direction);
+      }
+    }
+  }
+
+  #[test]
+  fn face_hits_have_no_corner() {
+    let contact = box_contact(
+      &Vector3::new(3., 6., 0.),
+      &Vector3::new(0., -1., 0.),
+      2.,
+      &Transform::default(),
+      20.,
+      10.,
+    )
+    .expect("the ball touches the top face");
+    assert!(contact.corner.is_none());
+    assert_close(&contact.along, &Vector3::x());
+  }
+}
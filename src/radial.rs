@@ -0,0 +1,51 @@
+use crate::modes;
+use rand::Rng;
+use std::f32::consts::PI;
+
+///
+/// constants
+///
+
+const RING_RADII: [f32; 3] = [30., 50., 70.];
+const RING_GAP: f32 = 4.;
+
+///
+/// types
+///
+
+/// Where and how tilted something sits on the playfield: `x`, `y` and the rotation in radians.
+pub type Placement = (f32, f32, f32);
+
+///
+/// functions
+///
+
+/// Bricks of `brick_width` in rings around `center`, turned along their ring. Later waves fill more of the rings
+/// and there is always at least one brick.
+pub fn rings(center: (f32, f32), brick_width: f32, number: u32) -> Vec<Placement> {
+  let mut rng = rand::thread_rng();
+  let density = modes::wave_density(number);
+  let slots = RING_RADII
+    .iter()
+    .flat_map(|&radius| {
+      let count = (2. * PI * radius / (brick_width + RING_GAP)).floor().max(1.) as usize;
+      (0..count).map(move |slot| {
+        let angle = 2. * PI * slot as f32 / count as f32;
+        (
+          center.0 + radius * angle.cos(),
+          center.1 + radius * angle.sin(),
+          angle + PI / 2.,
+        )
+      })
+    })
+    .collect::<Vec<_>>();
+  let mut bricks = slots
+    .iter()
+    .copied()
+    .filter(|_| rng.gen_range(0., 1.) < density)
+    .collect::<Vec<_>>();
+  if bricks.is_empty() {
+    bricks.push(slots[rng.gen_range(0, slots.len())]);
+  }
+  bricks
+}
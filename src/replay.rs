@@ -0,0 +1,225 @@
+use crate::radial::Placement;
+use crate::save::{self, Versioned};
+use crate::snapshot::WorldSnapshot;
+use amethyst::ecs::Entity;
+use amethyst::utils::application_root_dir;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+///
+/// constants
+///
+
+const REPLAYS_DIR: &str = "replays";
+/// The oldest replays are deleted beyond this many.
+const REPLAY_LIMIT: usize = 20;
+pub const FRAMES_PER_SECOND: f32 = 30.;
+/// Every this many frames the world is stored even if only the paddles and balls moved, which brings back what the
+/// frames in between don't hold, like tints.
+const KEYFRAME_INTERVAL: usize = 150;
+/// Recording stops after 30 minutes, a level that takes longer isn't worth watching again.
+const MAX_FRAMES: usize = 30 * 60 * 30;
+/// Playback speeds, a replay starts at `1.`.
+pub const SPEEDS: [f32; 5] = [0.25, 0.5, 1., 2., 4.];
+pub const NORMAL_SPEED: usize = 2;
+
+///
+/// types
+///
+
+/// The playfield of a replay at one point in time.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ReplayFrame {
+  /// The world as the gameplay snapshot captures it, only stored in keyframes. The frames in between show the
+  /// keyframe before them with the entities in `moved` put where they were.
+  pub keyframe: Option<WorldSnapshot>,
+  /// Paddles, balls and mini-paddles by their index in the entities of the keyframe, stored in every frame.
+  pub moved: Vec<(usize, Placement)>,
+  /// Angle the bricks of a bonus round are swung to, they are turned by the difference to the keyframe.
+  pub field_angle: f32,
+}
+
+/// What a frame of a replay is recorded from, see `ReplayRecorder::tick`.
+pub struct FrameCapture {
+  /// Every brick with the hits it has left and everything in `moving`. The frame becomes a keyframe when this
+  /// changes, bricks that are only swung around in a bonus round don't make one.
+  pub cast: Vec<(Entity, u32)>,
+  /// Entities that move on their own, with where they are.
+  pub moving: Vec<(Entity, Placement)>,
+  pub field_angle: f32,
+}
+
+/// A played level recorded as world snapshots, like a suspended level. A frame becomes a keyframe whenever something
+/// comes, goes or takes a hit and at least every `KEYFRAME_INTERVAL` frames.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Replay {
+  pub level: String,
+  pub score: u32,
+  /// Seconds since the unix epoch.
+  pub recorded_at: u64,
+  pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+  fn dir() -> Option<PathBuf> {
+    application_root_dir().ok().map(|app_root| app_root.join(REPLAYS_DIR))
+  }
+
+  /// Every saved replay, newest first, sorted by their file names. Replays are large, so they are only loaded when
+  /// watched.
+  pub fn paths() -> Vec<PathBuf> {
+    let entries = match Replay::dir().and_then(|dir| fs::read_dir(dir).ok()) {
+      Some(entries) => entries,
+      None => return vec![],
+    };
+    let mut paths = entries
+      .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+      .filter(|path| path.extension().map_or(false, |ext| ext == "ron"))
+      .collect::<Vec<_>>();
+    paths.sort();
+    paths.reverse();
+    paths
+  }
+
+  pub fn load(path: &Path) -> Option<Replay> {
+    save::load(path)
+  }
+
+  /// Writes the replay as `<recorded_at>_<n>.ron` and deletes the oldest ones beyond `REPLAY_LIMIT`.
+  pub fn save(&self) {
+    let dir = match Replay::dir() {
+      Some(dir) => dir,
+      None => return,
+    };
+    if let Err(e) = fs::create_dir_all(&dir) {
+      warn!("Couldn't create {}: {}", dir.display(), e);
+      return;
+    }
+    let path = (1..)
+      .map(|n| dir.join(format!("{:012}_{}.ron", self.recorded_at, n)))
+      .find(|path| !path.exists())
+      .expect("Ran out of replay file names!");
+    if let Err(e) = save::store(&path, self) {
+      warn!("Couldn't save replay {}: {}", path.display(), e);
+      return;
+    }
+    for old in Replay::paths().iter().skip(REPLAY_LIMIT) {
+      if let Err(e) = fs::remove_file(old) {
+        warn!("Couldn't remove replay {}: {}", old.display(), e);
+      }
+    }
+  }
+
+  /// Length in seconds.
+  pub fn duration(&self) -> f32 {
+    self.frames.len().saturating_sub(1) as f32 / FRAMES_PER_SECOND
+  }
+
+  /// The frame shown `seconds` into the replay.
+  pub fn frame_at(&self, seconds: f32) -> usize {
+    ((seconds.max(0.) * FRAMES_PER_SECOND) as usize).min(self.frames.len().saturating_sub(1))
+  }
+
+  /// The keyframe `frame` is rebuilt from, with its index.
+  pub fn keyframe_at(&self, frame: usize) -> Option<(usize, &WorldSnapshot)> {
+    self
+      .frames
+      .get(..=frame)?
+      .iter()
+      .enumerate()
+      .rev()
+      .find_map(|(index, frame)| frame.keyframe.as_ref().map(|keyframe| (index, keyframe)))
+  }
+}
+
+impl Versioned for Replay {
+  /// Frames were rectangles before version 2, there is no world to rebuild from them so those replays can't be
+  /// watched anymore.
+  const VERSION: u32 = 2;
+}
+
+/// Captures the playfield `FRAMES_PER_SECOND` times a second while a level is played.
+#[derive(Default)]
+pub struct ReplayRecorder {
+  frames: Vec<ReplayFrame>,
+  elapsed: f32,
+  /// The cast of the last keyframe and its entities in the order of its snapshot.
+  cast: Vec<(Entity, u32)>,
+  keyframe_entities: Vec<Entity>,
+  /// Frames since the last keyframe.
+  since_keyframe: usize,
+}
+
+impl ReplayRecorder {
+  /// Advances the recording by `delta_seconds`. `capture` is only called when a frame is due and `snapshot` only
+  /// when it is a keyframe, it returns the entities of the snapshot in its order.
+  pub fn tick(
+    &mut self,
+    delta_seconds: f32,
+    capture: impl FnOnce() -> FrameCapture,
+    snapshot: impl FnOnce() -> (WorldSnapshot, Vec<Entity>),
+  ) {
+    if self.frames.len() >= MAX_FRAMES {
+      return;
+    }
+    let interval = 1. / FRAMES_PER_SECOND;
+    self.elapsed += delta_seconds;
+    if !self.frames.is_empty() && self.elapsed < interval {
+      return;
+    }
+    // A slow frame is recorded once and not repeated, the replay runs a little faster there instead of stuttering.
+    self.elapsed %= interval;
+
+    let capture = capture();
+    let keyframe = self.frames.is_empty() || self.since_keyframe + 1 >= KEYFRAME_INTERVAL || capture.cast != self.cast;
+    let keyframe = if keyframe {
+      let (snapshot, entities) = snapshot();
+      self.since_keyframe = 0;
+      self.cast = capture.cast;
+      self.keyframe_entities = entities;
+      Some(snapshot)
+    } else {
+      self.since_keyframe += 1;
+      None
+    };
+    let keyframe_entities = &self.keyframe_entities;
+    let moved = capture
+      .moving
+      .into_iter()
+      .filter_map(|(entity, placement)| {
+        let index = keyframe_entities.iter().position(|&other| other == entity);
+        index.map(|index| (index, placement))
+      })
+      .collect();
+    self.frames.push(ReplayFrame {
+      keyframe,
+      moved,
+      field_angle: capture.field_angle,
+    });
+  }
+
+  /// Whether `finish` would save everything played so far.
+  pub fn is_complete(&self) -> bool {
+    self.frames.len() >= FRAMES_PER_SECOND as usize && self.frames.len() < MAX_FRAMES
+  }
+
+  /// Saves what was recorded under `level`, nothing if it was too short to be worth watching.
+  pub fn finish(&mut self, level: &str, score: u32) {
+    let frames = std::mem::replace(self, ReplayRecorder::default()).frames;
+    if frames.len() < FRAMES_PER_SECOND as usize {
+      return;
+    }
+    Replay {
+      level: level.to_string(),
+      score,
+      recorded_at: SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs()),
+      frames,
+    }
+    .save();
+  }
+}
@@ -0,0 +1,128 @@
+use amethyst::utils::application_root_dir;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+///
+/// constants
+///
+
+/// Written by the logger next to the config directory, the bundle takes a copy.
+pub const LOG_PATH: &str = "breakout.log";
+const REPORTS_DIR: &str = "reports";
+/// Relative to the application root, the bindings of the active control scheme are added on top.
+const CONFIG_FILES: [&str; 3] = ["config/settings.ron", "config/game.ron", "config/display.ron"];
+
+///
+/// types
+///
+
+/// What was going on when the report was made, written as `diagnostics.ron` into the bundle.
+#[derive(Debug, Serialize)]
+pub struct Diagnostics {
+  pub version: &'static str,
+  pub os: &'static str,
+  pub arch: &'static str,
+  pub mode: String,
+  pub level: String,
+  /// The waves of the endless modes are random, only a tournament plays a seeded one.
+  pub tournament_seed: Option<u64>,
+  pub score: u32,
+  /// `LevelStats` of the level being played.
+  pub stats: String,
+}
+
+///
+/// functions
+///
+
+/// Zips the diagnostics, the log and the config files into `reports/report_<time>.zip`. Files that don't exist are
+/// left out.
+pub fn write_bundle(diagnostics: &Diagnostics, controls: &Path) -> Result<PathBuf, String> {
+  let app_root = application_root_dir().map_err(|e| e.to_string())?;
+  let dir = app_root.join(REPORTS_DIR);
+  fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+  let saved_at = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |duration| duration.as_secs());
+  let path = dir.join(format!("report_{}.zip", saved_at));
+
+  let mut zip = ZipWriter::new(File::create(&path).map_err(|e| e.to_string())?);
+  let options = FileOptions::default();
+  let contents =
+    ron::ser::to_string_pretty(diagnostics, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+  zip.start_file("diagnostics.ron", options).map_err(|e| e.to_string())?;
+  zip.write_all(contents.as_bytes()).map_err(|e| e.to_string())?;
+
+  let files = CONFIG_FILES
+    .iter()
+    .map(|file| app_root.join(file))
+    .chain(vec![app_root.join(LOG_PATH), controls.to_path_buf()]);
+  for file in files {
+    let contents = match fs::read(&file) {
+      Ok(contents) => contents,
+      Err(_) => continue,
+    };
+    let name = file
+      .strip_prefix(&app_root)
+      .unwrap_or(&file)
+      .to_string_lossy()
+      .replace('\\', "/");
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    zip.write_all(&contents).map_err(|e| e.to_string())?;
+  }
+  zip.finish().map_err(|e| e.to_string())?;
+  Ok(path)
+}
+
+/// Posts the bundle at `path` to `url` on a background thread. Needs the `report` feature, the outcome is only
+/// logged.
+pub fn submit(path: PathBuf, url: String) {
+  remote::submit(path, url);
+}
+
+#[cfg(feature = "report")]
+mod remote {
+  use log::{info, warn};
+  use std::fs;
+  use std::path::PathBuf;
+  use std::thread;
+  use std::time::Duration;
+
+  pub fn submit(path: PathBuf, url: String) {
+    let spawned = thread::Builder::new().name("report".to_string()).spawn(move || {
+      let result = fs::read(&path).map_err(|e| e.to_string()).and_then(|bundle| {
+        ureq::post(&url)
+          .timeout(Duration::from_secs(30))
+          .set("Content-Type", "application/zip")
+          .send_bytes(&bundle)
+          .map_err(|e| e.to_string())
+      });
+      match result {
+        Ok(_) => info!("Sent {} to {}", path.display(), url),
+        Err(e) => warn!("Couldn't send {} to {}: {}", path.display(), url, e),
+      }
+    });
+    if let Err(e) = spawned {
+      warn!("Couldn't start the report thread: {}", e);
+    }
+  }
+}
+
+#[cfg(not(feature = "report"))]
+mod remote {
+  use log::warn;
+  use std::path::PathBuf;
+
+  pub fn submit(path: PathBuf, url: String) {
+    warn!(
+      "Can't send {} to {}, the game was built without the report feature",
+      path.display(),
+      url
+    );
+  }
+}
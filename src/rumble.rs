@@ -0,0 +1,156 @@
+use crate::settings::Settings;
+use amethyst::{
+  derive::SystemDesc,
+  ecs::prelude::{Read, System, SystemData, Write},
+};
+use std::sync::mpsc::Sender;
+
+///
+/// types
+///
+
+#[derive(Copy, Clone, Debug)]
+pub enum RumbleEvent {
+  /// Strength between `0.` and `1.`, how far off center the ball hit the paddle.
+  PaddleHit(f32),
+  BrickDestroyed,
+}
+
+impl RumbleEvent {
+  fn pulse(self) -> Pulse {
+    match self {
+      RumbleEvent::PaddleHit(strength) => Pulse {
+        strength: 0.2 + 0.4 * strength.min(1.).max(0.),
+        duration_ms: 60,
+      },
+      RumbleEvent::BrickDestroyed => Pulse {
+        strength: 0.7,
+        duration_ms: 120,
+      },
+    }
+  }
+}
+
+#[cfg_attr(not(feature = "rumble"), allow(dead_code))]
+#[derive(Copy, Clone, Debug)]
+struct Pulse {
+  strength: f32,
+  duration_ms: u32,
+}
+
+/// Systems push the events that should be felt on the gamepad here, `RumbleSystem` drains it every frame.
+#[derive(Default)]
+pub struct RumbleQueue(pub Vec<RumbleEvent>);
+
+///
+/// systems
+///
+
+/// Forwards rumble pulses to a background thread that owns the gamepads. Without the `rumble` feature or when no
+/// gamepad backend is available the queue is simply drained.
+#[derive(SystemDesc)]
+pub struct RumbleSystem {
+  sender: Option<Sender<Pulse>>,
+}
+
+impl Default for RumbleSystem {
+  fn default() -> Self {
+    RumbleSystem {
+      sender: backend::spawn(),
+    }
+  }
+}
+
+impl<'a> System<'a> for RumbleSystem {
+  type SystemData = (Write<'a, RumbleQueue>, Read<'a, Settings>);
+
+  fn run(&mut self, (mut queue, settings): Self::SystemData) {
+    for event in queue.0.drain(..) {
+      if !settings.rumble {
+        continue;
+      }
+      if let Some(sender) = &self.sender {
+        if sender.send(event.pulse()).is_err() {
+          self.sender = None;
+          break;
+        }
+      }
+    }
+  }
+}
+
+#[cfg(feature = "rumble")]
+mod backend {
+  use super::Pulse;
+  use gilrs::ff::{BaseEffect, BaseEffectType, EffectBuilder, Replay, Ticks};
+  use gilrs::{GamepadId, Gilrs};
+  use log::warn;
+  use std::sync::mpsc::{channel, Sender};
+  use std::thread;
+  use std::time::Duration;
+
+  pub fn spawn() -> Option<Sender<Pulse>> {
+    let (sender, receiver) = channel::<Pulse>();
+    let spawned = thread::Builder::new().name("rumble".to_string()).spawn(move || {
+      let mut gilrs = match Gilrs::new() {
+        Ok(gilrs) => gilrs,
+        Err(e) => {
+          warn!("Gamepad rumble is unavailable: {}", e);
+          return;
+        }
+      };
+      let mut active: Option<GamepadId> = None;
+      let mut _current_effect = None;
+
+      loop {
+        while let Some(event) = gilrs.next_event() {
+          active = Some(event.id);
+        }
+        let pulse = match receiver.recv_timeout(Duration::from_millis(50)) {
+          Ok(pulse) => pulse,
+          Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
+          Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return,
+        };
+        let id = match active.filter(|&id| gilrs.gamepad(id).is_ff_supported()) {
+          Some(id) => id,
+          None => continue,
+        };
+        let effect = EffectBuilder::new()
+          .add_effect(BaseEffect {
+            kind: BaseEffectType::Strong {
+              magnitude: (pulse.strength * f32::from(u16::max_value())) as u16,
+            },
+            scheduling: Replay {
+              play_for: Ticks::from_ms(pulse.duration_ms),
+              ..Default::default()
+            },
+            ..Default::default()
+          })
+          .gamepads(&[id])
+          .finish(&mut gilrs);
+        match effect.and_then(|effect| effect.play().map(|_| effect)) {
+          Ok(effect) => _current_effect = Some(effect),
+          Err(e) => warn!("Couldn't play rumble effect: {}", e),
+        }
+      }
+    });
+
+    match spawned {
+      Ok(_) => Some(sender),
+      Err(e) => {
+        warn!("Couldn't start the rumble thread: {}", e);
+        None
+      }
+    }
+  }
+}
+
+#[cfg(not(feature = "rumble"))]
+mod backend {
+  use super::Pulse;
+  use std::sync::mpsc::Sender;
+
+  pub fn spawn() -> Option<Sender<Pulse>> {
+    None
+  }
+}
@@ -0,0 +1,117 @@
+use log::{info, warn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+///
+/// types
+///
+
+/// A persisted format. Files are written as `(version: .., data: ..)`, so a changed format can upgrade files written
+/// by older versions of the game instead of discarding them.
+pub trait Versioned: Serialize + DeserializeOwned {
+  /// Written into new files. Bump it whenever the format changes and teach `migrate` the previous version.
+  const VERSION: u32;
+
+  /// Reads `contents` written with an older `version`. Files from before versioning are version 0 and hold the data
+  /// without the header.
+  fn migrate(version: u32, contents: &str) -> Result<Self, String> {
+    match version {
+      0 => ron::de::from_str(contents).map_err(|e| e.to_string()),
+      _ => Err(format!("no migration from version {}", version)),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct Header {
+  version: u32,
+  /// Seconds since the unix epoch, files written before syncing existed have none.
+  #[serde(default)]
+  saved_at: u64,
+}
+
+#[derive(Deserialize)]
+struct SaveFile<T> {
+  data: T,
+}
+
+#[derive(Serialize)]
+struct SaveFileRef<'a, T> {
+  version: u32,
+  saved_at: u64,
+  data: &'a T,
+}
+
+/// Reads a save file and upgrades it to the current version. A file that can't be read is copied to `<file>.bak`
+/// before `None` is returned, so saving the defaults over it doesn't lose it.
+pub fn load<T: Versioned>(path: &Path) -> Option<T> {
+  let contents = fs::read_to_string(path).ok()?;
+  let version = ron::de::from_str::<Header>(&contents).map_or(0, |header| header.version);
+  let result = if version == T::VERSION {
+    data(&contents)
+  } else if version > T::VERSION {
+    Err(format!("written by a newer version ({})", version))
+  } else {
+    T::migrate(version, &contents).map(|data| {
+      info!("Upgraded {} from version {} to {}", path.display(), version, T::VERSION);
+      data
+    })
+  };
+
+  match result {
+    Ok(data) => Some(data),
+    Err(e) => {
+      let mut backup = path.as_os_str().to_owned();
+      backup.push(".bak");
+      warn!(
+        "Couldn't read {}, using the defaults and keeping a copy in {}: {}",
+        path.display(),
+        Path::new(&backup).display(),
+        e
+      );
+      if let Err(e) = fs::copy(path, &backup) {
+        warn!("Couldn't back up {}: {}", path.display(), e);
+      }
+      None
+    }
+  }
+}
+
+/// The data of a file with a version header, for migrations that read an older type.
+pub fn data<T: DeserializeOwned>(contents: &str) -> Result<T, String> {
+  ron::de::from_str::<SaveFile<T>>(contents)
+    .map(|file| file.data)
+    .map_err(|e| e.to_string())
+}
+
+/// Writes `data` with the current version header.
+pub fn store<T: Versioned>(path: &Path, data: &T) -> Result<(), String> {
+  let file = SaveFileRef {
+    version: T::VERSION,
+    saved_at: SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |duration| duration.as_secs()),
+    data,
+  };
+  ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+    .map_err(|e| e.to_string())
+    .and_then(|contents| write_atomic(path, &contents).map_err(|e| e.to_string()))
+}
+
+/// When the contents of a save file were written, `0` if that is unknown.
+pub fn saved_at(contents: &str) -> u64 {
+  ron::de::from_str::<Header>(contents).map_or(0, |header| header.saved_at)
+}
+
+/// Writes `contents` next to `path` first and renames it into place afterwards, so a crash while saving leaves
+/// the previous file intact instead of a half written one.
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+  let mut temp = path.as_os_str().to_owned();
+  temp.push(".tmp");
+  fs::write(&temp, contents)?;
+  fs::rename(&temp, path)
+}
@@ -0,0 +1,179 @@
+use crate::controls::ControlScheme;
+use crate::save::{self, Versioned};
+use crate::sync::SyncConfig;
+use amethyst::core::frame_limiter::FrameRateLimitStrategy;
+use amethyst::utils::application_root_dir;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+///
+/// constants
+///
+
+const SETTINGS_PATH: &str = "config/settings.ron";
+/// The frame limiter sleeps until this long before the next frame is due and yields the rest, sleeping all the way
+/// overshoots on most schedulers.
+const FRAME_LIMIT_YIELD: Duration = Duration::from_millis(2);
+/// Used with vsync on and no cap, see `Settings::vsync`.
+const VSYNC_FPS: u32 = 60;
+/// Choices of `Settings::auto_serve` after off, in seconds.
+const AUTO_SERVE_SECONDS: [f32; 3] = [1., 3., 5.];
+
+///
+/// enums
+///
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum FrameCap {
+  Fps60,
+  Fps120,
+  Unlimited,
+}
+
+impl FrameCap {
+  const ALL: [FrameCap; 3] = [FrameCap::Fps60, FrameCap::Fps120, FrameCap::Unlimited];
+
+  pub fn next(self) -> Self {
+    let index = FrameCap::ALL.iter().position(|&cap| cap == self).unwrap_or(0);
+    FrameCap::ALL[(index + 1) % FrameCap::ALL.len()]
+  }
+
+  pub fn label(self) -> &'static str {
+    match self {
+      FrameCap::Fps60 => "60 fps",
+      FrameCap::Fps120 => "120 fps",
+      FrameCap::Unlimited => "unlimited",
+    }
+  }
+
+  fn fps(self) -> Option<u32> {
+    match self {
+      FrameCap::Fps60 => Some(60),
+      FrameCap::Fps120 => Some(120),
+      FrameCap::Unlimited => None,
+    }
+  }
+}
+
+///
+/// types
+///
+
+/// Player facing options, loaded once at startup and written back whenever a menu changes them.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+  pub rumble: bool,
+  /// Name of the announcer pack, `None` keeps the announcer silent.
+  pub announcer: Option<String>,
+  /// Shows a short caption for gameplay sounds, on the side of the screen they come from.
+  pub captions: bool,
+  /// Lists notable game events like bombs, power-ups and lost lives in a corner of the hud.
+  pub event_log: bool,
+  /// Speaks the selected menu item and the events of the event log, needs the `narration` feature.
+  pub narration: bool,
+  /// Seconds the input lags behind the picture and sound, measured by the calibration screen. Widens the input
+  /// buffer and the close call window.
+  pub input_latency: f32,
+  /// For public showings: a soft zoom pulse replaces the hit-stop and tournament players get numbered names instead
+  /// of typed ones.
+  pub streamer_mode: bool,
+  pub controls: ControlScheme,
+  /// Marks where the falling ball will cross the paddle line.
+  pub assist: bool,
+  /// Freezes the game for a moment when a bomb goes off or the last brick breaks.
+  pub hit_stop: bool,
+  /// Turns off effects that jolt the screen, like the hit-stop.
+  pub reduced_motion: bool,
+  /// Light sprites around the ball and explosions, can be turned off on slow machines.
+  pub glow: bool,
+  /// The renderer doesn't let the present mode be chosen, so with vsync on an uncapped game is limited to 60 fps
+  /// instead. Turning it off lets `frame_cap` alone decide.
+  pub vsync: bool,
+  pub frame_cap: FrameCap,
+  /// Remote the save files are synced with on start and exit.
+  pub sync: Option<SyncConfig>,
+  /// Diagnostic bundles are posted here once written, needs the `report` feature.
+  pub report_url: Option<String>,
+  /// Answers with the version of the latest release, asked on start. Unset keeps the game from checking, needs the
+  /// `update_check` feature.
+  pub update_check_url: Option<String>,
+  /// Local port that streams the game events as JSON lines, for stream overlays and other tools. Unset keeps it
+  /// closed, needs the `observer` feature.
+  pub observer_port: Option<u16>,
+  /// Seconds the ball sits on the paddle before it is served by itself at the last serve angle of the level. Unset
+  /// waits for the player.
+  pub auto_serve: Option<f32>,
+  /// Lets the balls of multi-ball bounce off each other instead of passing through.
+  pub chaos: bool,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Settings {
+      rumble: true,
+      announcer: None,
+      captions: false,
+      event_log: false,
+      narration: false,
+      input_latency: 0.,
+      streamer_mode: false,
+      controls: ControlScheme::default(),
+      assist: false,
+      hit_stop: true,
+      reduced_motion: false,
+      glow: true,
+      vsync: true,
+      frame_cap: FrameCap::Fps60,
+      sync: None,
+      report_url: None,
+      update_check_url: None,
+      observer_port: None,
+      auto_serve: None,
+      chaos: false,
+    }
+  }
+}
+
+impl Settings {
+  /// The auto serve delay after the current one, off comes after the longest.
+  pub fn next_auto_serve(&self) -> Option<f32> {
+    match self.auto_serve {
+      None => AUTO_SERVE_SECONDS.first().copied(),
+      Some(seconds) => AUTO_SERVE_SECONDS.iter().copied().find(|&choice| choice > seconds),
+    }
+  }
+
+  pub fn path() -> Option<PathBuf> {
+    application_root_dir().ok().map(|app_root| app_root.join(SETTINGS_PATH))
+  }
+
+  pub fn load() -> Self {
+    Settings::path().and_then(|path| save::load(&path)).unwrap_or_default()
+  }
+
+  /// Arguments for `FrameLimiter::set_rate` and `ApplicationBuilder::with_frame_limit`.
+  pub fn frame_limit(&self) -> (FrameRateLimitStrategy, u32) {
+    let fps = self.frame_cap.fps().or_else(|| Some(VSYNC_FPS).filter(|_| self.vsync));
+    match fps {
+      Some(fps) => (FrameRateLimitStrategy::SleepAndYield(FRAME_LIMIT_YIELD), fps),
+      None => (FrameRateLimitStrategy::Unlimited, 0),
+    }
+  }
+
+  pub fn save(&self) {
+    let path = match Settings::path() {
+      Some(path) => path,
+      None => return,
+    };
+    if let Err(e) = save::store(&path, self) {
+      warn!("Couldn't save {}: {}", SETTINGS_PATH, e);
+    }
+  }
+}
+
+impl Versioned for Settings {
+  const VERSION: u32 = 1;
+}
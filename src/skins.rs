@@ -0,0 +1,127 @@
+use crate::campaign::{Campaign, Progress};
+
+///
+/// constants
+///
+
+pub const PADDLE_SKINS: [Skin; 5] = [
+  Skin {
+    name: "Blue",
+    sprite: 1,
+    tint: [1., 1., 1.],
+    unlock: SkinUnlock::Always,
+  },
+  Skin {
+    name: "Green",
+    sprite: 3,
+    tint: [1., 1., 1.],
+    unlock: SkinUnlock::LevelsCleared(3),
+  },
+  Skin {
+    name: "Red",
+    sprite: 4,
+    tint: [1., 1., 1.],
+    unlock: SkinUnlock::LevelsCleared(6),
+  },
+  Skin {
+    name: "Purple",
+    sprite: 5,
+    tint: [1., 1., 1.],
+    unlock: SkinUnlock::CampaignCleared,
+  },
+  Skin {
+    name: "Gold",
+    sprite: 1,
+    tint: [1., 0.8, 0.2],
+    unlock: SkinUnlock::NewGamePlusLevelsCleared(3),
+  },
+];
+
+pub const BALL_SKINS: [Skin; 5] = [
+  Skin {
+    name: "Blue",
+    sprite: 2,
+    tint: [1., 1., 1.],
+    unlock: SkinUnlock::Always,
+  },
+  Skin {
+    name: "Green",
+    sprite: 6,
+    tint: [1., 1., 1.],
+    unlock: SkinUnlock::LevelsCleared(2),
+  },
+  Skin {
+    name: "Red",
+    sprite: 7,
+    tint: [1., 1., 1.],
+    unlock: SkinUnlock::LevelsCleared(5),
+  },
+  Skin {
+    name: "Purple",
+    sprite: 8,
+    tint: [1., 1., 1.],
+    unlock: SkinUnlock::CampaignCleared,
+  },
+  Skin {
+    name: "Ghost",
+    sprite: 2,
+    tint: [0.6, 0.6, 0.6],
+    unlock: SkinUnlock::NewGamePlusLevelsCleared(6),
+  },
+];
+
+///
+/// enums
+///
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SkinUnlock {
+  Always,
+  /// At least this many campaign levels have to be cleared.
+  LevelsCleared(usize),
+  /// Every campaign world has to be cleared, which also unlocks New Game+.
+  CampaignCleared,
+  /// At least this many New Game+ levels have to be cleared.
+  NewGamePlusLevelsCleared(usize),
+}
+
+///
+/// types
+///
+
+/// A cosmetic look for the paddle or the ball, `sprite` indexes `textures/breakout.ron`.
+#[derive(Copy, Clone, Debug)]
+pub struct Skin {
+  pub name: &'static str,
+  pub sprite: usize,
+  pub tint: [f32; 3],
+  pub unlock: SkinUnlock,
+}
+
+impl SkinUnlock {
+  pub fn is_unlocked(self, campaign: &Campaign, progress: &Progress) -> bool {
+    match self {
+      SkinUnlock::Always => true,
+      SkinUnlock::LevelsCleared(count) => campaign.cleared_count(&progress.cleared) >= count,
+      SkinUnlock::CampaignCleared => progress.new_game_plus,
+      SkinUnlock::NewGamePlusLevelsCleared(count) => campaign.cleared_count(&progress.cleared_plus) >= count,
+    }
+  }
+
+  pub fn describe(self) -> String {
+    match self {
+      SkinUnlock::Always => String::new(),
+      SkinUnlock::LevelsCleared(count) => format!("clear {} campaign levels", count),
+      SkinUnlock::CampaignCleared => "clear the campaign".to_string(),
+      SkinUnlock::NewGamePlusLevelsCleared(count) => format!("clear {} new game+ levels", count),
+    }
+  }
+}
+
+/// Index of the skin named `name`, unknown or locked skins fall back to the first one.
+pub fn selected(skins: &[Skin], name: &str, campaign: &Campaign, progress: &Progress) -> usize {
+  skins
+    .iter()
+    .position(|skin| skin.name == name && skin.unlock.is_unlocked(campaign, progress))
+    .unwrap_or(0)
+}
@@ -0,0 +1,398 @@
+use crate::campaign::CampaignLevel;
+use crate::level::{Level, ARMORED_BRICK, DOOR, PLAIN_BRICK, SPLIT_BRICK, SWITCH_BRICK};
+use crate::save::{self, Versioned};
+use amethyst::{
+  core::math::{Quaternion, UnitQuaternion, Vector3},
+  core::Transform,
+  ecs::prelude::{Builder, Entity, Join, World, WorldExt},
+  ecs::Component,
+  renderer::{palette::Srgba, resources::Tint},
+  shred::Resource,
+  utils::application_root_dir,
+};
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+///
+/// constants
+///
+
+const SUSPENDED_PATH: &str = "config/suspended.ron";
+
+///
+/// types
+///
+
+/// The registered components and resources of a world, each serialized on its own under the name it was
+/// registered with. Components of a type that isn't registered anymore are skipped on restore.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct WorldSnapshot {
+  pub entities: Vec<BTreeMap<String, String>>,
+  pub resources: BTreeMap<String, String>,
+}
+
+/// Reads every entity of the world that has the component, with the serialized component.
+pub type CaptureFn = fn(&World) -> Vec<(Entity, String)>;
+/// Adds the serialized component to the entity.
+pub type RestoreFn = fn(&mut World, Entity, &str) -> Result<(), String>;
+
+struct ComponentEntry {
+  name: &'static str,
+  /// Entities are only captured if they have at least one key component, the others are captured along.
+  key: bool,
+  capture: CaptureFn,
+  restore: RestoreFn,
+}
+
+struct ResourceEntry {
+  name: &'static str,
+  capture: fn(&World) -> Option<String>,
+  restore: fn(&mut World, &str) -> Result<(), String>,
+}
+
+/// Knows which components and resources make up the state of a game and how to capture and restore them. Types
+/// are registered once with their name, everything that needs the state of the world goes through the plugin.
+#[derive(Default)]
+pub struct SnapshotPlugin {
+  components: Vec<ComponentEntry>,
+  resources: Vec<ResourceEntry>,
+}
+
+impl SnapshotPlugin {
+  /// Registers a component that makes an entity part of the snapshot.
+  pub fn with_component<T: Component + Serialize + DeserializeOwned>(mut self, name: &'static str) -> Self {
+    self.components.push(ComponentEntry {
+      name,
+      key: true,
+      capture: capture_component::<T>,
+      restore: restore_component::<T>,
+    });
+    self
+  }
+
+  /// Registers a component that is only captured on entities that are part of the snapshot, like a marker that
+  /// also shows up on entities that aren't.
+  pub fn with_attachment<T: Component + Serialize + DeserializeOwned>(mut self, name: &'static str) -> Self {
+    self.components.push(ComponentEntry {
+      name,
+      key: false,
+      capture: capture_component::<T>,
+      restore: restore_component::<T>,
+    });
+    self
+  }
+
+  /// Registers an attachment that can't be serialized as it is, like a sprite that refers to a loaded asset.
+  pub fn with_attachment_fns(mut self, name: &'static str, capture: CaptureFn, restore: RestoreFn) -> Self {
+    self.components.push(ComponentEntry {
+      name,
+      key: false,
+      capture,
+      restore,
+    });
+    self
+  }
+
+  pub fn with_resource<T: Resource + Serialize + DeserializeOwned>(mut self, name: &'static str) -> Self {
+    self.resources.push(ResourceEntry {
+      name,
+      capture: capture_resource::<T>,
+      restore: restore_resource::<T>,
+    });
+    self
+  }
+
+  pub fn capture(&self, world: &World) -> WorldSnapshot {
+    self.capture_entities(world).0
+  }
+
+  /// Captures the world like `capture`, with the entities of the snapshot in the order of its `entities`.
+  pub fn capture_entities(&self, world: &World) -> (WorldSnapshot, Vec<Entity>) {
+    let mut entities = BTreeMap::<Entity, BTreeMap<String, String>>::new();
+    for entry in self.components.iter().filter(|entry| entry.key) {
+      for (entity, contents) in (entry.capture)(world) {
+        entities
+          .entry(entity)
+          .or_default()
+          .insert(entry.name.to_string(), contents);
+      }
+    }
+    for entry in self.components.iter().filter(|entry| !entry.key) {
+      for (entity, contents) in (entry.capture)(world) {
+        if let Some(components) = entities.get_mut(&entity) {
+          components.insert(entry.name.to_string(), contents);
+        }
+      }
+    }
+    let resources = self
+      .resources
+      .iter()
+      .filter_map(|entry| (entry.capture)(world).map(|contents| (entry.name.to_string(), contents)))
+      .collect();
+    let (order, entities) = entities.into_iter().unzip();
+    (WorldSnapshot { entities, resources }, order)
+  }
+
+  /// Creates the entities of `snapshot` and replaces the resources with its copies. The entities already in the
+  /// world are left alone, the caller clears what the snapshot replaces.
+  pub fn restore(&self, world: &mut World, snapshot: &WorldSnapshot) -> Result<(), String> {
+    self.restore_entities(world, snapshot)?;
+    for entry in &self.resources {
+      if let Some(contents) = snapshot.resources.get(entry.name) {
+        (entry.restore)(world, contents).map_err(|e| format!("{}: {}", entry.name, e))?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Creates the entities of `snapshot` and leaves the resources alone, like a replay that only shows the playfield.
+  /// Returns the entities in the order of its `entities`, none are left behind when one can't be restored.
+  pub fn restore_entities(&self, world: &mut World, snapshot: &WorldSnapshot) -> Result<Vec<Entity>, String> {
+    let mut created = Vec::with_capacity(snapshot.entities.len());
+    for components in &snapshot.entities {
+      let entity = world.create_entity().build();
+      created.push(entity);
+      if let Err(e) = self.restore_components(world, entity, components) {
+        world
+          .delete_entities(&created)
+          .expect("Failed to remove a partly restored snapshot!");
+        return Err(e);
+      }
+    }
+    Ok(created)
+  }
+
+  fn restore_components(
+    &self,
+    world: &mut World,
+    entity: Entity,
+    components: &BTreeMap<String, String>,
+  ) -> Result<(), String> {
+    for entry in &self.components {
+      if let Some(contents) = components.get(entry.name) {
+        (entry.restore)(world, entity, contents).map_err(|e| format!("{}: {}", entry.name, e))?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// `Transform` as it is written into a snapshot.
+#[derive(Deserialize, Serialize)]
+struct TransformValues {
+  translation: [f32; 3],
+  /// Quaternion as `[i, j, k, w]`.
+  rotation: [f32; 4],
+  scale: [f32; 3],
+}
+
+/// A brick as it was written before bricks had their own component, a paddle without a player and its points.
+#[derive(Deserialize)]
+struct LegacyBrick {
+  width: f32,
+  height: f32,
+}
+
+#[derive(Deserialize)]
+struct LegacyPoints(u32);
+
+/// A brick as it is written into a snapshot.
+#[derive(Serialize)]
+struct BrickValues {
+  width: f32,
+  height: f32,
+  tier: char,
+  hp: u32,
+  score: u32,
+}
+
+/// A level left through the pause menu, the next start of the game offers to continue it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Suspended {
+  pub level: Level,
+  pub campaign: Option<CampaignLevel>,
+  pub new_game_plus: bool,
+  pub snapshot: WorldSnapshot,
+}
+
+impl Suspended {
+  fn path() -> Option<PathBuf> {
+    application_root_dir()
+      .ok()
+      .map(|app_root| app_root.join(SUSPENDED_PATH))
+  }
+
+  pub fn exists() -> bool {
+    Suspended::path().map_or(false, |path| path.exists())
+  }
+
+  /// Reads the suspended level and removes it, a level can only be continued once.
+  pub fn take() -> Option<Suspended> {
+    let path = Suspended::path()?;
+    let suspended = save::load(&path);
+    if let Err(e) = fs::remove_file(&path) {
+      warn!("Couldn't remove {}: {}", path.display(), e);
+    }
+    suspended
+  }
+
+  pub fn save(&self) {
+    let path = match Suspended::path() {
+      Some(path) => path,
+      None => return,
+    };
+    if let Err(e) = save::store(&path, self) {
+      warn!("Couldn't save {}: {}", SUSPENDED_PATH, e);
+    }
+  }
+}
+
+impl Versioned for Suspended {
+  const VERSION: u32 = 2;
+
+  /// Bricks were paddles without a player before version 2, they are turned into bricks that break in one hit.
+  fn migrate(version: u32, contents: &str) -> Result<Self, String> {
+    let mut suspended = match version {
+      0 => ron::de::from_str::<Suspended>(contents).map_err(|e| e.to_string())?,
+      1 => save::data::<Suspended>(contents)?,
+      _ => return Err(format!("no migration from version {}", version)),
+    };
+    for components in &mut suspended.snapshot.entities {
+      if components.contains_key("player") {
+        continue;
+      }
+      let paddle = match components.remove("paddle") {
+        Some(paddle) => ron::de::from_str::<LegacyBrick>(&paddle).map_err(|e| e.to_string())?,
+        None => continue,
+      };
+      let score = match components.remove("points") {
+        Some(points) => ron::de::from_str::<LegacyPoints>(&points).map_err(|e| e.to_string())?.0,
+        None => 0,
+      };
+      let tier = [
+        ("split_brick", SPLIT_BRICK),
+        ("armored", ARMORED_BRICK),
+        ("door", DOOR),
+        ("switch", SWITCH_BRICK),
+      ]
+      .iter()
+      .find(|(name, _)| components.contains_key(*name))
+      .map_or(PLAIN_BRICK, |&(_, tier)| tier);
+      let brick = BrickValues {
+        width: paddle.width,
+        height: paddle.height,
+        tier,
+        hp: 1,
+        score,
+      };
+      let brick = ron::ser::to_string(&brick).map_err(|e| e.to_string())?;
+      components.insert("brick".to_string(), brick);
+    }
+    Ok(suspended)
+  }
+}
+
+///
+/// functions
+///
+
+fn capture_component<T: Component + Serialize>(world: &World) -> Vec<(Entity, String)> {
+  let storage = world.read_storage::<T>();
+  (&world.entities(), &storage)
+    .join()
+    .filter_map(|(entity, component)| match ron::ser::to_string(component) {
+      Ok(contents) => Some((entity, contents)),
+      Err(e) => {
+        warn!("Couldn't capture a component of {:?}: {}", entity, e);
+        None
+      }
+    })
+    .collect()
+}
+
+fn restore_component<T: Component + DeserializeOwned>(
+  world: &mut World,
+  entity: Entity,
+  contents: &str,
+) -> Result<(), String> {
+  let component = ron::de::from_str::<T>(contents).map_err(|e| e.to_string())?;
+  world
+    .write_storage::<T>()
+    .insert(entity, component)
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+fn capture_resource<T: Resource + Serialize>(world: &World) -> Option<String> {
+  let resource = world.try_fetch::<T>()?;
+  ron::ser::to_string(&*resource)
+    .map_err(|e| warn!("Couldn't capture a resource: {}", e))
+    .ok()
+}
+
+fn restore_resource<T: Resource + DeserializeOwned>(world: &mut World, contents: &str) -> Result<(), String> {
+  let resource = ron::de::from_str::<T>(contents).map_err(|e| e.to_string())?;
+  world.insert(resource);
+  Ok(())
+}
+
+/// Captures `Transform` as `TransformValues`, so snapshots don't depend on how amethyst serializes it.
+pub fn capture_transforms(world: &World) -> Vec<(Entity, String)> {
+  let transforms = world.read_storage::<Transform>();
+  (&world.entities(), &transforms)
+    .join()
+    .filter_map(|(entity, transform)| {
+      let translation = transform.translation();
+      let rotation = transform.rotation().quaternion().coords;
+      let scale = transform.scale();
+      let values = TransformValues {
+        translation: [translation.x, translation.y, translation.z],
+        rotation: [rotation.x, rotation.y, rotation.z, rotation.w],
+        scale: [scale.x, scale.y, scale.z],
+      };
+      ron::ser::to_string(&values).ok().map(|contents| (entity, contents))
+    })
+    .collect()
+}
+
+pub fn restore_transform(world: &mut World, entity: Entity, contents: &str) -> Result<(), String> {
+  let values = ron::de::from_str::<TransformValues>(contents).map_err(|e| e.to_string())?;
+  let [x, y, z] = values.translation;
+  let [i, j, k, w] = values.rotation;
+  let [scale_x, scale_y, scale_z] = values.scale;
+  let mut transform = Transform::default();
+  transform.set_translation_xyz(x, y, z);
+  transform.set_rotation(UnitQuaternion::from_quaternion(Quaternion::new(w, i, j, k)));
+  transform.set_scale(Vector3::new(scale_x, scale_y, scale_z));
+  world
+    .write_storage::<Transform>()
+    .insert(entity, transform)
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+pub fn capture_tints(world: &World) -> Vec<(Entity, String)> {
+  let tints = world.read_storage::<Tint>();
+  (&world.entities(), &tints)
+    .join()
+    .filter_map(|(entity, tint)| {
+      let (r, g, b, a) = tint.0.into_components();
+      ron::ser::to_string(&[r, g, b, a])
+        .ok()
+        .map(|contents| (entity, contents))
+    })
+    .collect()
+}
+
+pub fn restore_tint(world: &mut World, entity: Entity, contents: &str) -> Result<(), String> {
+  let [r, g, b, a] = ron::de::from_str::<[f32; 4]>(contents).map_err(|e| e.to_string())?;
+  world
+    .write_storage::<Tint>()
+    .insert(entity, Tint(Srgba::new(r, g, b, a)))
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,113 @@
+use crate::embedded;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+///
+/// constants
+///
+
+const SOUND_MAPPING_PATH: &str = "assets/sounds/sounds.ron";
+
+///
+/// types
+///
+
+/// Sound events of the game, `assets/sounds/sounds.ron` decides which wav file each of them plays.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum SoundType {
+  PaddleHit,
+  Confirm,
+  Pause,
+  WallHit,
+  /// The ball was saved by the very edge of the paddle.
+  CloseCall,
+  /// A destroyed brick, by tier. Tiers without a sound of their own use the next lower tier.
+  BrickHit(u8),
+  /// A brick that takes more than one hit survived one.
+  BrickDamaged,
+  /// A bonus brick was hit in time.
+  PowerUp,
+  /// Chain lightning arcing from a brick to its neighbours.
+  Lightning,
+  /// The ball was kicked away by a bumper.
+  Bumper,
+  /// Two balls bounced off each other, with the chaos setting on.
+  BallHit,
+  /// The last switch brick of a link was destroyed and its doors opened.
+  DoorOpened,
+  /// The run is over, played by the game over screen.
+  GameOver,
+  /// Looped quietly in zen mode. No file is shipped for it, it stays silent until the mapping names one.
+  Ambient,
+}
+
+/// Gameplay sounds played this frame together with the horizontal position of their source, if they have one.
+/// The captions are driven by these.
+#[derive(Default)]
+pub struct SoundEvents(pub Vec<(SoundType, Option<f32>)>);
+
+impl SoundType {
+  /// Text shown for the sound when captions are enabled, menu sounds have none.
+  pub fn caption(self) -> Option<&'static str> {
+    match self {
+      SoundType::PaddleHit => Some("PADDLE HIT"),
+      SoundType::WallHit => Some("WALL HIT"),
+      SoundType::CloseCall => Some("CLOSE CALL"),
+      SoundType::BrickHit(_) => Some("BRICK BREAK"),
+      SoundType::BrickDamaged => Some("BRICK CRACKS"),
+      SoundType::PowerUp => Some("POWER-UP"),
+      SoundType::Lightning => Some("LIGHTNING"),
+      SoundType::Bumper => Some("BUMPER"),
+      SoundType::BallHit => Some("BALLS CLASH"),
+      SoundType::DoorOpened => Some("DOOR OPENS"),
+      SoundType::Confirm | SoundType::Pause | SoundType::GameOver | SoundType::Ambient => None,
+    }
+  }
+
+  /// The key a sound is stored under, brick tiers without a mapping fall back to lower tiers.
+  pub fn lookup<T>(self, sounds: &HashMap<SoundType, T>) -> Option<&T> {
+    match self {
+      SoundType::BrickHit(tier) => (0..=tier).rev().find_map(|tier| sounds.get(&SoundType::BrickHit(tier))),
+      _ => sounds.get(&self),
+    }
+  }
+}
+
+/// Paths relative to the assets directory, entries of the mapping file replace these.
+fn default_mapping() -> HashMap<SoundType, String> {
+  vec![
+    (SoundType::PaddleHit, "sounds/paddle_hit.wav"),
+    (SoundType::Confirm, "sounds/confirm.wav"),
+    (SoundType::Pause, "sounds/pause.wav"),
+    (SoundType::WallHit, "sounds/wall_hit.wav"),
+    (SoundType::CloseCall, "sounds/select.wav"),
+    (SoundType::BrickHit(0), "sounds/brick-hit-2.wav"),
+    (SoundType::BrickDamaged, "sounds/wall_hit.wav"),
+    (SoundType::PowerUp, "sounds/select.wav"),
+    (SoundType::Lightning, "sounds/confirm.wav"),
+    (SoundType::Bumper, "sounds/paddle_hit.wav"),
+    (SoundType::BallHit, "sounds/paddle_hit.wav"),
+    (SoundType::DoorOpened, "sounds/pause.wav"),
+    (SoundType::GameOver, "sounds/wall_hit.wav"),
+  ]
+  .into_iter()
+  .map(|(sound_type, path)| (sound_type, path.to_string()))
+  .collect()
+}
+
+pub fn load_mapping() -> HashMap<SoundType, String> {
+  let mut mapping = default_mapping();
+  let overrides: HashMap<SoundType, String> = embedded::read(SOUND_MAPPING_PATH)
+    .ok()
+    .and_then(|bytes| match ron::de::from_bytes(&bytes) {
+      Ok(overrides) => Some(overrides),
+      Err(e) => {
+        warn!("Couldn't parse {}, using the default sounds: {}", SOUND_MAPPING_PATH, e);
+        None
+      }
+    })
+    .unwrap_or_default();
+  mapping.extend(overrides);
+  mapping
+}
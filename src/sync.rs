@@ -0,0 +1,193 @@
+use crate::save;
+use crate::settings::Settings;
+use amethyst::utils::application_root_dir;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+///
+/// constants
+///
+
+/// Save files relative to the application root, the same names are used on the remote. The settings stay on this
+/// machine, they hold the password of the remote and the display and controls of this setup.
+const SYNCED_FILES: [&str; 2] = ["config/progress.ron", "custom_levels/records.ron"];
+/// How long the game waits for a sync before it goes on without it.
+const SYNC_TIMEOUT_SECONDS: u64 = 5;
+/// How long a single request to the remote may take.
+const REQUEST_TIMEOUT_SECONDS: u64 = 2;
+
+///
+/// types
+///
+
+/// Where the save files are synced to, set in `config/settings.ron`. Needs the `sync` feature.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SyncConfig {
+  /// Base URL of a WebDAV share or any server that answers GET and PUT, the files are stored directly below it.
+  pub url: String,
+  #[serde(default)]
+  pub user: Option<String>,
+  #[serde(default)]
+  pub password: Option<String>,
+}
+
+/// A place save files can be read from and written to.
+pub trait SaveBackend {
+  /// Contents of `file`, `None` if it doesn't exist yet.
+  fn read(&self, file: &str) -> Result<Option<String>, String>;
+  fn write(&self, file: &str, contents: &str) -> Result<(), String>;
+}
+
+/// The save files on this machine.
+pub struct LocalBackend {
+  root: PathBuf,
+  /// Set when the game stopped waiting for the sync, a download that arrives later would race the game's own saves.
+  abandoned: Arc<AtomicBool>,
+}
+
+impl SaveBackend for LocalBackend {
+  fn read(&self, file: &str) -> Result<Option<String>, String> {
+    match fs::read_to_string(self.root.join(file)) {
+      Ok(contents) => Ok(Some(contents)),
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(e.to_string()),
+    }
+  }
+
+  fn write(&self, file: &str, contents: &str) -> Result<(), String> {
+    if self.abandoned.load(Ordering::SeqCst) {
+      return Err("the game stopped waiting for the sync".to_string());
+    }
+    let path = self.root.join(file);
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    save::write_atomic(&path, contents).map_err(|e| e.to_string())
+  }
+}
+
+/// Copies every save file to the side where it is missing or older, going by the time stamp `save::store` writes
+/// into it. Files saved at the same time are left alone.
+pub fn sync(local: &dyn SaveBackend, remote: &dyn SaveBackend) {
+  for &file in SYNCED_FILES.iter() {
+    let result = local.read(file).and_then(|local_contents| {
+      let remote_contents = remote.read(file)?;
+      let saved_at = |contents: &Option<String>| contents.as_ref().map(|contents| save::saved_at(contents));
+      match (saved_at(&local_contents), saved_at(&remote_contents)) {
+        (Some(local_at), remote_at) if remote_at.map_or(true, |remote_at| local_at > remote_at) => {
+          info!("Uploading {}", file);
+          remote.write(file, local_contents.as_deref().unwrap_or_default())
+        }
+        (local_at, Some(remote_at)) if local_at.map_or(true, |local_at| remote_at > local_at) => {
+          info!("Downloading {}", file);
+          local.write(file, remote_contents.as_deref().unwrap_or_default())
+        }
+        _ => Ok(()),
+      }
+    });
+    if let Err(e) = result {
+      warn!("Couldn't sync {}: {}", file, e);
+    }
+  }
+}
+
+/// Syncs with the remote from the settings, if one is configured. Called before the save files are loaded and after
+/// the game closed. The sync runs on its own thread, the game waits `SYNC_TIMEOUT_SECONDS` at most and leaves the
+/// local files alone once it goes on.
+pub fn sync_configured() {
+  let config = match Settings::load().sync {
+    Some(config) => config,
+    None => return,
+  };
+  let root = match application_root_dir() {
+    Ok(app_root) => app_root,
+    Err(_) => return,
+  };
+  let abandoned = Arc::new(AtomicBool::new(false));
+  let local = LocalBackend {
+    root,
+    abandoned: abandoned.clone(),
+  };
+  let (done, finished) = mpsc::channel();
+  thread::spawn(move || {
+    if let Some(remote) = remote::backend(&config) {
+      sync(&local, remote.as_ref());
+    }
+    // Nobody is listening anymore when the game went on without the sync.
+    let _ = done.send(());
+  });
+  let timeout = Duration::from_secs(SYNC_TIMEOUT_SECONDS);
+  if finished.recv_timeout(timeout).is_err() {
+    abandoned.store(true, Ordering::SeqCst);
+    warn!("Sync took longer than {}s, going on without it", SYNC_TIMEOUT_SECONDS);
+  }
+}
+
+#[cfg(feature = "sync")]
+mod remote {
+  use super::{SaveBackend, SyncConfig, REQUEST_TIMEOUT_SECONDS};
+  use std::time::Duration;
+
+  /// A WebDAV share or a plain HTTP server, files are fetched with GET and uploaded with PUT. Directories are
+  /// flattened into the file name so the server doesn't need to create collections.
+  struct HttpBackend {
+    config: SyncConfig,
+  }
+
+  impl HttpBackend {
+    fn request(&self, method: &str, file: &str) -> ureq::Request {
+      let url = format!("{}/{}", self.config.url.trim_end_matches('/'), file.replace('/', "_"));
+      let request = ureq::request(method, &url).timeout(Duration::from_secs(REQUEST_TIMEOUT_SECONDS));
+      match &self.config.user {
+        Some(user) => {
+          let password = self.config.password.as_deref().unwrap_or_default();
+          let credentials = base64::encode(format!("{}:{}", user, password));
+          request.set("Authorization", &format!("Basic {}", credentials))
+        }
+        None => request,
+      }
+    }
+  }
+
+  impl SaveBackend for HttpBackend {
+    fn read(&self, file: &str) -> Result<Option<String>, String> {
+      match self.request("GET", file).call() {
+        Ok(response) => response.into_string().map(Some).map_err(|e| e.to_string()),
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(e) => Err(e.to_string()),
+      }
+    }
+
+    fn write(&self, file: &str, contents: &str) -> Result<(), String> {
+      self
+        .request("PUT", file)
+        .send_string(contents)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+    }
+  }
+
+  pub fn backend(config: &SyncConfig) -> Option<Box<dyn SaveBackend>> {
+    Some(Box::new(HttpBackend { config: config.clone() }))
+  }
+}
+
+#[cfg(not(feature = "sync"))]
+mod remote {
+  use super::{SaveBackend, SyncConfig};
+  use log::warn;
+
+  pub fn backend(config: &SyncConfig) -> Option<Box<dyn SaveBackend>> {
+    warn!(
+      "Can't sync with {}, the game was built without the sync feature",
+      config.url
+    );
+    None
+  }
+}
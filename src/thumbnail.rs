@@ -0,0 +1,98 @@
+use crate::level::Level;
+use amethyst::utils::application_root_dir;
+use log::warn;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::BufWriter;
+
+///
+/// constants
+///
+
+/// Relative to the assets directory, so the loader finds the cached pictures like any other texture.
+const THUMBNAILS_DIR: &str = "thumbnails";
+/// Pixels of a brick in the thumbnail, the gap between bricks is one pixel.
+const CELL_WIDTH: u32 = 6;
+const CELL_HEIGHT: u32 = 3;
+/// Bumped when the look of the thumbnails changes, so the old ones aren't picked up anymore.
+const THUMBNAIL_VERSION: u32 = 1;
+
+///
+/// functions
+///
+
+/// Width and height in pixels of the thumbnail of `level`.
+pub fn dimensions(level: &Level) -> (u32, u32) {
+  let columns = level.columns() as u32;
+  let rows = level.rows.len() as u32;
+  (
+    (columns * (CELL_WIDTH + 1)).saturating_sub(1).max(1),
+    (rows * (CELL_HEIGHT + 1)).saturating_sub(1).max(1),
+  )
+}
+
+/// The asset path of the thumbnail of `level`, drawing its bricks in `color` first if it isn't cached yet.
+/// Thumbnails are named after a hash of the layout, so edited levels get a new one. `None` if it couldn't be written.
+pub fn path(level: &Level, color: impl Fn(char) -> [f32; 3]) -> Option<String> {
+  let mut hasher = Sha256::new();
+  hasher.update(THUMBNAIL_VERSION.to_le_bytes());
+  for row in &level.rows {
+    hasher.update(row.as_bytes());
+    hasher.update(b"\n");
+  }
+  let name = format!(
+    "{}/{}.png",
+    THUMBNAILS_DIR,
+    hasher
+      .finalize()
+      .iter()
+      .take(16)
+      .map(|byte| format!("{:02x}", byte))
+      .collect::<String>()
+  );
+
+  let file = application_root_dir().ok()?.join("assets").join(&name);
+  if file.exists() {
+    return Some(name);
+  }
+  if let Some(dir) = file.parent() {
+    if let Err(e) = fs::create_dir_all(dir) {
+      warn!("Couldn't create {}: {}", dir.display(), e);
+      return None;
+    }
+  }
+
+  let (width, height) = dimensions(level);
+  let mut pixels = vec![0u8; (width * height * 4) as usize];
+  for (x, y, brick) in level.bricks() {
+    let [r, g, b] = color(brick);
+    let rgba = [to_byte(r), to_byte(g), to_byte(b), 255];
+    for py in y as u32 * (CELL_HEIGHT + 1)..(y as u32 * (CELL_HEIGHT + 1) + CELL_HEIGHT).min(height) {
+      for px in x as u32 * (CELL_WIDTH + 1)..(x as u32 * (CELL_WIDTH + 1) + CELL_WIDTH).min(width) {
+        let at = ((py * width + px) * 4) as usize;
+        pixels[at..at + 4].copy_from_slice(&rgba);
+      }
+    }
+  }
+
+  let written = File::create(&file).map_err(|e| e.to_string()).and_then(|out| {
+    let mut encoder = png::Encoder::new(BufWriter::new(out), width, height);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+      .write_header()
+      .and_then(|mut writer| writer.write_image_data(&pixels))
+      .map_err(|e| e.to_string())
+  });
+  match written {
+    Ok(_) => Some(name),
+    Err(e) => {
+      warn!("Couldn't write thumbnail {}: {}", file.display(), e);
+      None
+    }
+  }
+}
+
+fn to_byte(channel: f32) -> u8 {
+  (channel.max(0.).min(1.) * 255.).round() as u8
+}
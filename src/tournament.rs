@@ -0,0 +1,119 @@
+use crate::level::Level;
+use crate::modes;
+use crate::save::{self, Versioned};
+use amethyst::utils::application_root_dir;
+use log::warn;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+///
+/// constants
+///
+
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 8;
+pub const MAX_NAME_LENGTH: usize = 10;
+const TOURNAMENT_PATH: &str = "config/tournament.ron";
+/// Wave of the endless modes every player gets, with its bricks placed from the seed of the tournament.
+const TOURNAMENT_WAVE: u32 = 3;
+
+///
+/// types
+///
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Entrant {
+  pub name: String,
+  /// Points of the finished turn, `None` until the player had their turn.
+  pub score: Option<u32>,
+}
+
+/// A local hot-seat tournament. Every player plays the same level in turn, the bracket is written after every
+/// turn so an interrupted tournament continues with the player whose turn it was.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Tournament {
+  pub entrants: Vec<Entrant>,
+  pub seed: u64,
+}
+
+impl Tournament {
+  pub fn new(names: &[String]) -> Self {
+    Tournament {
+      entrants: names
+        .iter()
+        .map(|name| Entrant {
+          name: name.clone(),
+          score: None,
+        })
+        .collect(),
+      seed: rand::thread_rng().gen(),
+    }
+  }
+
+  pub fn path() -> Option<PathBuf> {
+    application_root_dir()
+      .ok()
+      .map(|app_root| app_root.join(TOURNAMENT_PATH))
+  }
+
+  /// The tournament that is still running, if there is one.
+  pub fn load() -> Option<Self> {
+    Tournament::path().and_then(|path| save::load(&path))
+  }
+
+  pub fn save(&self) {
+    let path = match Tournament::path() {
+      Some(path) => path,
+      None => return,
+    };
+    if let Err(e) = save::store(&path, self) {
+      warn!("Couldn't save {}: {}", TOURNAMENT_PATH, e);
+    }
+  }
+
+  /// Removes the bracket once the tournament is over or abandoned.
+  pub fn clear() {
+    if let Some(path) = Tournament::path().filter(|path| path.exists()) {
+      if let Err(e) = fs::remove_file(&path) {
+        warn!("Couldn't remove {}: {}", TOURNAMENT_PATH, e);
+      }
+    }
+  }
+
+  /// The level every player plays.
+  pub fn level(&self) -> Level {
+    Level {
+      name: "TOURNAMENT".to_string(),
+      ..modes::seeded_wave(TOURNAMENT_WAVE, self.seed)
+    }
+  }
+
+  /// The player whose turn it is, `None` once everybody played.
+  pub fn current(&self) -> Option<&Entrant> {
+    self.entrants.iter().find(|entrant| entrant.score.is_none())
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.current().is_none()
+  }
+
+  /// Records the points of the current player.
+  pub fn record(&mut self, points: u32) {
+    if let Some(entrant) = self.entrants.iter_mut().find(|entrant| entrant.score.is_none()) {
+      entrant.score = Some(points);
+    }
+  }
+
+  /// The players by score, the ones still waiting for their turn come last in the order they were entered.
+  pub fn standings(&self) -> Vec<&Entrant> {
+    let mut standings = self.entrants.iter().collect::<Vec<_>>();
+    standings.sort_by(|a, b| b.score.cmp(&a.score));
+    standings
+  }
+}
+
+impl Versioned for Tournament {
+  const VERSION: u32 = 1;
+}
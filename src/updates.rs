@@ -0,0 +1,94 @@
+use std::sync::{Arc, Mutex};
+
+///
+/// types
+///
+
+/// The newer release found by the update check, filled in from a background thread once the request is answered.
+/// Stays empty if the check is off, failed or found nothing newer.
+#[derive(Clone, Default)]
+pub struct UpdateNotice(Arc<Mutex<Option<String>>>);
+
+impl UpdateNotice {
+  /// Asks `url` for the version of the latest release, if one is configured. The request runs in the background,
+  /// the notice is returned right away.
+  pub fn check(url: Option<&str>) -> Self {
+    let notice = UpdateNotice::default();
+    if let Some(url) = url {
+      remote::spawn(url.to_string(), notice.clone());
+    }
+    notice
+  }
+
+  pub fn latest(&self) -> Option<String> {
+    self.0.lock().ok().and_then(|latest| latest.clone())
+  }
+
+  /// Records `latest` if it is newer than this build. The server answers with the bare version, e.g. `0.2.0` or
+  /// `v0.2.0`.
+  #[cfg_attr(not(feature = "update_check"), allow(dead_code))]
+  fn offer(&self, latest: &str) {
+    let latest = latest.trim().trim_start_matches('v');
+    if parse(latest) > parse(env!("CARGO_PKG_VERSION")) {
+      if let Ok(mut notice) = self.0.lock() {
+        *notice = Some(latest.to_string());
+      }
+    }
+  }
+}
+
+///
+/// functions
+///
+
+/// The numeric parts of a version, anything after the first part that isn't a number is ignored.
+#[cfg_attr(not(feature = "update_check"), allow(dead_code))]
+fn parse(version: &str) -> Vec<u32> {
+  version
+    .split('.')
+    .map(|part| part.parse().ok())
+    .take_while(Option::is_some)
+    .flatten()
+    .collect()
+}
+
+#[cfg(feature = "update_check")]
+mod remote {
+  use super::UpdateNotice;
+  use log::{info, warn};
+  use std::thread;
+  use std::time::Duration;
+
+  pub fn spawn(url: String, notice: UpdateNotice) {
+    let spawned = thread::Builder::new().name("update_check".to_string()).spawn(move || {
+      let latest = ureq::get(&url)
+        .timeout(Duration::from_secs(10))
+        .call()
+        .map_err(|e| e.to_string())
+        .and_then(|response| response.into_string().map_err(|e| e.to_string()));
+      match latest {
+        Ok(latest) => {
+          info!("Latest release is {}", latest.trim());
+          notice.offer(&latest);
+        }
+        Err(e) => warn!("Couldn't check {} for updates: {}", url, e),
+      }
+    });
+    if let Err(e) = spawned {
+      warn!("Couldn't start the update check: {}", e);
+    }
+  }
+}
+
+#[cfg(not(feature = "update_check"))]
+mod remote {
+  use super::UpdateNotice;
+  use log::warn;
+
+  pub fn spawn(url: String, _notice: UpdateNotice) {
+    warn!(
+      "Can't check {} for updates, the game was built without the update_check feature",
+      url
+    );
+  }
+}